@@ -0,0 +1,166 @@
+//! Benchmarks of every phase of the triangulation pipeline, on random
+//! polygons from 10 to 1,000,000 vertices.\
+//! Space partitioning polygons are the typical input, random star-shaped
+//! ones are the worst case, they keep O(n) edges in the sweep's tree.
+//!
+//! ```shell
+//! cargo bench --bench pipeline
+//! cargo bench --bench pipeline -- monotone_partition/star/10000
+//! ```
+//!
+//! Set `BENCH_MAX_VERTICES` to skip the largest polygons for a quick run.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use egui::Pos2;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use triangulate_rs::{
+    monotone_partition, partition_into_monotone, random_polygon, to_event_queue, traverse_faces,
+    triangulate_monotone_polygons, PartitionPolygon, RandomMethod, UNCOLORED,
+};
+
+const SIZES: [usize; 6] = [10, 100, 1_000, 10_000, 100_000, 1_000_000];
+
+const FAMILIES: [RandomMethod; 2] = [RandomMethod::SpacePartition, RandomMethod::StarShaped];
+
+fn sizes() -> impl Iterator<Item = usize> {
+    let max = std::env::var("BENCH_MAX_VERTICES")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(usize::MAX);
+    SIZES.into_iter().filter(move |n| *n <= max)
+}
+
+/// Random polygon with `n` vertices, generated once per family and size
+/// and shared by all benchmarks.
+fn polygon(method: RandomMethod, n: usize) -> &'static [Pos2] {
+    type Polygons = BTreeMap<(&'static str, usize), &'static [Pos2]>;
+    static POLYGONS: OnceLock<Mutex<Polygons>> = OnceLock::new();
+    let mut polygons = POLYGONS.get_or_init(Default::default).lock().unwrap();
+    polygons
+        .entry((method.name(), n))
+        .or_insert_with(|| random_polygon(method, n, 0).leak())
+}
+
+fn built(vertices: &[Pos2]) -> PartitionPolygon {
+    let mut poly = PartitionPolygon::new();
+    poly.build_from_pts(vertices);
+    poly
+}
+
+/// Polygon after triangulating its monotone polygons, with its triangles.
+fn triangulated(vertices: &[Pos2]) -> (PartitionPolygon, Vec<Vec<usize>>) {
+    let (mut poly, monotone_polygons) = partition_into_monotone(vertices, None);
+    triangulate_monotone_polygons(&mut poly, monotone_polygons, vertices, None);
+    let triangles = poly.make_faces(vertices);
+    (poly, triangles)
+}
+
+fn linked(vertices: &[Pos2]) -> PartitionPolygon {
+    let (mut poly, triangles) = triangulated(vertices);
+    poly.link_face(&triangles, vertices);
+    poly
+}
+
+/// Run `f` for every family and size in a group named `name`, large
+/// inputs take fewer samples so the suite finishes in reasonable time.
+fn bench_sizes(
+    c: &mut Criterion,
+    name: &str,
+    mut f: impl FnMut(&mut criterion::Bencher, &'static [Pos2]),
+) {
+    let mut group = c.benchmark_group(name);
+    for method in FAMILIES {
+        for n in sizes() {
+            group.sample_size(if n >= 100_000 { 10 } else { 50 });
+            group.throughput(Throughput::Elements(n as u64));
+            group.bench_function(BenchmarkId::new(method.name(), n), |b| {
+                f(b, polygon(method, n))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn event_queue(c: &mut Criterion) {
+    bench_sizes(c, "to_event_queue", |b, vertices| {
+        let poly = built(vertices);
+        b.iter(|| to_event_queue(&poly.vertices));
+    });
+}
+
+fn partition(c: &mut Criterion) {
+    bench_sizes(c, "monotone_partition", |b, vertices| {
+        b.iter_batched(
+            || built(vertices),
+            |mut poly| {
+                monotone_partition(&mut poly, None);
+                poly
+            },
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+fn make_polygons(c: &mut Criterion) {
+    bench_sizes(c, "make_polygons", |b, vertices| {
+        let (mut poly, _) = partition_into_monotone(vertices, None);
+        b.iter(|| poly.make_faces(vertices));
+    });
+}
+
+fn triangulate(c: &mut Criterion) {
+    bench_sizes(c, "triangulate_monotone", |b, vertices| {
+        b.iter_batched(
+            || partition_into_monotone(vertices, None),
+            |(mut poly, monotone_polygons)| {
+                triangulate_monotone_polygons(&mut poly, monotone_polygons, vertices, None);
+                poly
+            },
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+fn link_face(c: &mut Criterion) {
+    bench_sizes(c, "link_face", |b, vertices| {
+        b.iter_batched(
+            || triangulated(vertices),
+            |(mut poly, triangles)| {
+                poly.link_face(&triangles, vertices);
+                poly
+            },
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+fn coloring(c: &mut Criterion) {
+    bench_sizes(c, "coloring_dfs", |b, vertices| {
+        let poly = linked(vertices);
+        b.iter(|| {
+            let mut classes = vec![UNCOLORED; vertices.len()];
+            traverse_faces(&poly.faces[0], &mut classes);
+            classes
+        });
+    });
+}
+
+fn kd_tree(c: &mut Criterion) {
+    bench_sizes(c, "kd_tree_build", |b, vertices| {
+        let poly = linked(vertices);
+        b.iter(|| poly.face_kd_tree());
+    });
+}
+
+criterion_group!(
+    benches,
+    event_queue,
+    partition,
+    make_polygons,
+    triangulate,
+    link_face,
+    coloring,
+    kd_tree
+);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+//! Parsers must return an error on any input, never panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use triangulate_rs::{parse_polygons, polar, InputFormat, Project};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    for format in InputFormat::ALL {
+        let _ = parse_polygons(text, format);
+    }
+    let _ = Project::from_json(text);
+    let _ = polar(text, 16, 100.);
+});
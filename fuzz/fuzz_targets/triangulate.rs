@@ -0,0 +1,37 @@
+//! Every valid polygon must be triangulated and 3-colored correctly,
+//! invalid ones must be rejected, and nothing may panic.\
+//! Small integer coordinates make collinear vertices and events at the
+//! same height common. `cargo fuzz tmin triangulate <crash>` drops
+//! vertices of a failing polygon, `shrink_polygon` reduces it further.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use egui::pos2;
+use libfuzzer_sys::fuzz_target;
+use triangulate_rs::{
+    polygon_triangulation, three_coloring, try_polygon_triangulation, validate_polygon,
+    verify_coloring, verify_triangulation, vertex_types,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Polygon {
+    vertices: Vec<(i8, i8)>,
+}
+
+fuzz_target!(|polygon: Polygon| {
+    let pts: Vec<_> = polygon
+        .vertices
+        .iter()
+        .map(|(x, y)| pos2(*x as f32, *y as f32))
+        .collect();
+    if validate_polygon(&pts).is_err() {
+        assert!(try_polygon_triangulation(&pts, None).is_err());
+        return;
+    }
+    let _ = vertex_types(&pts);
+    let poly = polygon_triangulation(&pts, None);
+    let report = verify_triangulation(&pts, &poly);
+    assert!(report.is_valid(), "{:?}: {}", pts, report);
+    let classes = three_coloring(&poly, 0, &[]).expect("triangulation can be colored");
+    assert_eq!(verify_coloring(&poly, &classes), Ok(()));
+});
@@ -1,83 +1,387 @@
 use eframe::egui::*;
 use egui_extras::install_image_loaders;
 use kd_tree::{KdMap, KdTree2};
-use log::debug;
-use std::iter::zip;
+use log::{debug, error, info};
 
 // use crate::monotone_y_partition::monoton_polygon_partition;
-use crate::monotone_triangulation::polygon_triangulation;
-use crate::monotone_y_partition::PartitionPolygon;
+use crate::camera::Camera;
+use crate::export::{export, ExportFormat, ExportStyle, TriangulationData};
+use crate::history::{Command, History};
+use crate::holes::{
+    bridge_holes, try_polygon_with_holes_triangulation, validate_polygon_with_holes, BridgedPolygon,
+};
+use crate::import::{parse_polygons, InputFormat, PolygonWithHoles};
+use crate::monotone_triangulation::partition_into_monotone;
+use crate::monotone_y_partition::{vertex_types, PartitionPolygon, VertexType};
+use crate::polygon_validation::PolygonError;
+use crate::presets::{find_preset, presets, Preset, PresetCategory, DEFAULT_PRESET};
+use crate::project::{Algorithm, Annotation, Project, ViewSettings, PROJECT_EXTENSION};
+use crate::random_polygon::{RandomMethod, RandomSettings};
+use crate::shapes::ParametricShape;
+use crate::snapping::{snap, Snap, SnapGuide, SnapSettings};
+use crate::sweep_trace::{AlgorithmTrace, TracePhase};
 use crate::transform_pos::TransformPos;
-use crate::vertex_coloring::dfs;
-
-// #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-// #[cfg_attr(feature = "serde", serde(default))]
+use crate::triangle_base::signed_area;
+use crate::triangulation_validation::{
+    verify_triangulation, TriangulationReport, TriangulationViolation,
+};
+use crate::vertex_coloring::{art_gallery_guards, three_coloring, verify_coloring, COLOR_CLASSES};
 
 type Points = Vec<Pos2>;
 
-#[allow(dead_code)]
-fn example_poly() -> Points {
-    vec![
-        Pos2::new(157., 29.),  // 0
-        Pos2::new(308., 173.), // 1
-        Pos2::new(481., 49.),  // 2
-        Pos2::new(624., 180.), // 3
-        Pos2::new(500., 349.), // 4
-        Pos2::new(378., 286.), // 5
-        Pos2::new(185., 333.), // 6
-    ]
+/// All vertex types, in the order of the legend.
+const VERTEX_TYPES: [VertexType; 5] = [
+    VertexType::StartVertex,
+    VertexType::EndVertex,
+    VertexType::RegularVetex,
+    VertexType::SplitVertex,
+    VertexType::MergeVertex,
+];
+
+fn vertex_type_name(vertex_type: &VertexType) -> &'static str {
+    match vertex_type {
+        VertexType::StartVertex => "start",
+        VertexType::EndVertex => "end",
+        VertexType::RegularVetex => "regular",
+        VertexType::SplitVertex => "split",
+        VertexType::MergeVertex => "merge",
+    }
+}
+
+/// Textbook glyph of a vertex type: start vertex is an open square,
+/// end vertex is a filled square, regular vertex is a disc,
+/// split vertex and merge vertex are triangles pointing up and down.
+fn vertex_type_glyph(vertex_type: &VertexType, center: Pos2, radius: f32, color: Color32) -> Shape {
+    let square = Rect::from_center_size(center, Vec2::splat(radius * 2.));
+    // Screen's y axis points downward.
+    let up = vec![
+        pos2(center.x, center.y - radius * 1.3),
+        pos2(center.x + radius * 1.2, center.y + radius * 0.8),
+        pos2(center.x - radius * 1.2, center.y + radius * 0.8),
+    ];
+    let down = vec![
+        pos2(center.x, center.y + radius * 1.3),
+        pos2(center.x - radius * 1.2, center.y - radius * 0.8),
+        pos2(center.x + radius * 1.2, center.y - radius * 0.8),
+    ];
+    match vertex_type {
+        VertexType::StartVertex => {
+            Shape::rect_stroke(square, Rounding::ZERO, Stroke::new(2., color))
+        }
+        VertexType::EndVertex => Shape::rect_filled(square, Rounding::ZERO, color),
+        VertexType::RegularVetex => Shape::circle_filled(center, radius, color),
+        VertexType::SplitVertex => Shape::convex_polygon(up, color, Stroke::NONE),
+        VertexType::MergeVertex => Shape::convex_polygon(down, color, Stroke::NONE),
+    }
+}
+
+/// Clickable thumbnail of a preset with its name below.
+fn preset_thumbnail(ui: &mut Ui, preset: &Preset) -> Response {
+    let size = vec2(96., 72.);
+    let (rect, response) = ui.allocate_exact_size(size + vec2(0., 16.), Sense::click());
+    let thumbnail = Rect::from_min_size(rect.min, size);
+    let visuals = ui.style().interact(&response);
+    let painter = ui.painter();
+    painter.rect(
+        thumbnail,
+        Rounding::same(4.),
+        visuals.bg_fill,
+        visuals.bg_stroke,
+    );
+
+    let mut camera = Camera::default();
+    camera.fit(thumbnail, &preset.polygon.exterior, 6.);
+    let to_screen = camera.model_to_screen(thumbnail);
+    let rings = std::iter::once(&preset.polygon.exterior).chain(&preset.polygon.holes);
+    for ring in rings.filter(|ring| !ring.is_empty()) {
+        let mut points: Points = ring.iter().map(|x| to_screen * *x).collect();
+        points.push(points[0]);
+        painter.add(Shape::line(
+            points,
+            Stroke::new(1., visuals.fg_stroke.color),
+        ));
+    }
+    painter.text(
+        pos2(rect.center().x, rect.max.y),
+        Align2::CENTER_BOTTOM,
+        preset.name,
+        FontId::proportional(11.),
+        visuals.text_color(),
+    );
+    response.on_hover_text(preset.description)
+}
+
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+const SAVE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+
+/// Storage key of the palette in eframe's persistent storage,
+/// used by saves made before the whole app state was stored.
+const PALETTE_KEY: &str = "palette";
+/// Storage key of SavedState in eframe's persistent storage.
+const STATE_KEY: &str = "state";
+/// Version of SavedState written by this build.\
+/// Adding a field with a sensible default doesn't need a new version,
+/// changing the meaning of a field does, along with a migration in `restore`.
+const STATE_VERSION: u32 = 1;
+
+/// Colors of every element drawn in the painting area.
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+struct ElementColors {
+    /// Vertices which aren't 3-colored yet.
+    vertex: Color32,
+    /// Vertex index labels.
+    label: Color32,
+    /// Polygon outline.
+    edge: Color32,
+    /// Diagonals inserted by triangulation.
+    diagonal: Color32,
+    /// Markers on triangles' centroids.
+    centroid: Color32,
+    /// The selected start triangle.
+    selection: Color32,
+    /// Colors of the 3-coloring's color classes.
+    color_classes: [Color32; COLOR_CLASSES as usize],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum PaletteName {
+    Classic,
+    /// Okabe & Ito's color-blind safe palette.
+    OkabeIto,
+    /// Paul Tol's "bright" color-blind safe palette.
+    TolBright,
+    /// Palette edited by user.
+    Custom,
+}
+
+impl PaletteName {
+    const PRESETS: [PaletteName; 3] = [
+        PaletteName::Classic,
+        PaletteName::OkabeIto,
+        PaletteName::TolBright,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            PaletteName::Classic => "Classic",
+            PaletteName::OkabeIto => "Okabe-Ito (color-blind safe)",
+            PaletteName::TolBright => "Tol bright (color-blind safe)",
+            PaletteName::Custom => "Custom",
+        }
+    }
+
+    /// Element colors of a preset palette for light or dark visuals.\
+    /// Return None for custom palette.
+    fn colors(&self, dark_mode: bool) -> Option<ElementColors> {
+        let rgb = Color32::from_rgb;
+        let colors = match (self, dark_mode) {
+            (PaletteName::Classic, false) => ElementColors {
+                vertex: Color32::BLACK,
+                label: Color32::RED,
+                edge: rgb(25, 200, 100),
+                diagonal: rgb(25, 200, 100),
+                centroid: Color32::BLACK,
+                selection: Color32::BLACK,
+                color_classes: [Color32::RED, Color32::GREEN, Color32::BLUE],
+            },
+            (PaletteName::Classic, true) => ElementColors {
+                vertex: Color32::LIGHT_GRAY,
+                label: rgb(255, 110, 110),
+                edge: rgb(25, 200, 100),
+                diagonal: rgb(25, 200, 100),
+                centroid: Color32::LIGHT_GRAY,
+                selection: Color32::WHITE,
+                color_classes: [Color32::RED, Color32::GREEN, rgb(80, 130, 255)],
+            },
+            (PaletteName::OkabeIto, false) => ElementColors {
+                vertex: Color32::BLACK,
+                label: rgb(213, 94, 0),
+                edge: rgb(0, 114, 178),
+                diagonal: rgb(204, 121, 167),
+                centroid: Color32::BLACK,
+                selection: Color32::BLACK,
+                color_classes: [rgb(230, 159, 0), rgb(86, 180, 233), rgb(0, 158, 115)],
+            },
+            (PaletteName::OkabeIto, true) => ElementColors {
+                vertex: Color32::LIGHT_GRAY,
+                label: rgb(240, 228, 66),
+                edge: rgb(86, 180, 233),
+                diagonal: rgb(204, 121, 167),
+                centroid: Color32::LIGHT_GRAY,
+                selection: Color32::WHITE,
+                color_classes: [rgb(230, 159, 0), rgb(240, 228, 66), rgb(0, 158, 115)],
+            },
+            (PaletteName::TolBright, false) => ElementColors {
+                vertex: Color32::BLACK,
+                label: rgb(170, 51, 119),
+                edge: rgb(68, 119, 170),
+                diagonal: rgb(102, 204, 238),
+                centroid: rgb(100, 100, 100),
+                selection: Color32::BLACK,
+                color_classes: [rgb(238, 102, 119), rgb(34, 136, 51), rgb(204, 187, 68)],
+            },
+            (PaletteName::TolBright, true) => ElementColors {
+                vertex: Color32::LIGHT_GRAY,
+                label: rgb(204, 187, 68),
+                edge: rgb(102, 204, 238),
+                diagonal: rgb(187, 187, 187),
+                centroid: Color32::LIGHT_GRAY,
+                selection: Color32::WHITE,
+                color_classes: [rgb(238, 102, 119), rgb(34, 136, 51), rgb(68, 119, 170)],
+            },
+            (PaletteName::Custom, _) => return None,
+        };
+        Some(colors)
+    }
+}
+
+/// A named palette, which has a set of element colors for light visuals
+/// and another set for dark visuals.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct Palette {
+    name: PaletteName,
+    light: ElementColors,
+    dark: ElementColors,
 }
 
-fn example_poly2() -> Points {
-    vec![
-        Pos2::new(218., 60.),  //0
-        Pos2::new(251., 197.), //1
-        Pos2::new(362., 97.),  //2
-        Pos2::new(460., 127.), //3
-        Pos2::new(527., 250.), //4
-        Pos2::new(628., 111.), //5
-        Pos2::new(688., 38.),  //6
-        Pos2::new(739., 257.), //7
-        Pos2::new(646., 395.), //8
-        Pos2::new(530., 406.), //9
-        Pos2::new(380., 365.), //10
-        Pos2::new(257., 413.), //11
-    ]
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::from_name(PaletteName::Classic)
+    }
 }
 
-fn generate_point_colors(len: usize) -> Vec<Color32> {
-    vec![Color32::BLACK; len]
+impl Palette {
+    /// Build a palette from one of the preset palettes.
+    fn from_name(name: PaletteName) -> Self {
+        Palette {
+            name,
+            light: name.colors(false).expect("not a preset palette"),
+            dark: name.colors(true).expect("not a preset palette"),
+        }
+    }
+
+    /// Element colors adapted to egui's dark/light visuals.
+    fn colors(&self, dark_mode: bool) -> &ElementColors {
+        if dark_mode {
+            &self.dark
+        } else {
+            &self.light
+        }
+    }
+
+    fn colors_mut(&mut self, dark_mode: bool) -> &mut ElementColors {
+        if dark_mode {
+            &mut self.dark
+        } else {
+            &mut self.light
+        }
+    }
 }
+
 pub struct Painting {
     /// in 0-1 normalized coordinates
     points: Points,
-    point_colors: Vec<egui::Color32>,
+    color_classes: Vec<u8>,
     polygon_partition: Vec<Points>,
-    stroke: Stroke,
+    stroke_width: f32,
     radius: f32,
+    palette: Palette,
     kdtree: KdMap<[f32; 2], usize>,
     focused_point: Option<(Pos2, usize)>,
     _painting_rect: Rect,
     dcel: PartitionPolygon,
+    // Ring that was last triangulated, the holes bridged to `points`,
+    // vertex indices of `dcel`, `trace` and `color_classes` refer to it.
+    bridged: BridgedPolygon,
+    // Holes of the polygon are triangulated with it, other polygons
+    // of a loaded file are only shown.
+    holes: Vec<Points>,
+    other_polygons: Vec<PolygonWithHoles>,
+    // Notes pinned in model space
+    annotations: Vec<Annotation>,
+    // Project file that Save writes to
+    project_path: Option<std::path::PathBuf>,
+
+    // Undo/redo history of edits
+    history: History,
+    // Index and original position of the vertex being dragged
+    dragging: Option<(usize, Pos2)>,
+    // Maps model space to painting area
+    camera: Camera,
+    panning: bool,
+    snap_settings: SnapSettings,
+    // Parameters of the next random polygon
+    random_settings: RandomSettings,
+    // Parametric shape edited in the shapes panel
+    shape: ParametricShape,
+    // Last history entry is the Load of the shape being edited,
+    // so that dragging a slider doesn't flood history.
+    shape_loaded: bool,
+    shape_error: Option<String>,
+
+    // Step-by-step replay of triangulation
+    trace: AlgorithmTrace,
+    replay_step: Option<usize>,
+    replay_playing: bool,
+    replay_speed: f32, // steps per second
+    replay_elapsed: f32,
 
     // Application mode flag
     triangulated: bool,
-    coloring: bool,
+    // Re-run triangulation and coloring on every edit
+    live_mode: bool,
+    // Stop after the monotone partition, faces are the y-monotone pieces.
+    partition_only: bool,
+    // Why current points can't be triangulated, the last valid result is shown instead.
+    triangulation_error: Option<PolygonError>,
+    // Invariant check of the current triangulation, computed when the
+    // diagnostics panel is open since it takes O(n^2) time.
+    diagnostics: Option<TriangulationReport>,
+    // Result of the last file operation worth telling the user
+    file_message: Option<String>,
+    // Why the last click didn't add a vertex
+    click_message: Option<String>,
+    // Start face of 3-coloring, None if vertices aren't colored.
+    coloring_start: Option<usize>,
+
+    // Layer flags
+    show_vertex_types: bool,
+    show_guards: bool,
 
     // "about" page  window flag
     show_immediate_about_page: bool,
+    // palette window flag
+    show_palette_window: bool,
+    // history panel flag
+    show_history_panel: bool,
+    // snapping window flag
+    show_snapping_window: bool,
+    // annotations window flag
+    show_annotations_window: bool,
+    // preset gallery window flag
+    show_gallery_window: bool,
+    // parametric shapes panel flag
+    show_shapes_panel: bool,
+    // triangulation diagnostics panel flag
+    show_diagnostics_panel: bool,
 }
 
 impl Default for Painting {
     fn default() -> Self {
-        let points = example_poly2();
+        let points = find_preset(DEFAULT_PRESET)
+            .expect("default preset exists")
+            .polygon
+            .exterior;
         Self {
             // points: Default::default(),
             points,
-            point_colors: Vec::new(),
+            color_classes: Vec::new(),
             polygon_partition: Vec::new(),
-            stroke: Stroke::new(2.0, Color32::from_rgb(25, 200, 100)),
+            stroke_width: 2.0,
             radius: 5.,
+            palette: Palette::default(),
             kdtree: KdTree2::default(),
             focused_point: None,
             _painting_rect: Rect {
@@ -85,83 +389,879 @@ impl Default for Painting {
                 max: Pos2::ZERO,
             },
             dcel: PartitionPolygon::new(),
+            bridged: BridgedPolygon::default(),
+            holes: Vec::new(),
+            other_polygons: Vec::new(),
+            annotations: Vec::new(),
+            project_path: None,
+
+            history: History::default(),
+            dragging: None,
+            camera: Camera::default(),
+            panning: false,
+            snap_settings: SnapSettings::default(),
+            random_settings: RandomSettings::default(),
+            shape: ParametricShape::default(),
+            shape_loaded: false,
+            shape_error: None,
+
+            trace: AlgorithmTrace::default(),
+            replay_step: None,
+            replay_playing: false,
+            replay_speed: 2.,
+            replay_elapsed: 0.,
 
             triangulated: false,
-            coloring: false,
+            live_mode: false,
+            partition_only: false,
+            triangulation_error: None,
+            diagnostics: None,
+            file_message: None,
+            click_message: None,
+            coloring_start: None,
+
+            show_vertex_types: false,
+            show_guards: false,
 
             show_immediate_about_page: false,
+            show_palette_window: false,
+            show_history_panel: false,
+            show_snapping_window: false,
+            show_annotations_window: false,
+            show_gallery_window: false,
+            show_shapes_panel: false,
+            show_diagnostics_panel: false,
         }
     }
 }
 
+/// Part of the painting that survives restarts.\
+/// Missing fields take their default value, so older saves still load.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct SavedState {
+    /// 0 if the save has no version, i.e. it isn't a SavedState.
+    version: u32,
+    points: Points,
+    stroke_width: f32,
+    radius: f32,
+    palette: Palette,
+    camera: Camera,
+    snap_settings: SnapSettings,
+    show_vertex_types: bool,
+    show_guards: bool,
+    holes: Vec<Points>,
+    other_polygons: Vec<PolygonWithHoles>,
+    annotations: Vec<Annotation>,
+    triangulated: bool,
+    live_mode: bool,
+    partition_only: bool,
+    coloring_start: Option<usize>,
+    selected_face: Option<usize>,
+}
+
+impl Default for SavedState {
+    fn default() -> Self {
+        let mut state = Painting::default().saved_state();
+        state.version = 0;
+        state
+    }
+}
+
 impl Painting {
     /// Called once before the first frame.
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        // if let Some(storage) = cc.storage {
-        //     return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        // }
+        let mut painting: Painting = Default::default();
+        if let Some(storage) = cc.storage {
+            painting.restore(
+                eframe::get_value(storage, STATE_KEY),
+                eframe::get_value(storage, PALETTE_KEY),
+            );
+        }
+        painting
+    }
 
-        Default::default()
+    fn saved_state(&self) -> SavedState {
+        SavedState {
+            version: STATE_VERSION,
+            points: self.points.clone(),
+            stroke_width: self.stroke_width,
+            radius: self.radius,
+            palette: self.palette.clone(),
+            camera: self.camera,
+            snap_settings: self.snap_settings,
+            show_vertex_types: self.show_vertex_types,
+            show_guards: self.show_guards,
+            holes: self.holes.clone(),
+            other_polygons: self.other_polygons.clone(),
+            annotations: self.annotations.clone(),
+            triangulated: self.triangulated,
+            live_mode: self.live_mode,
+            partition_only: self.partition_only,
+            coloring_start: self.coloring_start,
+            selected_face: self.selected_face(),
+        }
+    }
+
+    /// Restore a saved state, triangulation and coloring are recomputed
+    /// from the points.\
+    /// `palette` is the only thing stored by saves without a SavedState.
+    /// A save from a newer version is ignored, since its fields may
+    /// mean something else.
+    fn restore(&mut self, state: Option<SavedState>, palette: Option<Palette>) {
+        let state = match state {
+            Some(state) if state.version > STATE_VERSION => {
+                error!(
+                    "ignore saved state of version {}, this build reads up to {}",
+                    state.version, STATE_VERSION
+                );
+                return;
+            }
+            Some(state) => state,
+            None => {
+                if let Some(palette) = palette {
+                    self.palette = palette;
+                }
+                return;
+            }
+        };
+        info!("restore saved state of version {}", state.version);
+
+        if state.points.iter().all(|x| x.is_finite()) {
+            self.points = state.points;
+        }
+        self.set_view(state.camera, state.stroke_width, state.radius);
+        self.palette = state.palette;
+        self.snap_settings = state.snap_settings;
+        self.show_vertex_types = state.show_vertex_types;
+        self.show_guards = state.show_guards;
+        self.holes = state.holes;
+        self.other_polygons = state.other_polygons;
+        self.annotations = state.annotations;
+        self.triangulated = state.triangulated;
+        self.live_mode = state.live_mode;
+        self.partition_only = state.partition_only;
+        self.coloring_start = state.coloring_start;
+        self.rebuild();
+        self.select_face(state.selected_face);
+    }
+
+    /// Take camera and sizes from a file, values that would break drawing
+    /// (not finite, not positive, zoom out of range) keep the current ones.
+    fn set_view(&mut self, camera: Camera, stroke_width: f32, radius: f32) {
+        let valid_size = |x: f32| x.is_finite() && x > 0.;
+        if valid_size(stroke_width) {
+            self.stroke_width = stroke_width;
+        }
+        if valid_size(radius) {
+            self.radius = radius;
+        }
+        if camera.center.is_finite() && camera.zoom.is_finite() {
+            self.camera = Camera {
+                zoom: camera.zoom.clamp(Camera::MIN_ZOOM, Camera::MAX_ZOOM),
+                ..camera
+            };
+        }
+    }
+
+    /// Current element colors, adapted to the painter's dark/light visuals.
+    fn colors(&self, p: &Painter) -> ElementColors {
+        *self.palette.colors(p.ctx().style().visuals.dark_mode)
     }
 
     /// Transpose coordinates from gui's coordinate system to conventional coordinate system.
     /// Gui's coordinate system has its origin in the top left corner, while
-    /// conventional coordiante system's origin rests in the lower left corner,
-    /// which is more intuitive and easier to handle.
+    /// conventional coordiante system's y axis points up,
+    /// which is more intuitive and easier to handle.\
+    /// The mapping is defined by the camera, so model coordinates
+    /// don't change with window size.
     fn transpose_from_screen(&self) -> TransformPos {
-        TransformPos::new(vec2(0., self._painting_rect.size().y), vec2(1., -1.))
+        self.camera.screen_to_model(self._painting_rect)
     }
 
     /// Transpose coordinates from conventional coordinate system to gui's coordinate system.
     fn transpose_to_screen(&self) -> TransformPos {
-        let from_screen = TransformPos::new(vec2(0., self._painting_rect.size().y), vec2(1., -1.));
-        from_screen.inverse()
+        self.camera.model_to_screen(self._painting_rect)
     }
 
-    fn build_kd_tree(&mut self) {
-        let face_iter = Vec::from_iter(self.dcel.faces.iter().map(|face| {
-            let centrod = face.as_ref().borrow().centroid;
-            [centrod.x, centrod.y]
-        }));
-        let face_index_iter = Vec::from_iter(0..self.dcel.faces.len());
+    /// Snap a position in conventional coordinate system,
+    /// see `snapping::snap` for `anchor` and `exclude`.
+    fn snap_pos(&self, pos: Pos2, anchor: Option<usize>, exclude: Option<usize>) -> Snap {
+        let tolerance = self.snap_settings.tolerance / self.camera.zoom;
+        let targets = self
+            .holes
+            .iter()
+            .chain(
+                self.other_polygons
+                    .iter()
+                    .flat_map(|polygon| std::iter::once(&polygon.exterior).chain(&polygon.holes)),
+            )
+            .flatten()
+            .copied()
+            .collect::<Vec<Pos2>>();
+        snap(
+            pos,
+            &self.points,
+            &targets,
+            anchor,
+            exclude,
+            &self.snap_settings,
+            tolerance,
+        )
+    }
+
+    /// Triangulate polygon defined by current points and holes, or only
+    /// partition it into monotone pieces if `partition_only` is set.\
+    /// If current points don't form a valid polygon, the last valid
+    /// triangulation is kept and the problem goes to `triangulation_error`.
+    fn triangulate(&mut self) {
+        self.replay_step = None;
+        self.replay_playing = false;
+        let mut trace = AlgorithmTrace::default();
+        let result = if self.partition_only {
+            validate_polygon_with_holes(&self.points, &self.holes).map(|()| {
+                let bridged = bridge_holes(&self.points, &self.holes);
+                let (mut dcel, _) = partition_into_monotone(&bridged.vertices, Some(&mut trace));
+                dcel.partition(&bridged.vertices);
+                (bridged, dcel)
+            })
+        } else {
+            try_polygon_with_holes_triangulation(&self.points, &self.holes, Some(&mut trace))
+        };
+        match result {
+            Ok((bridged, dcel)) => {
+                self.bridged = bridged;
+                self.dcel = dcel;
+                self.trace = trace;
+                self.polygon_partition = self.dcel.face_coordinates();
+                self.triangulation_error = None;
+                self.diagnostics = None;
+                self.triangulated = true;
+            }
+            Err(err) => {
+                debug!("keep last valid triangulation: {}", err);
+                self.triangulation_error = Some(err);
+                // Indices of the report may be out of bounds of the edited points.
+                self.diagnostics = None;
+                // Without a previous result, clicks must keep adding vertices.
+                self.triangulated = !self.dcel.faces.is_empty();
+            }
+        }
+    }
 
-        self.kdtree =
-            KdMap::build_by_ordered_float(Vec::from_iter(zip(face_iter, face_index_iter)));
+    /// Rebuild triangulation and coloring after the polygon is edited,
+    /// selected face and coloring start face are kept if they still exist.\
+    /// In live mode vertices are always colored.
+    fn rebuild(&mut self) {
+        if !self.triangulated && !self.live_mode {
+            return;
+        }
+        let selected_face = self.selected_face();
+        let coloring_start = self.coloring_start;
+        self.triangulate();
+        if self.triangulation_error.is_some() {
+            // Last valid result is kept as it is.
+            return;
+        }
+        self.select_face(selected_face);
+        let face_count = self.dcel.faces.len();
+        match coloring_start {
+            // Monotone pieces aren't triangles, so they can't be 3-colored.
+            _ if self.partition_only => self.clear_coloring(),
+            Some(start_face) if start_face < face_count => self.color_from(start_face),
+            Some(_) if face_count > 0 => self.color_from(0),
+            None if self.live_mode && face_count > 0 => {
+                self.color_from(selected_face.filter(|x| *x < face_count).unwrap_or(0))
+            }
+            _ => self.clear_coloring(),
+        }
+    }
+
+    /// Drop triangulation result, along with coloring and selected face.
+    fn reset_triangulation(&mut self) {
+        self.polygon_partition.clear();
+        self.focused_point = None;
+        self.dcel = PartitionPolygon::new();
+        self.bridged = BridgedPolygon::default();
+        self.trace.clear();
+        self.replay_step = None;
+        self.replay_playing = false;
+        self.triangulated = false;
+        self.triangulation_error = None;
+        self.diagnostics = None;
+        self.clear_coloring();
+    }
+
+    /// 3-coloring vertices starting from the given face.
+    fn color_from(&mut self, start_face: usize) {
+        match three_coloring(&self.dcel, start_face, &[]) {
+            Ok(classes) => {
+                self.color_classes = classes;
+                self.coloring_start = Some(start_face);
+            }
+            Err(err) => {
+                error!("3-coloring failed: {}", err);
+                self.clear_coloring();
+            }
+        }
+    }
+
+    fn clear_coloring(&mut self) {
+        self.color_classes.clear();
+        self.coloring_start = None;
+    }
+
+    fn selected_face(&self) -> Option<usize> {
+        self.focused_point.map(|point| point.1)
+    }
+
+    fn select_face(&mut self, face_idx: Option<usize>) {
+        self.focused_point = face_idx
+            .filter(|idx| *idx < self.dcel.faces.len())
+            .map(|idx| (self.dcel.faces[idx].as_ref().borrow().centroid, idx));
+    }
+
+    /// Apply a command to the painting.
+    fn apply(&mut self, command: &Command) {
+        match command {
+            Command::AddPoint { idx, pos } => {
+                self.points.insert(*idx, *pos);
+                self.rebuild();
+            }
+            Command::MovePoint { idx, to, .. } => {
+                self.points[*idx] = *to;
+                self.rebuild();
+            }
+            Command::DeletePoint { idx, .. } => {
+                self.points.remove(*idx);
+                self.rebuild();
+            }
+            Command::Clear { .. } => {
+                self.points.clear();
+                self.holes.clear();
+                self.other_polygons.clear();
+                self.reset_triangulation();
+            }
+            Command::Load {
+                points,
+                holes,
+                other_polygons,
+                cleared,
+            } => {
+                self.apply(cleared);
+                self.points.clone_from(points);
+                self.holes.clone_from(holes);
+                self.other_polygons.clone_from(other_polygons);
+                self.rebuild();
+            }
+            Command::Triangulate => self.triangulate(),
+            Command::Coloring { to, .. } => self.color_from(*to),
+            Command::SelectFace { to, .. } => self.select_face(*to),
+        }
+    }
+
+    /// Revert a command previously applied to the painting.
+    fn revert(&mut self, command: &Command) {
+        match command {
+            Command::AddPoint { idx, .. } => {
+                self.points.remove(*idx);
+                self.rebuild();
+            }
+            Command::MovePoint { idx, from, .. } => {
+                self.points[*idx] = *from;
+                self.rebuild();
+            }
+            Command::DeletePoint { idx, pos } => {
+                self.points.insert(*idx, *pos);
+                self.rebuild();
+            }
+            Command::Clear {
+                points,
+                holes,
+                other_polygons,
+                triangulated,
+                coloring_start,
+                selected_face,
+            } => {
+                self.points.clone_from(points);
+                self.holes.clone_from(holes);
+                self.other_polygons.clone_from(other_polygons);
+                if *triangulated {
+                    self.triangulate();
+                    self.select_face(*selected_face);
+                    if let Some(start_face) = coloring_start {
+                        self.color_from(*start_face);
+                    }
+                }
+            }
+            Command::Load { cleared, .. } => {
+                self.points.clear();
+                self.holes.clear();
+                self.other_polygons.clear();
+                self.reset_triangulation();
+                self.revert(cleared);
+            }
+            Command::Triangulate => self.reset_triangulation(),
+            Command::Coloring { from, .. } => match from {
+                Some(start_face) => self.color_from(*start_face),
+                None => self.clear_coloring(),
+            },
+            Command::SelectFace { from, .. } => self.select_face(*from),
+        }
+    }
+
+    /// Command that clears current painting, keeping it for undo.
+    fn clear_command(&self) -> Command {
+        Command::Clear {
+            points: self.points.clone(),
+            holes: self.holes.clone(),
+            other_polygons: self.other_polygons.clone(),
+            triangulated: self.triangulated,
+            coloring_start: self.coloring_start,
+            selected_face: self.selected_face(),
+        }
+    }
+
+    /// Replace painting with the first polygon and fit the view to it.\
+    /// Other polygons are shown but not triangulated, since triangulation
+    /// only handles a single polygon.
+    fn load_polygons(&mut self, mut polygons: Vec<PolygonWithHoles>) {
+        if polygons.is_empty() {
+            self.file_message = Some("No polygon found in file.".to_string());
+            return;
+        }
+        let first = polygons.remove(0);
+        self.file_message = if !polygons.is_empty() {
+            Some(format!(
+                "Triangulating the first polygon, {} other polygon(s) are only shown.",
+                polygons.len()
+            ))
+        } else {
+            None
+        };
+        self.execute(Command::Load {
+            points: first.exterior,
+            holes: first.holes,
+            other_polygons: polygons,
+            cleared: Box::new(self.clear_command()),
+        });
+        self.camera.fit(self._painting_rect, &self.points, 20.);
+    }
+
+    /// Replace painting with a random polygon and advance the seed,
+    /// the used seed is shown so the polygon can be reproduced.
+    fn load_random_polygon(&mut self) {
+        let settings = self.random_settings;
+        info!("load random polygon: {:?}", settings);
+        self.load_polygons(vec![settings.generate()]);
+        self.file_message = Some(format!(
+            "{} polygon with {} vertices, seed {}.",
+            settings.method.label(),
+            settings.vertices,
+            settings.seed
+        ));
+        self.random_settings.seed = settings.seed.wrapping_add(1);
+    }
+
+    /// Replace painting with the shape of the shapes panel.\
+    /// While the shape is being edited, its Load in history is amended
+    /// instead of pushing a new one on every slider step.
+    fn load_shape(&mut self) {
+        let points = match self.shape.generate() {
+            Ok(points) => points,
+            Err(err) => {
+                self.shape_error = Some(err.to_string());
+                return;
+            }
+        };
+        self.shape_error = None;
+        if self.shape_loaded {
+            if let Some(Command::Load { points: loaded, .. }) = self.history.last_mut() {
+                loaded.clone_from(&points);
+                self.points = points;
+                self.rebuild();
+                return;
+            }
+        }
+        info!("load shape: {:?}", self.shape);
+        self.load_polygons(vec![PolygonWithHoles::new(points, Vec::new())]);
+        self.shape_loaded = true;
+    }
+
+    /// Current state of work as a project.
+    fn to_project(&self) -> Project {
+        let mut polygons = vec![PolygonWithHoles {
+            exterior: self.points.clone(),
+            holes: self.holes.clone(),
+        }];
+        polygons.extend(self.other_polygons.iter().cloned());
+        let algorithm = match (self.triangulated, self.coloring_start) {
+            (false, _) => None,
+            (true, _) if self.partition_only => Some(Algorithm::Partition),
+            (true, None) => Some(Algorithm::Triangulate),
+            (true, Some(_)) if self.show_guards => Some(Algorithm::Guards),
+            (true, Some(_)) => Some(Algorithm::Color),
+        };
+        Project {
+            polygons,
+            algorithm,
+            live_mode: self.live_mode,
+            start_face: self.coloring_start,
+            selected_face: self.selected_face(),
+            color_classes: self.color_classes.clone(),
+            annotations: self.annotations.clone(),
+            view: ViewSettings {
+                camera: self.camera,
+                show_vertex_types: self.show_vertex_types,
+                show_guards: self.show_guards,
+                snap_settings: self.snap_settings,
+                stroke_width: self.stroke_width,
+                radius: self.radius,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Replace everything with a project, history starts over.\
+    /// Triangulation is recomputed, the stored coloring is kept
+    /// as long as it's still a valid 3-coloring.
+    fn load_project(&mut self, project: Project) {
+        let mut polygons = project.polygons.into_iter();
+        let first = polygons.next().unwrap_or_default();
+        self.reset_triangulation();
+        self.history = History::default();
+        self.points = first.exterior;
+        self.holes = first.holes;
+        self.other_polygons = polygons.collect();
+        self.annotations = project.annotations;
+        self.live_mode = project.live_mode;
+        self.partition_only = project.algorithm == Some(Algorithm::Partition);
+        self.set_view(
+            project.view.camera,
+            project.view.stroke_width,
+            project.view.radius,
+        );
+        self.show_vertex_types = project.view.show_vertex_types;
+        self.show_guards = project.view.show_guards || project.algorithm == Some(Algorithm::Guards);
+        self.snap_settings = project.view.snap_settings;
+
+        self.triangulated = project.algorithm.is_some();
+        self.coloring_start = match project.algorithm {
+            Some(Algorithm::Color | Algorithm::Guards) => project.start_face.or(Some(0)),
+            _ => None,
+        };
+        self.rebuild();
+        self.select_face(project.selected_face);
+        if !self.color_classes.is_empty()
+            && project.color_classes.len() == self.bridged.vertices.len()
+            && verify_coloring(&self.dcel, &project.color_classes).is_ok()
+        {
+            self.color_classes = project.color_classes;
+        }
+    }
+
+    /// Pick a project file with native file dialog and open it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_project(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Project", &[PROJECT_EXTENSION])
+            .pick_file()
+        else {
+            return;
+        };
+        info!("open project: {}", path.display());
+        let result = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Project::from_json(&text).map_err(|err| err.to_string()));
+        match result {
+            Ok(project) => {
+                self.load_project(project);
+                self.project_path = Some(path);
+                self.file_message = None;
+            }
+            Err(err) => {
+                error!("failed to open {}: {}", path.display(), err);
+                self.file_message = Some(format!("{}:{}", path.display(), err));
+            }
+        }
+    }
+
+    /// Save project to the file it was opened from or last saved to,
+    /// ask for a file if there is none or `save_as` is set.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_project(&mut self, save_as: bool) {
+        let path = match self.project_path.clone().filter(|_| !save_as) {
+            Some(path) => path,
+            None => {
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Project", &[PROJECT_EXTENSION])
+                    .set_file_name(format!("polygon.{}", PROJECT_EXTENSION))
+                    .save_file()
+                else {
+                    return;
+                };
+                path
+            }
+        };
+        info!("save project to {}", path.display());
+        self.file_message = match std::fs::write(&path, self.to_project().to_json()) {
+            Ok(()) => {
+                let message = format!("Saved to {}", path.display());
+                self.project_path = Some(path);
+                Some(message)
+            }
+            Err(err) => {
+                error!("failed to save {}: {}", path.display(), err);
+                Some(format!("{}: {}", path.display(), err))
+            }
+        };
+    }
+
+    /// Pick a polygon file with native file dialog and load it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_file(&mut self) {
+        let mut dialog = rfd::FileDialog::new();
+        for format in InputFormat::ALL {
+            dialog = dialog.add_filter(format.label(), format.extensions());
+        }
+        let Some(path) = dialog.pick_file() else {
+            return;
+        };
+        info!("open file: {}", path.display());
+        let Some(format) = InputFormat::from_path(&path) else {
+            self.file_message = Some(format!("Unknown file type: {}", path.display()));
+            return;
+        };
+        let result = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| parse_polygons(&text, format).map_err(|err| err.to_string()));
+        match result {
+            Ok(polygons) => self.load_polygons(polygons),
+            Err(err) => {
+                error!("failed to open {}: {}", path.display(), err);
+                self.file_message = Some(format!("{}:{}", path.display(), err));
+            }
+        }
+    }
+
+    /// Current triangulation result, only the outline is kept
+    /// if the polygon isn't (validly) triangulated.
+    fn triangulation_data(&self) -> TriangulationData {
+        if self.triangulated && self.triangulation_error.is_none() {
+            let mut data =
+                TriangulationData::new(&self.bridged.vertices, &self.dcel, &self.color_classes);
+            data.selected_face = self.selected_face();
+            data
+        } else {
+            TriangulationData::new(&self.points, &PartitionPolygon::new(), &[])
+        }
+    }
+
+    /// Figures are usually printed on white paper, so light colors are used.
+    fn export_style(&self) -> ExportStyle {
+        let colors = self.palette.colors(false);
+        ExportStyle {
+            vertex: colors.vertex,
+            label: colors.label,
+            edge: colors.edge,
+            diagonal: colors.diagonal,
+            selection: colors.selection,
+            color_classes: colors.color_classes,
+            stroke_width: self.stroke_width,
+            radius: self.radius,
+        }
+    }
+
+    /// Pick a file with native file dialog and export triangulation to it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_file(&mut self, format: ExportFormat) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(format!("triangulation.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        info!("export {} to {}", format.label(), path.display());
+        let text = export(&self.triangulation_data(), format, &self.export_style());
+        self.file_message = match std::fs::write(&path, text) {
+            Ok(()) => Some(format!("Exported to {}", path.display())),
+            Err(err) => {
+                error!("failed to export {}: {}", path.display(), err);
+                Some(format!("{}: {}", path.display(), err))
+            }
+        };
+    }
+
+    /// Apply a command and record it in history.
+    fn execute(&mut self, command: Command) {
+        debug!("execute: {}", command.description());
+        self.shape_loaded = false;
+        self.apply(&command);
+        self.history.push(command);
+    }
+
+    fn undo(&mut self) {
+        self.shape_loaded = false;
+        if let Some(command) = self.history.undo() {
+            debug!("undo: {}", command.description());
+            self.revert(&command);
+        }
+    }
+
+    fn redo(&mut self) {
+        self.shape_loaded = false;
+        if let Some(command) = self.history.redo() {
+            debug!("redo: {}", command.description());
+            self.apply(&command);
+        }
+    }
+
+    /// Find the vertex under the cursor given cursor's screen position.
+    fn vertex_at(&self, screen_pos: Pos2) -> Option<usize> {
+        let pick_radius = self.radius + 4.;
+        self.points
+            .iter()
+            .map(|point| (self.transpose_to_screen() * *point).distance(screen_pos))
+            .enumerate()
+            .filter(|(_, distance)| *distance <= pick_radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(idx, _)| idx)
+    }
+
+    /// Find the polygon edge under the cursor given cursor's screen position.\
+    /// Return the index of edge's origin and the point on the edge
+    /// closest to the cursor, in conventional coordinate system.
+    fn edge_at(&self, screen_pos: Pos2) -> Option<(usize, Pos2)> {
+        if self.points.len() < 2 {
+            return None;
+        }
+        let pick_radius = self.radius + 2.;
+        // The closing edge only exists when there are at least 3 points.
+        let edge_count = if self.points.len() > 2 {
+            self.points.len()
+        } else {
+            1
+        };
+        (0..edge_count)
+            .filter_map(|idx| {
+                let origin = self.transpose_to_screen() * self.points[idx];
+                let end = self.transpose_to_screen() * self.points[(idx + 1) % self.points.len()];
+                let edge = end - origin;
+                let t = ((screen_pos - origin).dot(edge) / edge.length_sq()).clamp(0., 1.);
+                let closest = origin + t * edge;
+                let distance = closest.distance(screen_pos);
+                (distance <= pick_radius).then_some((idx, closest, distance))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(idx, closest, _)| (idx, self.transpose_from_screen() * closest))
+    }
+
+    fn build_kd_tree(&mut self) {
+        self.kdtree = self.dcel.face_kd_tree();
     }
 
     /// Draw vertices spawned by Mouse click in the drawing area.
     fn draw_vertices(&mut self, p: &Painter) {
-        // Draw vertices
-        let mut idx: usize = 0;
-        let vertices = self.points.iter().map(|point| {
-            // Transpose vertex coordinate to gui's coordiante system.
-            let center = self.transpose_to_screen() * *point;
-            let ret = if self.point_colors.is_empty() {
-                egui::Shape::circle_filled(center, self.radius, Color32::BLACK)
-            } else {
-                egui::Shape::circle_filled(center, self.radius, self.point_colors[idx])
-            };
-            idx += 1;
-            ret
-        });
-        p.extend(vertices);
+        let colors = self.colors(p);
+        // Vertex types are computed on the fly, so they follow the polygon being drawn.
+        let mut types = Vec::new();
+        if self.show_vertex_types {
+            types = vertex_types(&self.points);
+            // Types of later vertices would be misplaced after a ring without types.
+            let typed = !types.is_empty();
+            for hole in self.holes.iter().filter(|_| typed) {
+                if hole.len() < 3 {
+                    break;
+                }
+                // Polygon is on the left of a hole walked in cw order.
+                let ccw = signed_area(hole) > 0.;
+                let mut hole = hole.clone();
+                if ccw {
+                    hole.reverse();
+                }
+                let mut hole_types = vertex_types(&hole);
+                if ccw {
+                    hole_types.reverse();
+                }
+                types.extend(hole_types);
+            }
+        }
+        // Vertices of the exterior and then the holes, numbered like `PolygonError`.
+        let vertices = self.points.iter().chain(self.holes.iter().flatten());
+        // Color classes belong to the last valid polygon if current one is invalid.
+        // Vertices of a bridge have two copies in the bridged ring, the first is shown.
+        let count = self.points.len() + self.holes.iter().map(Vec::len).sum::<usize>();
+        let mut classes = vec![None; count];
+        if self.triangulation_error.is_none() {
+            for (class, idx) in self.color_classes.iter().zip(&self.bridged.indices).rev() {
+                if let Some(x) = classes.get_mut(*idx) {
+                    *x = Some(*class as usize);
+                }
+            }
+        }
+        // Transpose vertex coordinates to gui's coordiante system.
+        let centers: Vec<Pos2> = vertices.map(|x| self.transpose_to_screen() * *x).collect();
+        p.extend(centers.iter().enumerate().map(|(idx, center)| {
+            let color = classes[idx].map_or(colors.vertex, |class| colors.color_classes[class]);
+            match types.get(idx) {
+                Some(vertex_type) => vertex_type_glyph(vertex_type, *center, self.radius, color),
+                None => egui::Shape::circle_filled(*center, self.radius, color),
+            }
+        }));
 
         // Add number to lower right corner of the vertex
-        for i in 0..self.points.len() {
+        for (i, pt) in centers.iter().enumerate() {
             let font_id = egui::FontId::new(15., FontFamily::Monospace);
-            let pt = self.transpose_to_screen() * self.points[i];
             let pos = pos2(pt.x + self.radius, pt.y + self.radius);
             let text = i.to_string();
-            p.text(pos, Align2::LEFT_TOP, text, font_id, Color32::RED);
+            p.text(pos, Align2::LEFT_TOP, text, font_id, colors.label);
         }
     }
 
-    fn draw_polygon(&self, pts: &Points, p: &Painter) {
+    /// Draw the legend of vertex type glyphs in the upper right corner.
+    fn draw_vertex_type_legend(&self, p: &Painter) {
+        if !self.show_vertex_types {
+            return;
+        }
+        let colors = self.colors(p);
+        let font_id = egui::FontId::new(14., FontFamily::Proportional);
+        let line_height = 20.;
+        let size = vec2(100., line_height * VERTEX_TYPES.len() as f32 + 10.);
+        let legend_rect = Rect::from_min_size(
+            pos2(
+                self._painting_rect.right() - size.x - 10.,
+                self._painting_rect.top() + 10.,
+            ),
+            size,
+        );
+        let visuals = &p.ctx().style().visuals;
+        p.rect(
+            legend_rect,
+            Rounding::same(4.),
+            visuals.extreme_bg_color,
+            visuals.widgets.noninteractive.bg_stroke,
+        );
+        for (i, vertex_type) in VERTEX_TYPES.iter().enumerate() {
+            let y = legend_rect.top() + 5. + line_height * (i as f32 + 0.5);
+            let center = pos2(legend_rect.left() + 15., y);
+            p.add(vertex_type_glyph(vertex_type, center, 5., colors.vertex));
+            p.text(
+                pos2(center.x + 15., y),
+                Align2::LEFT_CENTER,
+                vertex_type_name(vertex_type),
+                font_id.clone(),
+                visuals.text_color(),
+            );
+        }
+    }
+
+    fn draw_polygon(&self, pts: &Points, stroke: Stroke, p: &Painter) {
         let mut points = pts
             .iter()
             // Transpose vertex coordinate to gui's coordiante system.
@@ -171,7 +1271,7 @@ impl Painting {
         if self.points.len() > 2 {
             points.push(self.transpose_to_screen() * pts[0]);
         }
-        let polygon_outline = Shape::line(points, self.stroke);
+        let polygon_outline = Shape::line(points, stroke);
         p.add(polygon_outline);
     }
 
@@ -179,18 +1279,20 @@ impl Painting {
         if self.polygon_partition.is_empty() {
             return;
         }
+        let stroke = Stroke::new(self.stroke_width, self.colors(p).diagonal);
         for partition in self.polygon_partition.iter() {
-            self.draw_polygon(partition, p);
+            self.draw_polygon(partition, stroke, p);
         }
     }
 
     fn draw_centroid(&mut self, p: &Painter) {
+        let color = self.colors(p).centroid;
         if !self.dcel.faces.is_empty() {
             for face in self.dcel.faces.iter() {
                 let face_clone = face.clone();
                 let centroid = self.transpose_to_screen() * face_clone.as_ref().borrow().centroid;
 
-                let bounding_box_stroke = Stroke::new(2., Color32::BLACK);
+                let bounding_box_stroke = Stroke::new(2., color);
                 let rectangle = Rect {
                     max: pos2(centroid.x + self.radius, centroid.y + self.radius),
                     min: pos2(centroid.x - self.radius, centroid.y - self.radius),
@@ -210,30 +1312,376 @@ impl Painting {
                 min: pos2(centroid.x - self.radius, centroid.y - self.radius),
             };
             let centroid_mark =
-                egui::Shape::rect_filled(rectangle, egui::Rounding::ZERO, Color32::BLACK);
+                egui::Shape::rect_filled(rectangle, egui::Rounding::ZERO, self.colors(p).selection);
             p.add(centroid_mark);
         }
     }
 
+    /// Draw the exterior and the holes of the polygon.
+    fn draw_outline(&self, stroke: Stroke, p: &Painter) {
+        self.draw_polygon(&self.points, stroke, p);
+        for hole in self.holes.iter().filter(|hole| !hole.is_empty()) {
+            self.draw_polygon(hole, stroke, p);
+        }
+    }
+
+    /// Draw other polygons of a loaded file, which aren't triangulated, thin and faded.
+    fn draw_inactive_rings(&self, p: &Painter) {
+        let stroke = Stroke::new(1., self.colors(p).edge.gamma_multiply(0.5));
+        let rings = self
+            .other_polygons
+            .iter()
+            .flat_map(|polygon| std::iter::once(&polygon.exterior).chain(&polygon.holes));
+        for ring in rings.filter(|ring| !ring.is_empty()) {
+            let mut points: Points = ring
+                .iter()
+                .map(|x| self.transpose_to_screen() * *x)
+                .collect();
+            points.push(points[0]);
+            p.add(Shape::line(points, stroke));
+        }
+    }
+
+    /// Circle guard vertices, i.e. the smallest color class.
+    fn draw_guards(&self, p: &Painter) {
+        if !self.show_guards || self.color_classes.len() != self.bridged.vertices.len() {
+            return;
+        }
+        let colors = self.colors(p);
+        for idx in art_gallery_guards(&self.color_classes) {
+            let center = self.transpose_to_screen() * self.bridged.vertices[idx];
+            let color = colors.color_classes[self.color_classes[idx] as usize];
+            p.circle_stroke(center, self.radius * 2., Stroke::new(2., color));
+        }
+    }
+
+    /// Draw annotations as labels next to a pin.
+    fn draw_annotations(&self, p: &Painter) {
+        let color = self.colors(p).label;
+        for annotation in self.annotations.iter() {
+            let pos = self.transpose_to_screen() * annotation.pos;
+            p.circle_filled(pos, 3., color);
+            p.text(
+                pos + vec2(6., -6.),
+                Align2::LEFT_BOTTOM,
+                &annotation.text,
+                FontId::proportional(14.),
+                color,
+            );
+        }
+    }
+
+    /// Draw snapping grid over the visible part of painting area.
+    fn draw_grid(&self, p: &Painter) {
+        let spacing = self.snap_settings.grid_spacing;
+        // Too dense grid lines are just noise.
+        if !self.snap_settings.grid || spacing * self.camera.zoom < 5. {
+            return;
+        }
+        let rect = self._painting_rect;
+        let corner1 = self.transpose_from_screen() * rect.left_top();
+        let corner2 = self.transpose_from_screen() * rect.right_bottom();
+        let stroke = Stroke::new(
+            1.,
+            p.ctx()
+                .style()
+                .visuals
+                .widgets
+                .noninteractive
+                .bg_stroke
+                .color,
+        );
+        let mut x = (corner1.x.min(corner2.x) / spacing).ceil() * spacing;
+        while x <= corner1.x.max(corner2.x) {
+            let screen_x = (self.transpose_to_screen() * pos2(x, 0.)).x;
+            p.vline(screen_x, rect.y_range(), stroke);
+            x += spacing;
+        }
+        let mut y = (corner1.y.min(corner2.y) / spacing).ceil() * spacing;
+        while y <= corner1.y.max(corner2.y) {
+            let screen_y = (self.transpose_to_screen() * pos2(0., y)).y;
+            p.hline(rect.x_range(), screen_y, stroke);
+            y += spacing;
+        }
+    }
+
+    /// Draw hover guides of a snapped position.
+    fn draw_snap_guides(&self, snapped: &Snap, p: &Painter) {
+        let color = self.colors(p).selection;
+        let stroke = Stroke::new(1., color);
+        let pos = self.transpose_to_screen() * snapped.pos;
+        let to_screen = |idx: usize| self.transpose_to_screen() * self.points[idx];
+        for guide in snapped.guides.iter() {
+            match *guide {
+                SnapGuide::Grid => {
+                    let size = self.radius;
+                    p.line_segment([pos - vec2(size, 0.), pos + vec2(size, 0.)], stroke);
+                    p.line_segment([pos - vec2(0., size), pos + vec2(0., size)], stroke);
+                }
+                SnapGuide::Vertex(_) => {
+                    p.circle_stroke(pos, self.radius * 2., stroke);
+                }
+                SnapGuide::Horizontal(idx) | SnapGuide::Vertical(idx) => {
+                    p.extend(Shape::dashed_line(&[to_screen(idx), pos], stroke, 6., 4.));
+                }
+                SnapGuide::Angle(idx, degrees) => {
+                    p.extend(Shape::dashed_line(&[to_screen(idx), pos], stroke, 6., 4.));
+                    p.text(
+                        pos + vec2(self.radius, -self.radius),
+                        Align2::LEFT_BOTTOM,
+                        format!("{:.0}°", degrees),
+                        FontId::monospace(12.),
+                        color,
+                    );
+                }
+            }
+        }
+        p.circle_stroke(pos, self.radius, stroke);
+    }
+
+    /// Edge given by the index of its origin, vertices of the exterior and
+    /// then the holes are numbered like the indices of `PolygonError`.
+    fn ring_edge(&self, idx: usize) -> Option<[Pos2; 2]> {
+        let mut idx = idx;
+        for ring in std::iter::once(&self.points).chain(&self.holes) {
+            if idx < ring.len() {
+                return Some([ring[idx], ring[(idx + 1) % ring.len()]]);
+            }
+            idx -= ring.len();
+        }
+        None
+    }
+
+    /// Highlight the edges, vertices or hole that make current polygon invalid.
+    fn draw_polygon_error(&self, p: &Painter) {
+        let error_color = p.ctx().style().visuals.error_fg_color;
+        let to_screen = |idx: usize| {
+            self.ring_edge(idx)
+                .map(|edge| edge.map(|x| self.transpose_to_screen() * x))
+        };
+        let stroke = Stroke::new(self.stroke_width + 2., error_color);
+        match self.triangulation_error {
+            Some(PolygonError::SelfIntersection(i, j)) => {
+                for edge in [to_screen(i), to_screen(j)].into_iter().flatten() {
+                    p.line_segment(edge, stroke);
+                }
+            }
+            Some(PolygonError::DuplicateVertex(i, _)) => {
+                if let Some([center, _]) = to_screen(i) {
+                    p.circle_stroke(center, self.radius * 2., stroke);
+                }
+            }
+            Some(PolygonError::HoleOutside(k)) => {
+                if let Some(hole) = self.holes.get(k) {
+                    self.draw_polygon(hole, stroke, p);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Highlight faces and diagonals that break an invariant of triangulation,
+    /// while the diagnostics panel is open.
+    fn draw_violations(&self, p: &Painter) {
+        let Some(report) = self
+            .diagnostics
+            .as_ref()
+            .filter(|_| self.show_diagnostics_panel)
+        else {
+            return;
+        };
+        let error_color = p.ctx().style().visuals.error_fg_color;
+        let stroke = Stroke::new(self.stroke_width + 2., error_color);
+        let to_screen = |idx: usize| self.transpose_to_screen() * self.bridged.vertices[idx];
+        let diagonal = |idx: usize| {
+            let (a, b) = self.dcel.diagonals[idx];
+            p.line_segment([to_screen(a), to_screen(b)], stroke);
+        };
+        for violation in report.violations.iter() {
+            match violation {
+                TriangulationViolation::NotTriangle { face, .. }
+                | TriangulationViolation::Degenerate { face, .. }
+                | TriangulationViolation::ClockWise { face, .. } => {
+                    self.draw_polygon(&self.polygon_partition[*face], stroke, p);
+                }
+                TriangulationViolation::DiagonalsCross(i, j) => {
+                    diagonal(*i);
+                    diagonal(*j);
+                }
+                TriangulationViolation::DiagonalCrossesBoundary { diagonal: i, .. }
+                | TriangulationViolation::DiagonalNotShared { diagonal: i, .. } => diagonal(*i),
+                _ => {}
+            }
+        }
+    }
+
+    /// Draw the state of triangulation at the current replay step.
+    fn draw_replay_step(&self, step_idx: usize, p: &Painter) {
+        let colors = self.colors(p);
+        let step = &self.trace.steps[step_idx];
+        let to_screen = |idx: usize| self.transpose_to_screen() * self.bridged.vertices[idx];
+
+        // Monotone polygon being triangulated
+        if !step.monotone_polygon.is_empty() {
+            let mut outline = step
+                .monotone_polygon
+                .iter()
+                .map(|idx| to_screen(*idx))
+                .collect::<Vec<Pos2>>();
+            outline.push(outline[0]);
+            let stroke = Stroke::new(self.stroke_width * 3., colors.diagonal.gamma_multiply(0.4));
+            p.add(Shape::line(outline, stroke));
+        }
+
+        // Edges in status tree, and their helpers
+        for (edge_origin, helper) in step.status.iter() {
+            let edge_end = (edge_origin + 1) % self.bridged.vertices.len();
+            let stroke = Stroke::new(self.stroke_width * 3., colors.selection.gamma_multiply(0.5));
+            p.line_segment([to_screen(*edge_origin), to_screen(edge_end)], stroke);
+            p.circle_stroke(
+                to_screen(*helper),
+                self.radius * 1.6,
+                Stroke::new(1.5, colors.selection),
+            );
+        }
+
+        // Diagonals inserted until current step
+        let diagonal_stroke = Stroke::new(self.stroke_width, colors.diagonal);
+        for (idx1, idx2) in self.trace.diagonals_until(step_idx) {
+            p.line_segment([to_screen(idx1), to_screen(idx2)], diagonal_stroke);
+        }
+        let new_diagonal_stroke = Stroke::new(self.stroke_width * 2., colors.selection);
+        for (idx1, idx2) in step.diagonals.iter() {
+            p.line_segment([to_screen(*idx1), to_screen(*idx2)], new_diagonal_stroke);
+        }
+
+        // Horizontal sweep line through event vertex
+        let event = to_screen(step.event_vertex);
+        let rect = p.clip_rect();
+        p.hline(
+            rect.x_range(),
+            event.y,
+            Stroke::new(1., colors.edge.gamma_multiply(0.5)),
+        );
+        p.circle_stroke(event, self.radius * 2., Stroke::new(2., colors.selection));
+    }
+
+    /// Describe the current replay step in text.
+    fn ui_replay_details(&self, ui: &mut Ui, step_idx: usize) {
+        let step = &self.trace.steps[step_idx];
+        match step.phase {
+            TracePhase::MonotonePartition => {
+                let vertex_type = step
+                    .vertex_type
+                    .as_ref()
+                    .map_or("unknown", vertex_type_name);
+                ui.label(format!(
+                    "Monotone partition: vertex{} is {} vertex",
+                    step.event_vertex, vertex_type
+                ));
+                let status = step
+                    .status
+                    .iter()
+                    .map(|(origin, helper)| format!("e{}(helper {})", origin, helper))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                ui.label(format!("Status tree: [{}]", status));
+                ui.label(format!("Event queue: {:?}", step.event_stack));
+            }
+            TracePhase::TriangulateMonotone => {
+                ui.label(format!(
+                    "Triangulate monotone polygon {:?}: event vertex{}",
+                    step.monotone_polygon, step.event_vertex
+                ));
+                ui.label(format!("Process stack: {:?}", step.process_stack));
+                ui.label(format!("Event stack: {:?}", step.event_stack));
+            }
+        }
+        ui.label(format!("Inserted diagonals: {:?}", step.diagonals));
+    }
+
+    /// Play/pause/step/scrub controls of the replay.
+    fn ui_replay(&mut self, ui: &mut Ui) {
+        if !self.triangulated || self.trace.is_empty() || self.triangulation_error.is_some() {
+            return;
+        }
+        let last_step = self.trace.len() - 1;
+
+        // Advance replay while playing
+        if self.replay_playing {
+            self.replay_elapsed += ui.input(|i| i.stable_dt);
+            let step = self.replay_step.unwrap_or(0);
+            if self.replay_elapsed * self.replay_speed >= 1. {
+                self.replay_elapsed = 0.;
+                if step >= last_step {
+                    self.replay_playing = false;
+                } else {
+                    self.replay_step = Some(step + 1);
+                }
+            } else {
+                self.replay_step = Some(step);
+            }
+            ui.ctx().request_repaint();
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Replay");
+            if ui.button("⏮").on_hover_text("First step").clicked() {
+                self.replay_step = Some(0);
+            }
+            if ui.button("⏴").on_hover_text("Previous step").clicked() {
+                self.replay_step = Some(self.replay_step.unwrap_or(0).saturating_sub(1));
+            }
+            let play_text = if self.replay_playing { "⏸" } else { "▶" };
+            if ui.button(play_text).on_hover_text("Play/pause").clicked() {
+                self.replay_playing = !self.replay_playing;
+                self.replay_elapsed = 0.;
+                if self.replay_playing && !matches!(self.replay_step, Some(x) if x < last_step) {
+                    self.replay_step = Some(0);
+                }
+            }
+            if ui.button("⏵").on_hover_text("Next step").clicked() {
+                self.replay_step = Some(self.replay_step.map_or(0, |x| (x + 1).min(last_step)));
+            }
+            let mut step = self.replay_step.unwrap_or(last_step);
+            if ui
+                .add(egui::Slider::new(&mut step, 0..=last_step).text("step"))
+                .changed()
+            {
+                self.replay_step = Some(step);
+            }
+            ui.add(
+                DragValue::new(&mut self.replay_speed)
+                    .range(0.5..=20.0)
+                    .suffix(" steps/s"),
+            );
+            if ui
+                .add_enabled(self.replay_step.is_some(), egui::Button::new("Show result"))
+                .clicked()
+            {
+                self.replay_step = None;
+                self.replay_playing = false;
+            }
+        });
+        if let Some(step) = self.replay_step {
+            self.ui_replay_details(ui, step);
+        }
+    }
+
     /// Define Gui widget layout, and button click event.
     fn ui_control(&mut self, ui: &mut egui::Ui) -> egui::Response {
         ui.horizontal(|ui| {
-            ui.label("Stroke:");
-            ui.add(&mut self.stroke);
+            ui.label("Stroke");
+            ui.add(DragValue::new(&mut self.stroke_width).range(0.0..=10.0));
             ui.separator();
             ui.label("Radius");
             ui.add(DragValue::new(&mut self.radius));
             ui.separator();
             // Clear button
-            if ui.button("Clear Painting").clicked() {
-                self.points.clear();
-                self.polygon_partition.clear();
-                self.focused_point = None;
-                self.dcel = PartitionPolygon::new();
-                self.point_colors.clear();
-
-                self.triangulated = false;
-                self.coloring = false;
+            if ui.button("Clear Painting").clicked()
+                && (!self.points.is_empty() || self.triangulated)
+            {
+                self.execute(self.clear_command());
             }
             // triangle button
             let triangle_button = ui
@@ -244,32 +1692,52 @@ impl Painting {
                     ));
                 });
             if triangle_button.clicked() {
-                self.triangulated = true;
-                // self.polygon_partition = monoton_polygon_partition(&self.points);
-                self.polygon_partition = polygon_triangulation(&self.points, &mut self.dcel);
+                self.execute(Command::Triangulate);
             }
             // 3-coloring button
             if ui
-                .add_enabled(self.triangulated, egui::Button::new("3-coloring triangles"))
+                .add_enabled(
+                    self.triangulated && !self.partition_only,
+                    egui::Button::new("3-coloring triangles"),
+                )
                 .clicked()
             {
-                self.coloring = true;
-
                 // Do 3 coloring vertices
-                self.point_colors = generate_point_colors(self.points.len());
-                let mut check_table: Vec<(usize, usize)> = Vec::new();
-                let start_face_idx = if self.focused_point.is_none() {
-                    0
-                } else {
-                    self.focused_point.unwrap().1
-                };
+                let start_face_idx = self.selected_face().unwrap_or(0);
+                self.execute(Command::Coloring {
+                    from: self.coloring_start,
+                    to: start_face_idx,
+                });
+            }
 
-                dfs(
-                    self.dcel.faces[start_face_idx].clone(),
-                    &mut check_table,
-                    &mut self.point_colors,
-                );
+            ui.separator();
+            if ui.button("Fit to polygon").clicked() {
+                self.camera.fit(self._painting_rect, &self.points, 20.);
+            }
+            if ui.button("Reset view").clicked() {
+                self.camera.reset();
+            }
+            ui.label(format!("{:.0}%", self.camera.zoom * 100.));
+            ui.separator();
+            if ui
+                .checkbox(&mut self.live_mode, "Live")
+                .on_hover_text("Triangulate and color the polygon on every edit")
+                .changed()
+                && self.live_mode
+            {
+                self.rebuild();
+            }
+            if ui
+                .checkbox(&mut self.partition_only, "Monotone only")
+                .on_hover_text("Stop after partitioning the polygon into y-monotone pieces")
+                .changed()
+            {
+                self.rebuild();
             }
+            ui.checkbox(&mut self.show_vertex_types, "Vertex types");
+            ui.checkbox(&mut self.show_guards, "Guards").on_hover_text(
+                "Mark the smallest color class, whose vertices guard the whole polygon",
+            );
 
             // Do something here
             if self.triangulated {
@@ -281,37 +1749,140 @@ impl Painting {
 
     /// Update gui elements
     fn ui_content(&mut self, ui: &mut Ui) -> egui::Response {
+        if let Some(message) = self.file_message.clone() {
+            ui.horizontal(|ui| {
+                ui.label(message);
+                if ui.small_button("✖").clicked() {
+                    self.file_message = None;
+                }
+            });
+        }
+        if let Some(err) = &self.triangulation_error {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("⚠ {}, showing the last valid triangulation.", err),
+            );
+        }
+        if let Some(message) = &self.click_message {
+            ui.colored_label(ui.visuals().warn_fg_color, message);
+        }
         let (mut response, painter) =
-            ui.allocate_painter(ui.available_size_before_wrap(), Sense::click());
+            ui.allocate_painter(ui.available_size_before_wrap(), Sense::click_and_drag());
         self._painting_rect = response.rect;
+        let pointer_pos = response.interact_pointer_pos();
+
+        // Zoom around the cursor with mouse wheel or pinch gesture.
+        if let Some(hover_pos) = response.hover_pos() {
+            let (scroll, zoom_delta) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+            let factor = (scroll * 0.002).exp() * zoom_delta;
+            if factor != 1. {
+                self.camera.zoom_at(response.rect, hover_pos, factor);
+            }
+        }
+
+        // Drag vertices around, triangulation is rebuilt once dragging stops,
+        // or on every move in live mode.\
+        // Dragging empty space or dragging with middle button pans the view.
+        if response.drag_started() {
+            let vertex = pointer_pos.and_then(|pos| self.vertex_at(pos));
+            match vertex {
+                Some(idx) if !response.dragged_by(PointerButton::Middle) => {
+                    self.dragging = Some((idx, self.points[idx]));
+                }
+                _ => self.panning = true,
+            }
+        }
+        if self.panning {
+            self.camera.pan(response.drag_delta());
+            if response.drag_stopped() {
+                self.panning = false;
+            }
+        }
+        let mut hover_snap = None;
+        if let (Some((idx, _)), Some(cur_pos)) = (self.dragging, pointer_pos) {
+            let prev = (idx + self.points.len() - 1) % self.points.len();
+            let snapped = self.snap_pos(
+                self.transpose_from_screen() * cur_pos,
+                Some(prev),
+                Some(idx),
+            );
+            let pos = snapped.pos;
+            hover_snap = Some(snapped);
+            if self.points[idx] != pos {
+                self.points[idx] = pos;
+                if self.live_mode {
+                    self.rebuild();
+                }
+                response.mark_changed();
+            }
+        }
+        if response.drag_stopped() {
+            if let Some((idx, from)) = self.dragging.take() {
+                let to = self.points[idx];
+                if from != to {
+                    // Vertex is already at its new position, only record the move.
+                    self.history.push(Command::MovePoint { idx, from, to });
+                    self.rebuild();
+                }
+            }
+        }
+
+        // Delete vertex with right click.
+        if response.secondary_clicked() {
+            if let Some(idx) = pointer_pos.and_then(|pos| self.vertex_at(pos)) {
+                let pos = self.points[idx];
+                self.execute(Command::DeletePoint { idx, pos });
+                response.mark_changed();
+            }
+        }
 
         // Catch mouse click event
-        if let Some(cur_pos) = response.interact_pointer_pos() {
+        if let Some(cur_pos) = pointer_pos.filter(|_| response.clicked()) {
             debug!("current cursor position:({},{})", cur_pos.x, cur_pos.y);
             // Transpose current cursor's position to conventional coordinate system.
             let current_point = self.transpose_from_screen() * cur_pos;
+            let anchor = self.points.len().checked_sub(1);
+            let snapped_point = self.snap_pos(current_point, anchor, None).pos;
 
             // Define mouse click behavior in painting area.
-            if self.triangulated {
+            if self.vertex_at(cur_pos).is_some() {
+                // Clicking on an existing vertex doesn't add a new one.
+            } else if let Some((edge_origin, pos)) = self.edge_at(cur_pos) {
+                // Insert a new vertex on the clicked edge.
+                self.execute(Command::AddPoint {
+                    idx: edge_origin + 1,
+                    pos,
+                });
+                response.mark_changed();
+            } else if self.triangulated && (!self.live_mode || ui.input(|i| i.modifiers.shift)) {
+                // Live mode keeps adding vertices, faces are selected with shift-click.
                 self.build_kd_tree();
                 if let Some(nearest_point) =
                     self.kdtree.nearest(&[current_point.x, current_point.y])
                 {
                     let [x, y] = nearest_point.item.0;
                     let face_idx = nearest_point.item.1;
-                    self.focused_point = Some((pos2(x, y), face_idx));
+                    if self.selected_face() != Some(face_idx) {
+                        self.execute(Command::SelectFace {
+                            from: self.selected_face(),
+                            to: Some(face_idx),
+                        });
+                    }
                     debug!("Focused point coordinate:({},{})", x, y);
                 }
             } else if let Some(last_point) = self.points.last() {
                 // Reject the current cursor position that is too close the last point position.
-                // HACK: Reject neighbor point that has same y coordinates,
-                // This is a temperary solution to https://github.com/ShampooDeng/triangulate-rs-egui/issues/13
-                if ((last_point.x - current_point.x).powi(2)
-                    + (last_point.y - current_point.y).powi(2)
-                    > 1000.)
-                    && last_point.y.ne(&current_point.y)
-                {
-                    self.points.push(current_point);
+                if last_point.distance(snapped_point) <= self.snap_settings.min_spacing {
+                    self.click_message = Some(format!(
+                        "Vertex not added, it's within {} of the last vertex.",
+                        self.snap_settings.min_spacing
+                    ));
+                } else {
+                    self.click_message = None;
+                    self.execute(Command::AddPoint {
+                        idx: self.points.len(),
+                        pos: snapped_point,
+                    });
                     response.mark_changed();
                     debug!(
                         "Pushing point coordinate:({},{})",
@@ -321,7 +1892,10 @@ impl Painting {
                 }
             } else {
                 // Jump to here when the points vec is empty.
-                self.points.push(current_point);
+                self.execute(Command::AddPoint {
+                    idx: 0,
+                    pos: snapped_point,
+                });
                 debug!(
                     "Pushing point coordinate:({},{})",
                     self.points.last().unwrap().x,
@@ -331,16 +1905,439 @@ impl Painting {
             }
         }
 
+        // Show where a click would place the new vertex.
+        let appending = !self.triangulated || self.live_mode;
+        if let Some(hover_pos) = response.hover_pos().filter(|_| hover_snap.is_none()) {
+            if appending
+                && !self.panning
+                && self.vertex_at(hover_pos).is_none()
+                && self.edge_at(hover_pos).is_none()
+            {
+                let anchor = self.points.len().checked_sub(1);
+                hover_snap =
+                    Some(self.snap_pos(self.transpose_from_screen() * hover_pos, anchor, None));
+            }
+        }
+
         // Drawing ui content
-        self.draw_polygon(&self.points, &painter);
-        self.draw_polygon_partition(&painter);
+        self.draw_grid(&painter);
+        self.draw_inactive_rings(&painter);
+        let outline_stroke = Stroke::new(self.stroke_width, self.colors(&painter).edge);
+        if let Some(step) = self.replay_step {
+            self.draw_outline(outline_stroke, &painter);
+            self.draw_replay_step(step, &painter);
+            self.draw_vertices(&painter);
+            self.draw_vertex_type_legend(&painter);
+            return response;
+        }
+        // Triangulation is outdated while a vertex is being dragged,
+        // unless it's rebuilt on the fly in live mode.
+        let show_result = self.dragging.is_none() || self.live_mode;
+        // Diagonals go first, so that polygon outline can be drawn over them.
+        if show_result {
+            self.draw_polygon_partition(&painter);
+        }
+        self.draw_outline(outline_stroke, &painter);
         self.draw_vertices(&painter);
-        self.draw_centroid(&painter);
-        self.draw_focused_point(&painter);
+        if show_result {
+            self.draw_centroid(&painter);
+            self.draw_focused_point(&painter);
+        }
+        self.draw_guards(&painter);
+        self.draw_polygon_error(&painter);
+        self.draw_violations(&painter);
+        self.draw_annotations(&painter);
+        if let Some(snapped) = hover_snap {
+            self.draw_snap_guides(&snapped, &painter);
+        }
+        self.draw_vertex_type_legend(&painter);
 
         response
     }
 
+    /// Draw the snapping window, where user can toggle snapping aids.
+    fn render_snapping_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_snapping_window;
+        let settings = &mut self.snap_settings;
+        egui::Window::new("Snapping")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("snapping_grid").show(ui, |ui| {
+                    ui.checkbox(&mut settings.grid, "Grid");
+                    ui.add(
+                        DragValue::new(&mut settings.grid_spacing)
+                            .range(1.0..=1000.0)
+                            .prefix("spacing "),
+                    );
+                    ui.end_row();
+                    ui.checkbox(&mut settings.vertices, "Vertices");
+                    ui.end_row();
+                    ui.checkbox(&mut settings.alignment, "Horizontal/vertical alignment");
+                    ui.end_row();
+                    ui.checkbox(&mut settings.angle, "Angle");
+                    ui.add(
+                        DragValue::new(&mut settings.angle_step)
+                            .range(1.0..=90.0)
+                            .suffix("°"),
+                    );
+                    ui.end_row();
+                    ui.label("Snapping distance");
+                    ui.add(
+                        DragValue::new(&mut settings.tolerance)
+                            .range(1.0..=50.0)
+                            .suffix(" px"),
+                    );
+                    ui.end_row();
+                    ui.label("Minimum vertex spacing");
+                    ui.add(DragValue::new(&mut settings.min_spacing).range(0.0..=1000.0));
+                    ui.end_row();
+                });
+            });
+        self.show_snapping_window = open;
+    }
+
+    /// Draw the gallery window, where clicking a thumbnail loads a preset polygon.
+    fn render_gallery_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_gallery_window;
+        let mut picked = None;
+        let mut random = false;
+        egui::Window::new("Gallery")
+            .open(&mut open)
+            .default_width(460.)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("Random").strong());
+                let settings = &mut self.random_settings;
+                ui.horizontal_wrapped(|ui| {
+                    egui::ComboBox::from_id_source("random_method")
+                        .selected_text(settings.method.label())
+                        .show_ui(ui, |ui| {
+                            for method in RandomMethod::ALL {
+                                ui.selectable_value(&mut settings.method, method, method.label());
+                            }
+                        });
+                    ui.add(
+                        DragValue::new(&mut settings.vertices)
+                            .range(3..=2000)
+                            .suffix(" vertices"),
+                    );
+                    ui.add(
+                        DragValue::new(&mut settings.holes)
+                            .range(0..=10)
+                            .suffix(" holes"),
+                    );
+                    ui.add(DragValue::new(&mut settings.seed).prefix("seed "));
+                });
+                if ui.button("Random polygon").clicked() {
+                    random = true;
+                }
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let presets = presets();
+                    for category in PresetCategory::ALL {
+                        ui.label(RichText::new(category.label()).strong());
+                        ui.horizontal_wrapped(|ui| {
+                            for preset in presets.iter().filter(|x| x.category == category) {
+                                if preset_thumbnail(ui, preset).clicked() {
+                                    picked = Some(preset.clone());
+                                }
+                            }
+                        });
+                        ui.separator();
+                    }
+                });
+            });
+        self.show_gallery_window = open;
+        if let Some(preset) = picked {
+            info!("load preset: {}", preset.name);
+            self.load_polygons(vec![preset.polygon]);
+        }
+        if random {
+            self.load_random_polygon();
+        }
+    }
+
+    /// Draw the shapes panel, where every parameter change regenerates the polygon.
+    fn render_shapes_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_shapes_panel {
+            return;
+        }
+        let mut changed = false;
+        egui::SidePanel::left("shapes_panel").show(ctx, |ui| {
+            ui.heading("Shapes");
+            ui.separator();
+            egui::ComboBox::from_label("Shape")
+                .selected_text(self.shape.label())
+                .show_ui(ui, |ui| {
+                    for shape in ParametricShape::defaults() {
+                        let selected = shape.label() == self.shape.label();
+                        if ui.selectable_label(selected, shape.label()).clicked() && !selected {
+                            self.shape = shape;
+                            changed = true;
+                        }
+                    }
+                });
+            match &mut self.shape {
+                ParametricShape::RegularPolygon { sides, radius } => {
+                    changed |= ui.add(Slider::new(sides, 3..=64).text("sides")).changed();
+                    changed |= ui
+                        .add(Slider::new(radius, 20.0..=240.0).text("radius"))
+                        .changed();
+                }
+                ParametricShape::Star {
+                    points,
+                    outer,
+                    inner,
+                } => {
+                    changed |= ui.add(Slider::new(points, 2..=32).text("points")).changed();
+                    changed |= ui
+                        .add(Slider::new(outer, 20.0..=240.0).text("outer radius"))
+                        .changed();
+                    changed |= ui
+                        .add(Slider::new(inner, 5.0..=240.0).text("inner radius"))
+                        .changed();
+                }
+                ParametricShape::Comb { teeth } => {
+                    changed |= ui.add(Slider::new(teeth, 1..=100).text("teeth")).changed();
+                }
+                ParametricShape::Spiral { turns, samples } => {
+                    changed |= ui
+                        .add(Slider::new(turns, 0.25..=4.0).text("turns"))
+                        .changed();
+                    changed |= ui
+                        .add(Slider::new(samples, 2..=400).text("samples"))
+                        .changed();
+                }
+                ParametricShape::Zigzag {
+                    teeth,
+                    amplitude,
+                    thickness,
+                } => {
+                    changed |= ui.add(Slider::new(teeth, 1..=50).text("teeth")).changed();
+                    changed |= ui
+                        .add(Slider::new(amplitude, 10.0..=300.0).text("amplitude"))
+                        .changed();
+                    changed |= ui
+                        .add(Slider::new(thickness, 5.0..=200.0).text("thickness"))
+                        .changed();
+                }
+                ParametricShape::Sawtooth { teeth, amplitude } => {
+                    changed |= ui.add(Slider::new(teeth, 1..=50).text("teeth")).changed();
+                    changed |= ui
+                        .add(Slider::new(amplitude, 10.0..=180.0).text("amplitude"))
+                        .changed();
+                }
+                ParametricShape::Polar {
+                    expression,
+                    samples,
+                } => {
+                    ui.label("r(θ) =");
+                    changed |= ui.text_edit_singleline(expression).changed();
+                    changed |= ui
+                        .add(Slider::new(samples, 3..=1000).text("samples"))
+                        .changed();
+                }
+            }
+            if ui.button("Generate").clicked() {
+                changed = true;
+            }
+            if let Some(err) = &self.shape_error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+        });
+        if changed {
+            self.load_shape();
+        }
+    }
+
+    /// Draw the diagnostics panel, which checks the invariants of
+    /// the current triangulation and lists the violated ones.
+    fn render_diagnostics_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_diagnostics_panel {
+            return;
+        }
+        let up_to_date =
+            self.triangulated && !self.partition_only && self.triangulation_error.is_none();
+        if up_to_date && self.diagnostics.is_none() {
+            self.diagnostics = Some(verify_triangulation(&self.bridged.vertices, &self.dcel));
+        }
+        egui::SidePanel::right("diagnostics_panel").show(ctx, |ui| {
+            ui.heading("Diagnostics");
+            ui.separator();
+            if let Some(err) = &self.triangulation_error {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Invalid polygon: {}", err),
+                );
+            }
+            let Some(report) = &self.diagnostics else {
+                if self.partition_only {
+                    ui.label("Only triangulations are checked, turn off \"Monotone only\".");
+                } else {
+                    ui.label("Triangulate to check the result.");
+                }
+                return;
+            };
+            egui::Grid::new("diagnostics_grid").show(ui, |ui| {
+                ui.label("Vertices");
+                ui.label(report.vertices.to_string());
+                ui.end_row();
+                ui.label("Triangles");
+                ui.label(report.triangles.to_string());
+                ui.end_row();
+                ui.label("Diagonals");
+                ui.label(report.diagonals.to_string());
+                ui.end_row();
+                ui.label("Polygon area");
+                ui.label(format!("{:.2}", report.polygon_area));
+                ui.end_row();
+                ui.label("Triangle area");
+                ui.label(format!("{:.2}", report.triangle_area));
+                ui.end_row();
+            });
+            ui.separator();
+            if report.is_valid() {
+                ui.label(RichText::new("✔ All invariants hold").color(Color32::GREEN));
+                return;
+            }
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("✖ {} violations", report.violations.len()),
+            );
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for violation in report.violations.iter() {
+                    ui.label(violation.to_string());
+                }
+            });
+        });
+    }
+
+    /// Draw the annotations window, where user can add, edit and remove notes.
+    fn render_annotations_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_annotations_window;
+        let center = self.camera.center;
+        egui::Window::new("Annotations")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let mut removed = None;
+                egui::Grid::new("annotations_grid").show(ui, |ui| {
+                    for (idx, annotation) in self.annotations.iter_mut().enumerate() {
+                        ui.text_edit_singleline(&mut annotation.text);
+                        ui.add(DragValue::new(&mut annotation.pos.x).prefix("x "));
+                        ui.add(DragValue::new(&mut annotation.pos.y).prefix("y "));
+                        if ui.small_button("✖").clicked() {
+                            removed = Some(idx);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(idx) = removed {
+                    self.annotations.remove(idx);
+                }
+                if ui.button("Add at view center").clicked() {
+                    self.annotations.push(Annotation {
+                        pos: center,
+                        text: format!("note {}", self.annotations.len() + 1),
+                    });
+                }
+            });
+        self.show_annotations_window = open;
+    }
+
+    /// Draw the palette window, where user can pick a preset palette
+    /// or edit colors of the current visuals.
+    fn render_palette_window(&mut self, ctx: &egui::Context) {
+        let dark_mode = ctx.style().visuals.dark_mode;
+        let mut open = self.show_palette_window;
+        egui::Window::new("Palette")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_label("Preset")
+                    .selected_text(self.palette.name.label())
+                    .show_ui(ui, |ui| {
+                        for name in PaletteName::PRESETS {
+                            if ui
+                                .selectable_label(self.palette.name == name, name.label())
+                                .clicked()
+                            {
+                                self.palette = Palette::from_name(name);
+                            }
+                        }
+                    });
+                ui.separator();
+                ui.label(if dark_mode {
+                    "Colors for dark mode"
+                } else {
+                    "Colors for light mode"
+                });
+
+                let mut colors = *self.palette.colors(dark_mode);
+                egui::Grid::new("palette_grid").show(ui, |ui| {
+                    let color_row = |ui: &mut Ui, label: &str, color: &mut Color32| {
+                        ui.label(label);
+                        ui.color_edit_button_srgba(color);
+                        ui.end_row();
+                    };
+                    color_row(ui, "Vertex", &mut colors.vertex);
+                    color_row(ui, "Label", &mut colors.label);
+                    color_row(ui, "Edge", &mut colors.edge);
+                    color_row(ui, "Diagonal", &mut colors.diagonal);
+                    color_row(ui, "Centroid", &mut colors.centroid);
+                    color_row(ui, "Selection", &mut colors.selection);
+                    for (i, color) in colors.color_classes.iter_mut().enumerate() {
+                        color_row(ui, &format!("Color class {}", i), color);
+                    }
+                });
+                if colors != *self.palette.colors(dark_mode) {
+                    *self.palette.colors_mut(dark_mode) = colors;
+                    self.palette.name = PaletteName::Custom;
+                }
+            });
+        self.show_palette_window = open;
+    }
+
+    /// Side panel listing executed and undone commands,
+    /// clicking an entry moves the painting to the state after that command.
+    fn render_history_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_history_panel {
+            return;
+        }
+        let mut undo_steps = 0;
+        let mut redo_steps = 0;
+        egui::SidePanel::right("history_panel").show(ctx, |ui| {
+            ui.heading("History");
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let done = self.history.done();
+                for (i, command) in done.iter().enumerate() {
+                    let is_current = i + 1 == done.len();
+                    if ui
+                        .selectable_label(is_current, command.description())
+                        .clicked()
+                    {
+                        undo_steps = done.len() - 1 - i;
+                    }
+                }
+                for (i, command) in self.history.undone().iter().rev().enumerate() {
+                    let text = RichText::new(command.description()).weak();
+                    if ui.selectable_label(false, text).clicked() {
+                        redo_steps = i + 1;
+                    }
+                }
+                if done.is_empty() && self.history.undone().is_empty() {
+                    ui.label("Nothing to undo");
+                }
+            });
+        });
+        for _ in 0..undo_steps {
+            self.undo();
+        }
+        for _ in 0..redo_steps {
+            self.redo();
+        }
+    }
+
     /// Draw ui content of the About page.
     fn render_about_page(&mut self, ctx: &egui::Context) {
         if !self.show_immediate_about_page {
@@ -395,20 +2392,117 @@ impl Painting {
 }
 
 impl eframe::App for Painting {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STATE_KEY, &self.saved_state());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Check redo first, since Ctrl+Z also matches Ctrl+Shift+Z.
+        if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+            self.redo();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+            self.undo();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if ctx.input_mut(|i| i.consume_shortcut(&SAVE_SHORTCUT)) {
+            self.save_project(false);
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 egui::widgets::global_dark_light_mode_buttons(ui);
+                ui.separator();
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.menu_button("File", |ui| {
+                    if ui.button("Open project…").clicked() {
+                        ui.close_menu();
+                        self.open_project();
+                    }
+                    if ui
+                        .add(
+                            egui::Button::new("Save project")
+                                .shortcut_text(ctx.format_shortcut(&SAVE_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.save_project(false);
+                    }
+                    if ui.button("Save project as…").clicked() {
+                        ui.close_menu();
+                        self.save_project(true);
+                    }
+                    ui.separator();
+                    if ui.button("Open polygon…").clicked() {
+                        ui.close_menu();
+                        self.open_file();
+                    }
+                    ui.menu_button("Export", |ui| {
+                        for format in ExportFormat::ALL {
+                            if ui.button(format.label()).clicked() {
+                                ui.close_menu();
+                                self.export_file(format);
+                            }
+                        }
+                    });
+                });
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                    .on_hover_text(ctx.format_shortcut(&UNDO_SHORTCUT))
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                    .on_hover_text(ctx.format_shortcut(&REDO_SHORTCUT))
+                    .clicked()
+                {
+                    self.redo();
+                }
+                ui.toggle_value(&mut self.show_history_panel, "History");
+                ui.toggle_value(&mut self.show_shapes_panel, "Shapes");
+                ui.toggle_value(&mut self.show_diagnostics_panel, "Diagnostics");
+                ui.separator();
+                if ui.button("Palette").clicked() {
+                    self.show_palette_window = !self.show_palette_window;
+                }
+                if ui.button("Snapping").clicked() {
+                    self.show_snapping_window = !self.show_snapping_window;
+                }
+                if ui.button("Gallery").clicked() {
+                    self.show_gallery_window = !self.show_gallery_window;
+                }
+                if ui
+                    .button("Random polygon")
+                    .on_hover_text("Parameters are in the gallery")
+                    .clicked()
+                {
+                    self.load_random_polygon();
+                }
+                if ui.button("Annotations").clicked() {
+                    self.show_annotations_window = !self.show_annotations_window;
+                }
                 if ui.button("About").clicked() {
                     self.show_immediate_about_page = true;
                 }
             });
             self.render_about_page(ctx);
         });
+        self.render_palette_window(ctx);
+        self.render_snapping_window(ctx);
+        self.render_gallery_window(ctx);
+        self.render_annotations_window(ctx);
+        self.render_history_panel(ctx);
+        self.render_shapes_panel(ctx);
+        self.render_diagnostics_panel(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            if self.triangulated {
-                ui.heading("Click to select a rectangle!");
+            if self.live_mode {
+                ui.heading("Click to place vertex, shift-click to select a triangle!");
+            } else if self.triangulated {
+                ui.heading("Click to select a triangle!");
             } else {
                 ui.horizontal(|ui| {
                     ui.heading("Click to place vertex! MUST in ");
@@ -416,7 +2510,10 @@ impl eframe::App for Painting {
                     ui.heading("order");
                 });
             }
+            ui.label("Drag a vertex to move it, click on an edge to insert a vertex, right click a vertex to delete it.");
+            ui.label("Scroll to zoom, drag empty space or drag with middle button to pan.");
             self.ui_control(ui);
+            self.ui_replay(ui);
             self.ui_content(ui);
         });
     }
@@ -424,7 +2521,195 @@ impl eframe::App for Painting {
 
 #[cfg(test)]
 mod tests {
-    use egui::pos2;
+    use super::{Algorithm, Camera, Painting, Palette, PaletteName, SavedState, STATE_VERSION};
+    use crate::monotone_y_partition::monotone_partition_polygon;
+    use crate::polygon_validation::PolygonError;
+    use crate::presets::find_preset;
+    use crate::project::Project;
+    use crate::triangulation_validation::verify_triangulation;
+    use egui::{pos2, vec2};
+
+    fn seven_vertices() -> Vec<egui::Pos2> {
+        find_preset("Seven vertices").unwrap().polygon.exterior
+    }
+
+    #[test]
+    fn test_saved_state() {
+        let mut painting = Painting {
+            points: seven_vertices(),
+            ..Default::default()
+        };
+        painting.triangulate();
+        painting.color_from(2);
+        painting.camera.zoom = 3.;
+        let text = serde_json::to_string(&painting.saved_state()).unwrap();
+
+        let mut restored = Painting::default();
+        restored.restore(serde_json::from_str(&text).ok(), None);
+        assert_eq!(restored.points, painting.points);
+        assert_eq!(restored.camera, painting.camera);
+        assert_eq!(restored.dcel.faces.len(), 5);
+        assert_eq!(restored.coloring_start, Some(2));
+        assert_eq!(restored.color_classes, painting.color_classes);
+
+        // Missing fields take default values.
+        let old: SavedState = serde_json::from_str(r#"{"version": 1, "live_mode": true}"#).unwrap();
+        assert_eq!(old.points, Painting::default().points);
+        // Saves from newer builds are ignored.
+        let mut newer = painting.saved_state();
+        newer.version = STATE_VERSION + 1;
+        let mut restored = Painting::default();
+        restored.restore(Some(newer), None);
+        assert!(!restored.triangulated);
+        // Saves made before SavedState only have the palette.
+        let mut restored = Painting::default();
+        let palette = Palette::from_name(PaletteName::OkabeIto);
+        restored.restore(None, Some(palette));
+        assert!(restored.palette.name == PaletteName::OkabeIto);
+    }
+
+    #[test]
+    fn test_triangulate_invalid() {
+        // Clockwise, so it can't be triangulated.
+        let mut points = seven_vertices();
+        points.reverse();
+        let mut painting = Painting {
+            points,
+            ..Default::default()
+        };
+        painting.triangulate();
+        assert!(painting.triangulation_error.is_some());
+        assert!(!painting.triangulated);
+
+        // A previous valid result is kept.
+        painting.points.reverse();
+        painting.triangulate();
+        painting.points.reverse();
+        painting.triangulate();
+        assert!(painting.triangulation_error.is_some());
+        assert!(painting.triangulated);
+        assert_eq!(painting.dcel.faces.len(), 5);
+    }
+
+    #[test]
+    fn test_triangulate_holes() {
+        let two_holes = find_preset("Two holes").unwrap().polygon;
+        let mut painting = Painting::default();
+        painting.load_polygons(vec![two_holes.clone()]);
+        assert_eq!(painting.file_message, None);
+        painting.triangulate();
+        painting.color_from(0);
+        // 12 vertices and 2 holes, bridged vertices are counted twice.
+        assert_eq!(painting.dcel.faces.len(), 12 + 2 * 2 - 2);
+        assert_eq!(painting.color_classes.len(), 12 + 2 * 2);
+        let data = painting.triangulation_data();
+        assert_eq!(data.triangles.len(), 14);
+        assert!(verify_triangulation(&painting.bridged.vertices, &painting.dcel).is_valid());
+
+        // A hole outside of the polygon keeps the last valid triangulation.
+        for x in painting.holes[1].iter_mut() {
+            *x += vec2(1000., 0.);
+        }
+        painting.triangulate();
+        assert_eq!(
+            painting.triangulation_error,
+            Some(PolygonError::HoleOutside(1))
+        );
+        assert_eq!(painting.dcel.faces.len(), 14);
+    }
+
+    #[test]
+    fn test_undo_load_holes() {
+        let mut painting = Painting::default();
+        let two_holes = find_preset("Two holes").unwrap().polygon;
+        let other = find_preset("Seven vertices").unwrap().polygon;
+        painting.load_polygons(vec![two_holes.clone(), other.clone()]);
+        assert_eq!(painting.holes, two_holes.holes);
+        assert_eq!(painting.other_polygons, vec![other.clone()]);
+
+        painting.execute(painting.clear_command());
+        assert!(painting.holes.is_empty());
+        assert!(painting.other_polygons.is_empty());
+        painting.undo();
+        assert_eq!(painting.points, two_holes.exterior);
+        assert_eq!(painting.holes, two_holes.holes);
+        assert_eq!(painting.other_polygons, vec![other.clone()]);
+
+        // Undo the load, the default painting has neither holes nor other polygons.
+        painting.undo();
+        assert_eq!(painting.points, Painting::default().points);
+        assert!(painting.holes.is_empty());
+        assert!(painting.other_polygons.is_empty());
+        painting.redo();
+        assert_eq!(painting.holes, two_holes.holes);
+        assert_eq!(painting.other_polygons, vec![other]);
+    }
+
+    #[test]
+    fn test_project_round_trip() {
+        let mut painting = Painting {
+            points: seven_vertices(),
+            show_guards: true,
+            ..Default::default()
+        };
+        painting.triangulate();
+        painting.color_from(1);
+        painting.select_face(Some(3));
+        let project = painting.to_project();
+        assert_eq!(project.algorithm, Some(Algorithm::Guards));
+
+        let mut opened = Painting::default();
+        opened.load_project(Project::from_json(&project.to_json()).unwrap());
+        assert_eq!(opened.points, painting.points);
+        assert_eq!(opened.color_classes, painting.color_classes);
+        assert_eq!(opened.selected_face(), Some(3));
+        assert_eq!(opened.to_project(), project);
+    }
+
+    #[test]
+    fn test_project_partition() {
+        let mut painting = Painting {
+            points: seven_vertices(),
+            partition_only: true,
+            ..Default::default()
+        };
+        painting.triangulate();
+        assert!(painting.triangulated);
+        // A split and a merge vertex, each needs a diagonal.
+        let pieces = monotone_partition_polygon(&painting.points).faces.len();
+        assert_eq!(pieces, 3);
+        assert_eq!(painting.dcel.faces.len(), pieces);
+        let project = painting.to_project();
+        assert_eq!(project.algorithm, Some(Algorithm::Partition));
+
+        let mut opened = Painting::default();
+        opened.load_project(Project::from_json(&project.to_json()).unwrap());
+        assert!(opened.partition_only);
+        assert_eq!(opened.dcel.faces.len(), pieces);
+        assert!(opened.color_classes.is_empty());
+        assert_eq!(opened.to_project(), project);
+    }
+
+    #[test]
+    fn test_project_invalid_view() {
+        let mut project = Painting {
+            points: seven_vertices(),
+            ..Default::default()
+        }
+        .to_project();
+        project.view.camera.zoom = 0.;
+        project.view.stroke_width = f32::NAN;
+        project.view.radius = -1.;
+
+        let mut opened = Painting::default();
+        opened.load_project(project);
+        assert_eq!(opened.camera.zoom, Camera::MIN_ZOOM);
+        assert_eq!(opened.stroke_width, Painting::default().stroke_width);
+        assert_eq!(opened.radius, Painting::default().radius);
+        // Panics on a zoom of zero.
+        let rect = egui::Rect::from_min_size(pos2(0., 0.), egui::vec2(800., 500.));
+        let _ = opened.camera.screen_to_model(rect);
+    }
 
     #[test]
     fn test_kdtree() {
@@ -0,0 +1,345 @@
+use crate::holes::{bridge_holes, validate_polygon_with_holes};
+use crate::import::{parse_polygons, InputFormat, PolygonWithHoles};
+use crate::monotone_triangulation::{partition_into_monotone, triangulate_monotone_polygons};
+use crate::monotone_y_partition::{vertex_types, VertexType};
+use crate::vertex_coloring::three_coloring;
+use egui::Pos2;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Number of vertices of each monotone partition type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct VertexTypeCounts {
+    pub start: usize,
+    pub end: usize,
+    pub regular: usize,
+    pub split: usize,
+    pub merge: usize,
+}
+
+impl VertexTypeCounts {
+    pub fn new(types: &[VertexType]) -> Self {
+        let mut ret = Self::default();
+        for vertex_type in types.iter() {
+            match vertex_type {
+                VertexType::StartVertex => ret.start += 1,
+                VertexType::EndVertex => ret.end += 1,
+                VertexType::RegularVetex => ret.regular += 1,
+                VertexType::SplitVertex => ret.split += 1,
+                VertexType::MergeVertex => ret.merge += 1,
+            }
+        }
+        ret
+    }
+}
+
+/// Wall time of every phase in milliseconds.\
+/// `parse` is the time spent on the whole file the polygon came from,
+/// `partition` includes bridging the holes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct PhaseTimings {
+    pub parse: f64,
+    pub validation: f64,
+    pub partition: f64,
+    pub triangulation: f64,
+    pub coloring: f64,
+}
+
+/// Shape quality of the triangles.\
+/// Aspect ratio is circumradius / (2 * inradius), 1 for an equilateral
+/// triangle and growing as triangles get slivery.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TriangleQuality {
+    /// Smallest interior angle in degrees.
+    pub min_angle: f64,
+    /// Largest interior angle in degrees.
+    pub max_angle: f64,
+    pub mean_aspect_ratio: f64,
+    pub max_aspect_ratio: f64,
+    pub min_area: f64,
+}
+
+impl TriangleQuality {
+    /// Quality of triangles given by their vertices' coordinates,
+    /// all zeros if there is no triangle.
+    pub fn new(triangles: &[[Pos2; 3]]) -> Self {
+        if triangles.is_empty() {
+            return Self::default();
+        }
+        let mut ret = Self {
+            min_angle: f64::MAX,
+            min_area: f64::MAX,
+            ..Default::default()
+        };
+        let mut aspect_sum = 0.;
+        for triangle in triangles.iter() {
+            let [a, b, c] = triangle.map(|p| [p.x as f64, p.y as f64]);
+            let length = |p: [f64; 2], q: [f64; 2]| (p[0] - q[0]).hypot(p[1] - q[1]);
+            let edges = [length(b, c), length(c, a), length(a, b)];
+            let area = ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() / 2.;
+            for i in 0..3 {
+                let (opposite, x, y) = (edges[i], edges[(i + 1) % 3], edges[(i + 2) % 3]);
+                let cos = ((x * x + y * y - opposite * opposite) / (2. * x * y)).clamp(-1., 1.);
+                let angle = cos.acos().to_degrees();
+                ret.min_angle = ret.min_angle.min(angle);
+                ret.max_angle = ret.max_angle.max(angle);
+            }
+            let semi_perimeter = edges.iter().sum::<f64>() / 2.;
+            let aspect = if area > 0. {
+                edges.iter().product::<f64>() * semi_perimeter / (8. * area * area)
+            } else {
+                f64::INFINITY
+            };
+            aspect_sum += aspect;
+            ret.max_aspect_ratio = ret.max_aspect_ratio.max(aspect);
+            ret.min_area = ret.min_area.min(area);
+        }
+        ret.mean_aspect_ratio = aspect_sum / triangles.len() as f64;
+        ret
+    }
+}
+
+/// Statistics of one polygon, or of a file that couldn't be read or parsed,
+/// in which case `polygon` is None.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct PolygonReport {
+    pub file: PathBuf,
+    pub polygon: Option<usize>,
+    pub vertices: usize,
+    pub holes: usize,
+    pub triangles: usize,
+    pub diagonals: usize,
+    pub vertex_types: VertexTypeCounts,
+    pub timings: PhaseTimings,
+    pub quality: TriangleQuality,
+    pub error: Option<String>,
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.
+}
+
+/// Triangulate and 3-color a ccw polygon, timing every phase.\
+/// Holes are bridged to the exterior, vertex types are those of the
+/// bridged ring. An invalid polygon is reported in `error`.
+pub fn polygon_report(polygon: &PolygonWithHoles) -> PolygonReport {
+    let mut ret = PolygonReport {
+        vertices: polygon.exterior.len() + polygon.holes.iter().map(Vec::len).sum::<usize>(),
+        holes: polygon.holes.len(),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let valid = validate_polygon_with_holes(&polygon.exterior, &polygon.holes);
+    ret.timings.validation = elapsed_ms(start);
+    if let Err(err) = valid {
+        ret.error = Some(err.to_string());
+        return ret;
+    }
+
+    let start = Instant::now();
+    let bridged = bridge_holes(&polygon.exterior, &polygon.holes);
+    let vertices = bridged.vertices.as_slice();
+    let (mut poly, monotone_polygons) = partition_into_monotone(vertices, None);
+    ret.timings.partition = elapsed_ms(start);
+    ret.vertex_types = VertexTypeCounts::new(&vertex_types(vertices));
+
+    let start = Instant::now();
+    triangulate_monotone_polygons(&mut poly, monotone_polygons, vertices, None);
+    poly.partition(vertices);
+    ret.timings.triangulation = elapsed_ms(start);
+    ret.triangles = poly.faces.len();
+    ret.diagonals = poly.diagonals.len();
+
+    let start = Instant::now();
+    let colored = three_coloring(&poly, 0, &[]);
+    ret.timings.coloring = elapsed_ms(start);
+    if let Err(err) = colored {
+        ret.error = Some(err.to_string());
+    }
+
+    let triangles: Vec<[Pos2; 3]> = poly
+        .faces
+        .iter()
+        .filter_map(|face| match face.as_ref().borrow().vertices[..] {
+            [a, b, c] => Some([vertices[a], vertices[b], vertices[c]]),
+            _ => None,
+        })
+        .collect();
+    ret.quality = TriangleQuality::new(&triangles);
+    ret
+}
+
+/// Reports of every polygon in a file, format is guessed from extension
+/// if not given.
+pub fn file_report(path: &Path, format: Option<InputFormat>) -> Vec<PolygonReport> {
+    let failed = |error: String| {
+        vec![PolygonReport {
+            file: path.to_owned(),
+            error: Some(error),
+            ..Default::default()
+        }]
+    };
+    let Some(format) = format.or_else(|| InputFormat::from_path(path)) else {
+        return failed("unknown file format".to_owned());
+    };
+    let start = Instant::now();
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => return failed(err.to_string()),
+    };
+    let polygons = match parse_polygons(&text, format) {
+        Ok(polygons) => polygons,
+        Err(err) => return failed(err.to_string()),
+    };
+    let parse = elapsed_ms(start);
+    if polygons.is_empty() {
+        return failed("no polygon found".to_owned());
+    }
+
+    polygons
+        .iter()
+        .enumerate()
+        .map(|(idx, polygon)| {
+            let mut report = polygon_report(polygon);
+            report.file = path.to_owned();
+            report.polygon = Some(idx);
+            report.timings.parse = parse;
+            report
+        })
+        .collect()
+}
+
+/// Reports of every polygon file in `dir`, sorted by file name.\
+/// Files with unknown extensions are skipped unless `format` is given,
+/// sub-directories aren't visited.
+pub fn batch_report(
+    dir: &Path,
+    format: Option<InputFormat>,
+) -> std::io::Result<Vec<PolygonReport>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && (format.is_some() || InputFormat::from_path(&path).is_some()) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    info!(
+        "batch processing {} files in {}",
+        paths.len(),
+        dir.display()
+    );
+    Ok(paths
+        .iter()
+        .flat_map(|path| file_report(path, format))
+        .collect())
+}
+
+/// One CSV row per report, with a header line.
+pub fn reports_to_csv(reports: &[PolygonReport]) -> String {
+    let mut ret = String::from(
+        "file,polygon,vertices,holes,triangles,diagonals,\
+         start,end,regular,split,merge,\
+         parse_ms,validation_ms,partition_ms,triangulation_ms,coloring_ms,\
+         min_angle,max_angle,mean_aspect_ratio,max_aspect_ratio,min_area,error\n",
+    );
+    for report in reports.iter() {
+        let types = &report.vertex_types;
+        let timings = &report.timings;
+        let quality = &report.quality;
+        writeln!(
+            ret,
+            "{},{},{},{},{},{},{},{},{},{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.4},{:.4},{:.4},{:.4},{},{}",
+            csv_field(&report.file.display().to_string()),
+            report.polygon.map(|x| x.to_string()).unwrap_or_default(),
+            report.vertices,
+            report.holes,
+            report.triangles,
+            report.diagonals,
+            types.start,
+            types.end,
+            types.regular,
+            types.split,
+            types.merge,
+            timings.parse,
+            timings.validation,
+            timings.partition,
+            timings.triangulation,
+            timings.coloring,
+            quality.min_angle,
+            quality.max_angle,
+            quality.mean_aspect_ratio,
+            quality.max_aspect_ratio,
+            quality.min_area,
+            csv_field(report.error.as_deref().unwrap_or_default()),
+        )
+        .unwrap();
+    }
+    ret
+}
+
+/// Quote a field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Reports as a pretty printed JSON array.
+pub fn reports_to_json(reports: &[PolygonReport]) -> String {
+    serde_json::to_string_pretty(reports).expect("reports are serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch_report, reports_to_csv, TriangleQuality};
+    use egui::pos2;
+
+    #[test]
+    fn test_batch_report() {
+        let dir = std::env::temp_dir().join(format!("triangulate-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.wkt"),
+            "POLYGON((157 29, 308 173, 481 49, 624 180, 500 349, 378 286, 185 333, 157 29))\n\
+             POLYGON((0 0, 10 10, 10 0, 0 10, 0 0))\n\
+             POLYGON((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6, 4 4))",
+        )
+        .unwrap();
+        std::fs::write(dir.join("b.csv"), "x,y\n0,0\n1,oops\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a polygon").unwrap();
+
+        let reports = batch_report(&dir, None).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(reports.len(), 4);
+        let first = &reports[0];
+        assert_eq!((first.polygon, first.error.as_deref()), (Some(0), None));
+        assert_eq!((first.triangles, first.diagonals), (5, 4));
+        let types = first.vertex_types;
+        assert_eq!(
+            types.start + types.end + types.regular + types.split + types.merge,
+            7
+        );
+        assert!(first.quality.min_angle > 0. && first.quality.max_aspect_ratio >= 1.);
+        // Bowtie.
+        assert!(reports[1].error.is_some());
+        // 8 vertices and a hole.
+        let square = &reports[2];
+        assert_eq!((square.holes, square.error.as_deref()), (1, None));
+        assert_eq!(square.triangles, 8 + 2 - 2);
+        assert_eq!(reports[3].polygon, None);
+        assert!(reports[3].error.as_ref().unwrap().starts_with("3:"));
+        assert_eq!(reports_to_csv(&reports).lines().count(), 5);
+
+        let equilateral = [[pos2(0., 0.), pos2(2., 0.), pos2(1., 3f32.sqrt())]];
+        let quality = TriangleQuality::new(&equilateral);
+        assert!((quality.max_aspect_ratio - 1.).abs() < 1e-5);
+        assert!((quality.min_angle - 60.).abs() < 1e-3);
+    }
+}
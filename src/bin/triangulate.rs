@@ -0,0 +1,400 @@
+//! Command line front end: read polygons from files or stdin,
+//! run one of the algorithms and write the result.
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use triangulate_rs::{
+    art_gallery_guards, batch_report, bridge_holes, export, monotone_partition_polygon,
+    parse_polygons, reports_to_csv, reports_to_json, three_coloring, validate_polygon_with_holes,
+    Algorithm, ExportFormat, ExportStyle, InputFormat, PartitionPolygon, PolygonWithHoles,
+    RandomMethod, RandomSettings, TriangulationData, TWO_OPT_MAX_VERTICES, UNCOLORED,
+};
+
+const USAGE: &str = "\
+Usage: triangulate [OPTIONS] [FILE]...
+
+Read polygons from FILEs (or stdin if none or `-` is given) and
+triangulate them. Holes are bridged to the exterior ring, output vertices
+are those of the bridged ring, where both ends of a bridge appear twice.
+
+Options:
+  -a, --algorithm <NAME>  partition, triangulate, color or guards [default: triangulate]
+  -f, --from <FORMAT>     input format: wkt, geojson, csv, poly or ipe
+                          [default: guessed from file extension]
+  -t, --to <FORMAT>       output format: json, obj, ply, svg, tikz or ipe [default: json]
+  -o, --output <FILE>     write to FILE instead of stdout
+  -s, --start-face <N>    triangle that coloring starts from [default: 0]
+  -r, --random <METHOD>   triangulate a random polygon instead of reading input,
+                          METHOD is space-partition, 2-opt or star
+  -n, --vertices <N>      vertex count of the random polygon, at most 5000 with 2-opt
+                          [default: 20]
+      --holes <N>         holes of the random polygon, fewer if they don't fit [default: 0]
+      --seed <N>          seed of the random polygon [default: 0]
+  -b, --batch <DIR>       triangulate and color every polygon file in DIR and write
+                          a statistics report instead, -t picks csv or json [default: csv]
+  -h, --help              print this help
+
+Figure formats hold a single polygon, with several polygons and --output
+FILE, they are written to FILE-1, FILE-2, ... (before the extension).
+Exits with 1 if any polygon is invalid, 2 on bad usage or unreadable input.";
+
+struct Options {
+    algorithm: Algorithm,
+    input_format: Option<InputFormat>,
+    output_format: ExportFormat,
+    output: Option<PathBuf>,
+    start_face: usize,
+    inputs: Vec<String>,
+    /// Directory to write a statistics report on.
+    batch: Option<PathBuf>,
+    /// Batch report as CSV instead of JSON.
+    csv_report: bool,
+    /// Generate a polygon instead of reading inputs.
+    random: Option<RandomSettings>,
+}
+
+/// Result of one polygon, JSON output is an array of these.
+#[derive(Default, Serialize)]
+struct PolygonResult {
+    /// Input file, `-` for stdin.
+    source: String,
+    /// Index of the polygon in its source.
+    polygon: usize,
+    #[serde(flatten)]
+    data: TriangulationData,
+    /// Vertex indices of monotone pieces, only for partition.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    faces: Vec<Vec<usize>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    guards: Vec<usize>,
+}
+
+fn input_format(name: &str) -> Option<InputFormat> {
+    let name = name.to_ascii_lowercase();
+    InputFormat::ALL
+        .into_iter()
+        .find(|x| x.extensions().contains(&name.as_str()))
+}
+
+fn output_format(name: &str) -> Option<ExportFormat> {
+    let name = name.to_ascii_lowercase();
+    ExportFormat::ALL
+        .into_iter()
+        .find(|x| x.extension() == name || x.label().to_ascii_lowercase() == name)
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        algorithm: Algorithm::Triangulate,
+        input_format: None,
+        output_format: ExportFormat::Json,
+        output: None,
+        start_face: 0,
+        inputs: Vec::new(),
+        batch: None,
+        csv_report: true,
+        random: None,
+    };
+    let mut to = None;
+    let mut random = RandomSettings::default();
+    let mut random_method = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("option {} needs a value", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-a" | "--algorithm" => {
+                let name = value()?;
+                options.algorithm = Algorithm::from_name(&name)
+                    .ok_or_else(|| format!("unknown algorithm `{}`", name))?;
+            }
+            "-f" | "--from" => {
+                let name = value()?;
+                options.input_format = Some(
+                    input_format(&name)
+                        .ok_or_else(|| format!("unknown input format `{}`", name))?,
+                );
+            }
+            "-t" | "--to" => to = Some(value()?),
+            "-r" | "--random" => {
+                let name = value()?;
+                random_method = Some(
+                    RandomMethod::from_name(&name)
+                        .ok_or_else(|| format!("unknown random method `{}`", name))?,
+                );
+            }
+            "-n" | "--vertices" => random.vertices = parse_number(&arg, &value()?)?,
+            "--holes" => random.holes = parse_number(&arg, &value()?)?,
+            "--seed" => random.seed = parse_number(&arg, &value()?)?,
+            "-b" | "--batch" => options.batch = Some(PathBuf::from(value()?)),
+            "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+            "-s" | "--start-face" => {
+                let face = value()?;
+                options.start_face = face
+                    .parse()
+                    .map_err(|_| format!("invalid start face `{}`", face))?;
+            }
+            "-" => options.inputs.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => options.inputs.push(arg),
+        }
+    }
+    match (to, &options.batch) {
+        (Some(name), Some(_)) => match name.to_ascii_lowercase().as_str() {
+            "csv" => options.csv_report = true,
+            "json" => options.csv_report = false,
+            _ => return Err(format!("batch report can't be written as `{}`", name)),
+        },
+        (Some(name), None) => {
+            options.output_format =
+                output_format(&name).ok_or_else(|| format!("unknown output format `{}`", name))?;
+        }
+        (None, _) => (),
+    }
+    if random_method.is_none() && random != RandomSettings::default() {
+        return Err("--vertices, --holes and --seed need --random".to_owned());
+    }
+    if let Some(method) = random_method {
+        if !options.inputs.is_empty() || options.batch.is_some() {
+            return Err("--random doesn't take input files".to_owned());
+        }
+        if random.vertices < 3 {
+            return Err("random polygon needs at least 3 vertices".to_owned());
+        }
+        if method == RandomMethod::TwoOpt && random.vertices > TWO_OPT_MAX_VERTICES {
+            return Err(format!(
+                "2-opt polygons can have at most {} vertices, it's O(n²) per pass",
+                TWO_OPT_MAX_VERTICES
+            ));
+        }
+        options.random = Some(RandomSettings { method, ..random });
+        options.inputs.push("random".to_owned());
+    }
+    if options.batch.is_some() && !options.inputs.is_empty() {
+        return Err("--batch doesn't take input files".to_owned());
+    }
+    if options.algorithm == Algorithm::Partition
+        && matches!(options.output_format, ExportFormat::Obj | ExportFormat::Ply)
+    {
+        // Mesh formats only hold triangles, monotone pieces would be dropped.
+        return Err(format!(
+            "partition can't be written as {}",
+            options.output_format.label()
+        ));
+    }
+    if options.inputs.is_empty() {
+        options.inputs.push("-".to_owned());
+    }
+    Ok(Some(options))
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` of {}", value, option))
+}
+
+/// Read and parse one input, `-` is stdin.
+fn read_input(
+    source: &str,
+    format: Option<InputFormat>,
+) -> Result<Vec<triangulate_rs::PolygonWithHoles>, String> {
+    let format = match (format, source) {
+        (Some(format), _) => format,
+        (None, "-") => return Err("-: input format of stdin must be given with --from".to_owned()),
+        (None, _) => InputFormat::from_path(Path::new(source))
+            .ok_or_else(|| format!("{}: can't guess input format, use --from", source))?,
+    };
+    let mut text = String::new();
+    let read = if source == "-" {
+        std::io::stdin().read_to_string(&mut text).map(|_| ())
+    } else {
+        std::fs::read_to_string(source).map(|x| text = x)
+    };
+    read.map_err(|err| format!("{}: {}", source, err))?;
+    parse_polygons(&text, format).map_err(|err| format!("{}:{}", source, err))
+}
+
+/// Run `algorithm` on a polygon with ccw exterior, which must be valid,
+/// its holes are bridged to the exterior first.
+fn run(
+    polygon: &PolygonWithHoles,
+    algorithm: Algorithm,
+    start_face: usize,
+) -> Result<PolygonResult, String> {
+    validate_polygon_with_holes(&polygon.exterior, &polygon.holes)
+        .map_err(|err| err.to_string())?;
+    let bridged = bridge_holes(&polygon.exterior, &polygon.holes);
+    let vertices = bridged.vertices.as_slice();
+    let mut result = PolygonResult::default();
+    if algorithm == Algorithm::Partition {
+        let poly = monotone_partition_polygon(vertices);
+        result.data = TriangulationData::new(vertices, &poly, &[]);
+        result.faces = face_vertices(&poly);
+        return Ok(result);
+    }
+
+    let poly = triangulate_rs::polygon_triangulation(vertices, None);
+    let mut classes = Vec::new();
+    if matches!(algorithm, Algorithm::Color | Algorithm::Guards) {
+        classes = three_coloring(&poly, start_face, &[]).map_err(|err| err.to_string())?;
+    }
+    if algorithm == Algorithm::Guards {
+        result.guards = art_gallery_guards(&classes);
+    }
+    result.data = TriangulationData::new(vertices, &poly, &classes);
+    Ok(result)
+}
+
+fn face_vertices(poly: &PartitionPolygon) -> Vec<Vec<usize>> {
+    poly.faces
+        .iter()
+        .map(|face| face.as_ref().borrow().vertices.clone())
+        .collect()
+}
+
+/// `path` with `-n` appended to its file stem.
+fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, n, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// Data drawn in figures, only guards are colored if there are any.
+fn figure_data(result: &PolygonResult) -> TriangulationData {
+    let mut data = result.data.clone();
+    if !result.guards.is_empty() {
+        for (idx, class) in data.color_classes.iter_mut().enumerate() {
+            if !result.guards.contains(&idx) {
+                *class = UNCOLORED;
+            }
+        }
+    }
+    data
+}
+
+fn write_output(path: Option<&Path>, text: &str) -> Result<(), String> {
+    match path {
+        Some(path) => {
+            std::fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))
+        }
+        None => std::io::stdout()
+            .write_all(text.as_bytes())
+            .map_err(|err| format!("stdout: {}", err)),
+    }
+}
+
+/// Write a report on every polygon file in `dir`.
+fn batch(dir: &Path, options: &Options) -> ExitCode {
+    let reports = match batch_report(dir, options.input_format) {
+        Ok(reports) => reports,
+        Err(err) => {
+            eprintln!("error: {}: {}", dir.display(), err);
+            return ExitCode::from(2);
+        }
+    };
+    let text = if options.csv_report {
+        reports_to_csv(&reports)
+    } else {
+        reports_to_json(&reports) + "\n"
+    };
+    if let Err(err) = write_output(options.output.as_deref(), &text) {
+        eprintln!("error: {}", err);
+        return ExitCode::from(2);
+    }
+    let failed = reports.iter().filter(|x| x.error.is_some()).count();
+    if failed > 0 {
+        eprintln!("{} of {} polygons failed", failed, reports.len());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if let Some(dir) = options.batch.as_deref() {
+        return batch(dir, &options);
+    }
+
+    let mut results = Vec::new();
+    let mut invalid = false;
+    for source in options.inputs.iter() {
+        let polygons = match options.random {
+            Some(settings) => Ok(vec![settings.generate()]),
+            None => read_input(source, options.input_format),
+        };
+        let polygons = match polygons {
+            Ok(polygons) => polygons,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return ExitCode::from(2);
+            }
+        };
+        if polygons.is_empty() {
+            eprintln!("warning: {}: no polygon found", source);
+        }
+        for (idx, polygon) in polygons.iter().enumerate() {
+            match run(polygon, options.algorithm, options.start_face) {
+                Ok(result) => results.push(PolygonResult {
+                    source: source.clone(),
+                    polygon: idx,
+                    ..result
+                }),
+                Err(err) => {
+                    eprintln!("error: {} polygon {}: {}", source, idx, err);
+                    invalid = true;
+                }
+            }
+        }
+    }
+
+    let written = if options.output_format == ExportFormat::Json {
+        let mut text = serde_json::to_string_pretty(&results).expect("results are serializable");
+        text.push('\n');
+        write_output(options.output.as_deref(), &text)
+    } else if results.len() > 1 && options.output.is_none() {
+        Err(format!(
+            "{} output holds a single polygon, use --output for {} polygons",
+            options.output_format.label(),
+            results.len()
+        ))
+    } else {
+        let style = ExportStyle::default();
+        results.iter().enumerate().try_for_each(|(n, result)| {
+            let text = export(&figure_data(result), options.output_format, &style);
+            match options.output.as_deref() {
+                Some(path) if results.len() > 1 => {
+                    write_output(Some(&numbered_path(path, n + 1)), &text)
+                }
+                path => write_output(path, &text),
+            }
+        })
+    };
+    if let Err(err) = written {
+        eprintln!("error: {}", err);
+        return ExitCode::from(2);
+    }
+    if invalid {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,102 @@
+use crate::transform_pos::TransformPos;
+use egui::{pos2, vec2, Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// Camera maps the model space, where polygon vertices live, to the screen.\
+/// Model space uses conventional coordinate system (y axis points up),
+/// so stored vertices don't depend on window size or view.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Camera {
+    /// Model position shown at the center of painting area.
+    pub center: Pos2,
+    /// Screen pixels per model unit.
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            // Center of the 800x500 canvas that example polygons were drawn on.
+            center: pos2(400., 250.),
+            zoom: 1.,
+        }
+    }
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 0.01;
+    pub const MAX_ZOOM: f32 = 100.;
+
+    /// Transform from model space to the screen, given painting area `rect`.
+    pub fn model_to_screen(&self, rect: Rect) -> TransformPos {
+        TransformPos::from_translation(rect.center().to_vec2())
+            * TransformPos::from_scaling(vec2(self.zoom, -self.zoom))
+            * TransformPos::from_translation(-self.center.to_vec2())
+    }
+
+    /// Transform from the screen to model space, given painting area `rect`.
+    pub fn screen_to_model(&self, rect: Rect) -> TransformPos {
+        self.model_to_screen(rect)
+            .inverse()
+            .expect("zoom is clamped to be positive")
+    }
+
+    /// Zoom by `factor`, the model position under `screen_pos` stays where it is.
+    pub fn zoom_at(&mut self, rect: Rect, screen_pos: Pos2, factor: f32) {
+        let anchor = self.screen_to_model(rect) * screen_pos;
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let offset = screen_pos - rect.center();
+        self.center = anchor - vec2(offset.x, -offset.y) / self.zoom;
+    }
+
+    /// Move the view by `screen_delta` pixels, e.g. the pointer's drag delta.
+    pub fn pan(&mut self, screen_delta: Vec2) {
+        self.center -= vec2(screen_delta.x, -screen_delta.y) / self.zoom;
+    }
+
+    /// Center the view on `points` and zoom so that they fill `rect`,
+    /// leaving `margin` pixels on each side.
+    pub fn fit(&mut self, rect: Rect, points: &[Pos2], margin: f32) {
+        if points.is_empty() {
+            return;
+        }
+        let bounds = Rect::from_points(points);
+        self.center = bounds.center();
+        let available = (rect.size() - Vec2::splat(2. * margin)).max(Vec2::splat(1.));
+        let zoom_x = available.x / bounds.width().max(f32::EPSILON);
+        let zoom_y = available.y / bounds.height().max(f32::EPSILON);
+        self.zoom = zoom_x.min(zoom_y).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Camera;
+    use egui::{pos2, Rect};
+
+    #[test]
+    fn test_camera() {
+        let rect = Rect::from_min_max(pos2(0., 20.), pos2(800., 520.));
+        let mut camera = Camera::default();
+        let model = pos2(157., 29.);
+        let screen = camera.model_to_screen(rect) * model;
+        assert!((camera.screen_to_model(rect) * screen).distance(model) < 1e-3);
+        // Model y axis points up.
+        assert!(screen.y > rect.center().y);
+
+        // Position under the cursor stays fixed while zooming.
+        let cursor = pos2(100., 400.);
+        let anchor = camera.screen_to_model(rect) * cursor;
+        camera.zoom_at(rect, cursor, 2.5);
+        assert!((camera.model_to_screen(rect) * anchor).distance(cursor) < 1e-3);
+
+        camera.fit(rect, &[pos2(0., 0.), pos2(10., 0.), pos2(10., 50.)], 10.);
+        assert_eq!(camera.center, pos2(5., 25.));
+        assert_eq!(camera.zoom, 480. / 50.);
+    }
+}
@@ -0,0 +1,372 @@
+//! Flat array API compatible with [earcut](https://github.com/mapbox/earcut),
+//! triangles come from monotone partition instead of ear clipping.
+
+use crate::export::TriangulationData;
+use crate::holes::try_polygon_with_holes_triangulation;
+use crate::import::ParseError;
+use crate::polygon_validation::PolygonError;
+use crate::triangle_base::signed_area;
+use egui::{pos2, Pos2};
+use std::fmt::Display;
+
+/// Reasons why earcut input can't be triangulated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EarcutError {
+    /// Dimensions are less than 2 or don't divide the number of coordinates.
+    Dimensions {
+        coordinates: usize,
+        dimensions: usize,
+    },
+    /// A hole or triangle index isn't a vertex of the input.
+    IndexOutOfRange { index: usize, vertices: usize },
+    /// Rings aren't a valid polygon with holes, indices are vertex indices
+    /// of the input.
+    Polygon(PolygonError),
+}
+
+impl Display for EarcutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EarcutError::Dimensions {
+                coordinates,
+                dimensions,
+            } => write!(
+                f,
+                "{} coordinates can't be split into vertices of {} dimensions",
+                coordinates, dimensions
+            ),
+            EarcutError::IndexOutOfRange { index, vertices } => write!(
+                f,
+                "index {} is out of range of {} vertices",
+                index, vertices
+            ),
+            EarcutError::Polygon(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EarcutError {}
+
+/// Triangulate earcut input, the vertices after every hole index are a hole.\
+/// Like earcut, rings may be in cw or ccw order and their first vertex
+/// may be repeated at the end.
+fn triangulate_ring(
+    vertices: Vec<Pos2>,
+    hole_indices: &[usize],
+) -> Result<Vec<usize>, EarcutError> {
+    let count = vertices.len();
+    let mut starts = vec![0];
+    for index in hole_indices.iter() {
+        if *index > count || *index < starts[starts.len() - 1] {
+            return Err(EarcutError::IndexOutOfRange {
+                index: *index,
+                vertices: count,
+            });
+        }
+        starts.push(*index);
+    }
+    starts.push(count);
+
+    // Rings and the input index of every ring vertex, numbered like
+    // `BridgedPolygon::indices`.
+    let mut rings: Vec<Vec<Pos2>> = Vec::new();
+    let mut original: Vec<usize> = Vec::new();
+    let mut reversed = false;
+    for range in starts.windows(2) {
+        let mut ring = vertices[range[0]..range[1]].to_vec();
+        if ring.len() > 1 && ring.first() == ring.last() {
+            ring.pop();
+        }
+        let mut indices: Vec<usize> = (range[0]..range[0] + ring.len()).collect();
+        if rings.is_empty() && signed_area(&ring) < 0. {
+            reversed = true;
+            ring.reverse();
+            indices.reverse();
+        }
+        rings.push(ring);
+        original.extend(indices);
+    }
+    let exterior = rings.remove(0);
+    let n = exterior.len();
+
+    let (bridged, poly) =
+        try_polygon_with_holes_triangulation(&exterior, &rings, None).map_err(|err| {
+            // Edge i of the reversed exterior is the input edge starting at its vertex i+1.
+            let edge = |idx: usize| {
+                if reversed && idx < n {
+                    original[(idx + 1) % n]
+                } else {
+                    original[idx]
+                }
+            };
+            EarcutError::Polygon(match err {
+                PolygonError::NonFiniteVertex(i) => PolygonError::NonFiniteVertex(original[i]),
+                PolygonError::DuplicateVertex(i, j) => {
+                    PolygonError::DuplicateVertex(original[i], original[j])
+                }
+                PolygonError::SelfIntersection(i, j) => {
+                    PolygonError::SelfIntersection(edge(i), edge(j))
+                }
+                err => err,
+            })
+        })?;
+    Ok(TriangulationData::new(&bridged.vertices, &poly, &[])
+        .triangles
+        .into_iter()
+        .flatten()
+        .map(|idx| original[bridged.indices[idx]])
+        .collect())
+}
+
+/// Vertices of a flat coordinate array, only x and y of every vertex are used.
+fn flat_vertices<T: Copy>(
+    data: &[T],
+    dim: usize,
+    to_pos: impl Fn(T, T) -> Pos2,
+) -> Result<Vec<Pos2>, EarcutError> {
+    if dim < 2 || !data.len().is_multiple_of(dim) {
+        return Err(EarcutError::Dimensions {
+            coordinates: data.len(),
+            dimensions: dim,
+        });
+    }
+    Ok(data.chunks_exact(dim).map(|x| to_pos(x[0], x[1])).collect())
+}
+
+/// Triangulate a polygon given as a flat `[x0, y0, x1, y1, ...]` array like
+/// earcut, every `dim` numbers are a vertex.\
+/// Return three vertex indices per triangle, triangles are in ccw order.\
+/// Holes are bridged to the outer ring, so triangles may not be in the
+/// order earcut would give them.\
+/// Unlike earcut, invalid polygons (e.g. self-intersecting ones) are
+/// errors, not an empty result.
+pub fn earcut(data: &[f32], hole_indices: &[usize], dim: usize) -> Result<Vec<usize>, EarcutError> {
+    triangulate_ring(flat_vertices(data, dim, pos2)?, hole_indices)
+}
+
+/// [`earcut`] of f64 coordinates.\
+/// Triangulation runs on f32, so far or close vertices may become equal.
+pub fn earcut_f64(
+    data: &[f64],
+    hole_indices: &[usize],
+    dim: usize,
+) -> Result<Vec<usize>, EarcutError> {
+    let ring = flat_vertices(data, dim, |x, y| pos2(x as f32, y as f32))?;
+    triangulate_ring(ring, hole_indices)
+}
+
+/// Flat input of earcut, what earcut's `flatten` returns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EarcutData {
+    vertices: Vec<f64>,
+    holes: Vec<usize>,
+    dimensions: usize,
+}
+
+impl EarcutData {
+    /// Checked like the arguments of [`earcut`], every hole must start at a
+    /// vertex.
+    pub fn new(
+        vertices: Vec<f64>,
+        holes: Vec<usize>,
+        dimensions: usize,
+    ) -> Result<Self, EarcutError> {
+        if dimensions < 2 || !vertices.len().is_multiple_of(dimensions) {
+            return Err(EarcutError::Dimensions {
+                coordinates: vertices.len(),
+                dimensions,
+            });
+        }
+        let n = vertices.len() / dimensions;
+        if let Some(index) = holes.iter().find(|idx| **idx >= n) {
+            return Err(EarcutError::IndexOutOfRange {
+                index: *index,
+                vertices: n,
+            });
+        }
+        Ok(EarcutData {
+            vertices,
+            holes,
+            dimensions,
+        })
+    }
+
+    /// Coordinates of all vertices, outer ring first.
+    pub fn vertices(&self) -> &[f64] {
+        &self.vertices
+    }
+
+    /// Index of the first vertex of every hole.
+    pub fn holes(&self) -> &[usize] {
+        &self.holes
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    pub fn triangulate(&self) -> Result<Vec<usize>, EarcutError> {
+        earcut_f64(&self.vertices, &self.holes, self.dimensions)
+    }
+
+    /// Relative difference between the area of the polygon and of the
+    /// triangles, computed like earcut's `deviation`, 0 is a perfect result.\
+    /// Triangle indices must be vertices of the polygon.
+    pub fn deviation(&self, triangles: &[usize]) -> Result<f64, EarcutError> {
+        let dim = self.dimensions;
+        let n = self.vertices.len() / dim;
+        if let Some(index) = triangles.iter().find(|idx| **idx >= n) {
+            return Err(EarcutError::IndexOutOfRange {
+                index: *index,
+                vertices: n,
+            });
+        }
+        let point = |idx: usize| (self.vertices[idx * dim], self.vertices[idx * dim + 1]);
+        // Twice the area of the ring from vertex start to end.
+        let ring_area = |start: usize, end: usize| {
+            let mut area = 0.;
+            for i in start..end {
+                let (p, q) = (point(i), point(if i + 1 == end { start } else { i + 1 }));
+                area += p.0 * q.1 - q.0 * p.1;
+            }
+            f64::abs(area)
+        };
+
+        let mut polygon_area = ring_area(0, self.holes.first().copied().unwrap_or(n));
+        for (i, start) in self.holes.iter().enumerate() {
+            polygon_area -= ring_area(*start, self.holes.get(i + 1).copied().unwrap_or(n));
+        }
+        let triangles_area: f64 = triangles
+            .chunks_exact(3)
+            .map(|x| {
+                let (a, b, c) = (point(x[0]), point(x[1]), point(x[2]));
+                ((a.0 - c.0) * (b.1 - a.1) - (a.0 - b.0) * (c.1 - a.1)).abs()
+            })
+            .sum();
+
+        if polygon_area == 0. && triangles_area == 0. {
+            return Ok(0.);
+        }
+        Ok(((triangles_area - polygon_area) / polygon_area).abs())
+    }
+}
+
+/// Parse earcut's JSON fixture format, an array of rings of `[x, y, ...]`
+/// points, the first ring is the outer one and the rest are holes.\
+/// Dimensions are taken from the first point, like earcut's `flatten`.
+pub fn parse_earcut_fixture(text: &str) -> Result<EarcutData, ParseError> {
+    let rings: Vec<Vec<Vec<f64>>> = serde_json::from_str(text)
+        .map_err(|err| ParseError::new(err.line().max(1), err.column().max(1), err.to_string()))?;
+    let dimensions = rings
+        .first()
+        .and_then(|ring| ring.first())
+        .map_or(2, |point| point.len());
+    if dimensions < 2 {
+        return Err(ParseError::new(
+            1,
+            1,
+            format!("points need x and y, got {} coordinates", dimensions),
+        ));
+    }
+
+    let (mut vertices, mut holes) = (Vec::new(), Vec::new());
+    for (i, ring) in rings.iter().enumerate() {
+        if i > 0 {
+            holes.push(vertices.len() / dimensions);
+        }
+        for (j, point) in ring.iter().enumerate() {
+            if point.len() < dimensions {
+                return Err(ParseError::new(
+                    1,
+                    1,
+                    format!(
+                        "point{} of ring{} has {} coordinates, expected {}",
+                        j,
+                        i,
+                        point.len(),
+                        dimensions
+                    ),
+                ));
+            }
+            vertices.extend_from_slice(&point[..dimensions]);
+        }
+    }
+    EarcutData::new(vertices, holes, dimensions)
+        .map_err(|err| ParseError::new(1, 1, err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_earcut() {
+        // cw ring with z coordinates and a repeated first vertex.
+        let data = [0., 0., 9., 2., 10., 1., 5., 11., 3., 4., 8., 1., 0., 0., 9.];
+        let triangles = earcut(&data, &[], 3).unwrap();
+        assert_eq!(triangles.len(), 6);
+        assert!(triangles.iter().all(|idx| *idx < 4));
+        let data = data.map(|x| x as f64);
+        assert_eq!(earcut_f64(&data, &[], 3).unwrap(), triangles);
+
+        let fixture = parse_earcut_fixture("[[[0,0,9],[2,10,1],[5,11,3],[4,8,1]]]").unwrap();
+        assert_eq!(fixture.vertices(), &data[..12]);
+        assert_eq!(fixture.dimensions(), 3);
+        assert_eq!(fixture.deviation(&fixture.triangulate().unwrap()), Ok(0.));
+        assert_eq!(
+            fixture.deviation(&[0, 1, 4]),
+            Err(EarcutError::IndexOutOfRange {
+                index: 4,
+                vertices: 4
+            })
+        );
+
+        let fixture = parse_earcut_fixture(
+            "[[[0,0],[10,1],[9,10],[1,9]], [[4,4],[5,6],[6,5]], [[2,2],[3,3],[3,2]]]",
+        )
+        .unwrap();
+        assert_eq!(fixture.holes(), &[4, 7]);
+        let triangles = fixture.triangulate().unwrap();
+        assert_eq!(triangles.len() / 3, 10 + 2 * 2 - 2);
+        assert_eq!(fixture.deviation(&triangles), Ok(0.));
+        // Indices of errors refer to the input, holes are any order.
+        let data = [0., 0., 10., 0., 10., 10., 0., 10., 4., 4., 14., 6., 6., 4.];
+        assert_eq!(
+            earcut(&data, &[4], 2),
+            Err(EarcutError::Polygon(PolygonError::SelfIntersection(1, 4)))
+        );
+        assert_eq!(
+            earcut(&data, &[8], 2),
+            Err(EarcutError::IndexOutOfRange {
+                index: 8,
+                vertices: 7
+            })
+        );
+        assert!(parse_earcut_fixture("[[[0,0],[1]]]").is_err());
+        assert!(EarcutData::new(vec![0., 0., 1., 5.], vec![2], 2).is_err());
+        assert!(EarcutData::new(vec![0., 0., 1., 5.], Vec::new(), 0).is_err());
+
+        // Indices of errors refer to the input, even if the ring is reversed.
+        assert_eq!(
+            earcut(&[0., 0., 4., 5., 1., 5., 3., 1.], &[], 2),
+            Err(EarcutError::Polygon(PolygonError::SelfIntersection(0, 2)))
+        );
+        // Horizontal edges.
+        assert_eq!(
+            earcut(&[0., 0., 1., 5., 4., 5., 3., 0.], &[], 2).map(|x| x.len()),
+            Ok(6)
+        );
+        // Coordinates beyond f32 are infinite after the conversion.
+        assert_eq!(
+            earcut_f64(&[0., 0., 1., 5., 1e39, 2.], &[], 2),
+            Err(EarcutError::Polygon(PolygonError::NonFiniteVertex(2)))
+        );
+        assert_eq!(
+            earcut(&[0., 0., 1., 5., 4., 5.], &[], 4),
+            Err(EarcutError::Dimensions {
+                coordinates: 6,
+                dimensions: 4
+            })
+        );
+    }
+}
@@ -0,0 +1,595 @@
+use crate::monotone_y_partition::PartitionPolygon;
+use crate::triangle_base::signed_area;
+use crate::vertex_coloring::COLOR_CLASSES;
+use egui::{Color32, Pos2, Rect};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::iter::zip;
+
+/// Triangulation result in a plain, serializable form.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TriangulationData {
+    pub vertices: Vec<Pos2>,
+    /// Vertex indices of every triangle, in ccw order.
+    pub triangles: Vec<[usize; 3]>,
+    pub diagonals: Vec<(usize, usize)>,
+    /// Color class of every vertex, empty if vertices aren't colored.
+    pub color_classes: Vec<u8>,
+    /// Index of the selected triangle.
+    #[serde(default)]
+    pub selected_face: Option<usize>,
+}
+
+impl TriangulationData {
+    /// Collect triangles and diagonals of a triangulated polygon,
+    /// `color_classes` may be empty.
+    pub fn new(vertices: &[Pos2], poly: &PartitionPolygon, color_classes: &[u8]) -> Self {
+        let triangles = poly
+            .faces
+            .iter()
+            .filter_map(|face| {
+                let face = face.as_ref().borrow();
+                let &[a, b, c] = face.vertices.as_slice() else {
+                    return None;
+                };
+                let coordinates = [vertices[a], vertices[b], vertices[c]];
+                Some(if signed_area(&coordinates) < 0. {
+                    [a, c, b]
+                } else {
+                    [a, b, c]
+                })
+            })
+            .collect();
+        Self {
+            vertices: vertices.to_vec(),
+            triangles,
+            diagonals: poly.diagonals.clone(),
+            color_classes: color_classes.to_vec(),
+            selected_face: None,
+        }
+    }
+
+    fn color_class(&self, idx: usize) -> Option<u8> {
+        self.color_classes
+            .get(idx)
+            .copied()
+            .filter(|x| *x < COLOR_CLASSES)
+    }
+}
+
+/// Colors and sizes shared by figure exporters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExportStyle {
+    pub vertex: Color32,
+    pub label: Color32,
+    pub edge: Color32,
+    pub diagonal: Color32,
+    pub selection: Color32,
+    pub color_classes: [Color32; COLOR_CLASSES as usize],
+    pub stroke_width: f32,
+    pub radius: f32,
+}
+
+impl Default for ExportStyle {
+    fn default() -> Self {
+        Self {
+            vertex: Color32::BLACK,
+            label: Color32::RED,
+            edge: Color32::from_rgb(25, 200, 100),
+            diagonal: Color32::from_rgb(25, 200, 100),
+            selection: Color32::BLACK,
+            color_classes: [Color32::RED, Color32::GREEN, Color32::BLUE],
+            stroke_width: 2.,
+            radius: 5.,
+        }
+    }
+}
+
+/// Names of color classes in TikZ and Ipe figures.
+const CLASS_COLOR_NAMES: [&str; COLOR_CLASSES as usize] =
+    ["tri-class0", "tri-class1", "tri-class2"];
+
+impl ExportStyle {
+    /// Fill color of a vertex, depending on its color class.
+    pub fn vertex_color(&self, data: &TriangulationData, idx: usize) -> Color32 {
+        match data.color_class(idx) {
+            Some(class) => self.color_classes[class as usize],
+            None => self.vertex,
+        }
+    }
+
+    /// Colors with names, so that TikZ and Ipe figures share the same definitions.
+    fn named_colors(&self) -> Vec<(&'static str, Color32)> {
+        let mut ret = vec![
+            ("tri-vertex", self.vertex),
+            ("tri-label", self.label),
+            ("tri-edge", self.edge),
+            ("tri-diagonal", self.diagonal),
+            ("tri-selection", self.selection),
+        ];
+        ret.extend(zip(CLASS_COLOR_NAMES, self.color_classes));
+        ret
+    }
+
+    fn vertex_color_name(&self, data: &TriangulationData, idx: usize) -> &'static str {
+        match data.color_class(idx) {
+            Some(class) => CLASS_COLOR_NAMES[class as usize],
+            None => "tri-vertex",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Obj,
+    Ply,
+    Svg,
+    Json,
+    Tikz,
+    Ipe,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 6] = [
+        ExportFormat::Obj,
+        ExportFormat::Ply,
+        ExportFormat::Svg,
+        ExportFormat::Json,
+        ExportFormat::Tikz,
+        ExportFormat::Ipe,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Obj => "Wavefront OBJ",
+            ExportFormat::Ply => "PLY",
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Tikz => "TikZ",
+            ExportFormat::Ipe => "Ipe",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Obj => "obj",
+            ExportFormat::Ply => "ply",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Json => "json",
+            ExportFormat::Tikz => "tex",
+            ExportFormat::Ipe => "ipe",
+        }
+    }
+}
+
+/// Write triangulation in the given format.
+pub fn export(data: &TriangulationData, format: ExportFormat, style: &ExportStyle) -> String {
+    match format {
+        ExportFormat::Obj => to_obj(data),
+        ExportFormat::Ply => to_ply(data, style),
+        ExportFormat::Svg => to_svg(data, style),
+        ExportFormat::Json => to_json(data),
+        ExportFormat::Tikz => to_tikz(data, style),
+        ExportFormat::Ipe => to_ipe(data, style),
+    }
+}
+
+/// Wavefront OBJ mesh in z = 0 plane, face indices start from 1.
+pub fn to_obj(data: &TriangulationData) -> String {
+    let mut ret = String::from("# triangulate-rs\n");
+    for p in data.vertices.iter() {
+        writeln!(ret, "v {} {} 0", p.x, p.y).unwrap();
+    }
+    for [a, b, c] in data.triangles.iter() {
+        writeln!(ret, "f {} {} {}", a + 1, b + 1, c + 1).unwrap();
+    }
+    ret
+}
+
+/// ASCII PLY mesh in z = 0 plane, vertices carry colors if they're 3-colored.
+pub fn to_ply(data: &TriangulationData, style: &ExportStyle) -> String {
+    let colored = !data.color_classes.is_empty();
+    let mut ret = String::from("ply\nformat ascii 1.0\ncomment triangulate-rs\n");
+    writeln!(ret, "element vertex {}", data.vertices.len()).unwrap();
+    ret.push_str("property float x\nproperty float y\nproperty float z\n");
+    if colored {
+        ret.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\n");
+    }
+    writeln!(ret, "element face {}", data.triangles.len()).unwrap();
+    ret.push_str("property list uchar int vertex_indices\nend_header\n");
+    for (idx, p) in data.vertices.iter().enumerate() {
+        write!(ret, "{} {} 0", p.x, p.y).unwrap();
+        if colored {
+            let color = style.vertex_color(data, idx);
+            write!(ret, " {} {} {}", color.r(), color.g(), color.b()).unwrap();
+        }
+        ret.push('\n');
+    }
+    for [a, b, c] in data.triangles.iter() {
+        writeln!(ret, "3 {} {} {}", a, b, c).unwrap();
+    }
+    ret
+}
+
+/// JSON document with vertices, triangles, diagonals and color classes.
+pub fn to_json(data: &TriangulationData) -> String {
+    serde_json::to_string_pretty(data).expect("triangulation data is serializable")
+}
+
+fn svg_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+/// SVG picture with one layer (group) for each of outline, diagonals,
+/// vertices and labels. Model y axis points up, so it's flipped.
+pub fn to_svg(data: &TriangulationData, style: &ExportStyle) -> String {
+    let margin = style.radius * 4.;
+    let bounds = if data.vertices.is_empty() {
+        Rect::ZERO
+    } else {
+        Rect::from_points(&data.vertices)
+    };
+    let to_svg = |p: &Pos2| (p.x - bounds.min.x + margin, bounds.max.y - p.y + margin);
+
+    let mut ret = String::new();
+    writeln!(
+        ret,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = bounds.width() + 2. * margin,
+        h = bounds.height() + 2. * margin,
+    )
+    .unwrap();
+    let layer = |ret: &mut String, id: &str, label: &str| {
+        writeln!(
+            ret,
+            r#"  <g id="{}" inkscape:groupmode="layer" inkscape:label="{}">"#,
+            id, label
+        )
+        .unwrap();
+    };
+
+    layer(&mut ret, "outline", "Outline");
+    let points = data
+        .vertices
+        .iter()
+        .map(|p| {
+            let (x, y) = to_svg(p);
+            format!("{},{}", x, y)
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    writeln!(
+        ret,
+        r#"    <polygon points="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+        points,
+        svg_color(style.edge),
+        style.stroke_width
+    )
+    .unwrap();
+    ret.push_str("  </g>\n");
+
+    layer(&mut ret, "diagonals", "Diagonals");
+    for (a, b) in data.diagonals.iter() {
+        let ((x1, y1), (x2, y2)) = (to_svg(&data.vertices[*a]), to_svg(&data.vertices[*b]));
+        writeln!(
+            ret,
+            r#"    <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+            x1,
+            y1,
+            x2,
+            y2,
+            svg_color(style.diagonal),
+            style.stroke_width
+        )
+        .unwrap();
+    }
+    ret.push_str("  </g>\n");
+
+    layer(&mut ret, "vertices", "Vertices");
+    for (idx, p) in data.vertices.iter().enumerate() {
+        let (x, y) = to_svg(p);
+        writeln!(
+            ret,
+            r#"    <circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            x,
+            y,
+            style.radius,
+            svg_color(style.vertex_color(data, idx))
+        )
+        .unwrap();
+    }
+    ret.push_str("  </g>\n");
+
+    layer(&mut ret, "labels", "Labels");
+    for (idx, p) in data.vertices.iter().enumerate() {
+        let (x, y) = to_svg(p);
+        writeln!(
+            ret,
+            r#"    <text x="{}" y="{}" font-family="monospace" font-size="15" fill="{}">{}</text>"#,
+            x + style.radius,
+            y + style.radius * 3.,
+            svg_color(style.label),
+            idx
+        )
+        .unwrap();
+    }
+    ret.push_str("  </g>\n</svg>\n");
+    ret
+}
+
+/// Scale and translate vertices so that their bounding box fits into
+/// `width` x `height` at `origin`, keeping aspect ratio.
+fn fit_into(vertices: &[Pos2], origin: Pos2, width: f32, height: f32) -> Vec<Pos2> {
+    if vertices.is_empty() {
+        return Vec::new();
+    }
+    let bounds = Rect::from_points(vertices);
+    let scale =
+        (width / bounds.width().max(f32::EPSILON)).min(height / bounds.height().max(f32::EPSILON));
+    vertices
+        .iter()
+        .map(|p| origin + (*p - bounds.min) * scale)
+        .collect()
+}
+
+fn tikz_color(color: Color32) -> String {
+    format!("{{RGB}}{{{},{},{}}}", color.r(), color.g(), color.b())
+}
+
+/// Standalone LaTeX document with a TikZ picture, 12cm wide at most.\
+/// Named colors and styles are defined up front, so they can be tweaked
+/// in one place.
+pub fn to_tikz(data: &TriangulationData, style: &ExportStyle) -> String {
+    let pts = fit_into(&data.vertices, Pos2::ZERO, 12., 12.);
+    let mut ret = String::from("\\documentclass[tikz]{standalone}\n");
+    for (name, color) in style.named_colors() {
+        writeln!(ret, "\\definecolor{{{}}}{}", name, tikz_color(color)).unwrap();
+    }
+    ret.push_str("\\begin{document}\n");
+    writeln!(
+        ret,
+        "\\begin{{tikzpicture}}[\n  \
+         edge/.style={{draw=tri-edge, line width={lw}pt, line join=round}},\n  \
+         diagonal/.style={{draw=tri-diagonal, line width={lw}pt}},\n  \
+         selection/.style={{fill=tri-selection, fill opacity=0.2}},\n  \
+         vertex/.style={{circle, inner sep=0pt, minimum size={size}pt}},\n  \
+         vlabel/.style={{text=tri-label, font=\\scriptsize\\ttfamily, below right}}]",
+        lw = style.stroke_width * 0.4,
+        size = style.radius * 0.8,
+    )
+    .unwrap();
+    let coordinate = |idx: usize| format!("({:.4},{:.4})", pts[idx].x, pts[idx].y);
+
+    if let Some([a, b, c]) = data.selected_face.and_then(|x| data.triangles.get(x)) {
+        writeln!(
+            ret,
+            "  \\fill[selection] {} -- {} -- {} -- cycle;",
+            coordinate(*a),
+            coordinate(*b),
+            coordinate(*c)
+        )
+        .unwrap();
+    }
+    for (a, b) in data.diagonals.iter() {
+        writeln!(
+            ret,
+            "  \\draw[diagonal] {} -- {};",
+            coordinate(*a),
+            coordinate(*b)
+        )
+        .unwrap();
+    }
+    if !pts.is_empty() {
+        let outline = (0..pts.len()).map(coordinate).collect::<Vec<String>>();
+        writeln!(ret, "  \\draw[edge] {} -- cycle;", outline.join(" -- ")).unwrap();
+    }
+    for idx in 0..pts.len() {
+        let color = style.vertex_color_name(data, idx);
+        writeln!(
+            ret,
+            "  \\node[vertex, fill={}] at {} {{}};\n  \\node[vlabel] at {} {{{}}};",
+            color,
+            coordinate(idx),
+            coordinate(idx),
+            idx
+        )
+        .unwrap();
+    }
+    ret.push_str("\\end{tikzpicture}\n\\end{document}\n");
+    ret
+}
+
+fn ipe_color(color: Color32) -> String {
+    let channel = |x: u8| format!("{:.3}", x as f32 / 255.);
+    format!(
+        "{} {} {}",
+        channel(color.r()),
+        channel(color.g()),
+        channel(color.b())
+    )
+}
+
+/// Ipe XML document, fitted into an A4 page, with one layer
+/// for each of selection, outline, diagonals, vertices and labels.
+pub fn to_ipe(data: &TriangulationData, style: &ExportStyle) -> String {
+    let pts = fit_into(&data.vertices, Pos2::new(64., 64.), 467., 714.);
+    let mut ret = String::from(
+        "<?xml version=\"1.0\"?>\n<!DOCTYPE ipe SYSTEM \"ipe.dtd\">\n\
+         <ipe version=\"70218\" creator=\"triangulate-rs\">\n<ipestyle name=\"triangulate\">\n",
+    );
+    for (name, color) in style.named_colors() {
+        writeln!(
+            ret,
+            "<color name=\"{}\" value=\"{}\"/>",
+            name,
+            ipe_color(color)
+        )
+        .unwrap();
+    }
+    // The vertex mark and sizes are defined here too, so the figure doesn't
+    // need Ipe's basic stylesheet.
+    writeln!(
+        ret,
+        "<pen name=\"tri-pen\" value=\"{}\"/>\n<opacity name=\"tri-selection\" value=\"0.2\"/>\n\
+         <symbolsize name=\"tri-mark\" value=\"{}\"/>\n<textsize name=\"tri-text\" value=\"\\small\"/>\n\
+         <symbol name=\"mark/tri-disk(sx)\" transformations=\"translations\">\n\
+         <path fill=\"sym-stroke\">\n0.5 0 0 0.5 0 0 e\n</path>\n</symbol>\n\
+         </ipestyle>\n<page>",
+        style.stroke_width * 0.4,
+        style.radius * 0.8
+    )
+    .unwrap();
+    let layers = ["selection", "outline", "diagonals", "vertices", "labels"];
+    for layer in layers {
+        writeln!(ret, "<layer name=\"{}\"/>", layer).unwrap();
+    }
+    writeln!(
+        ret,
+        "<view layers=\"{}\" active=\"outline\"/>",
+        layers.join(" ")
+    )
+    .unwrap();
+
+    let point = |idx: usize| format!("{:.3} {:.3}", pts[idx].x, pts[idx].y);
+    // Outline is the first closed path, which parse_ipe reads back as polygon.
+    if !pts.is_empty() {
+        ret.push_str("<path layer=\"outline\" stroke=\"tri-edge\" pen=\"tri-pen\">\n");
+        for idx in 0..pts.len() {
+            let op = if idx == 0 { "m" } else { "l" };
+            writeln!(ret, "{} {}", point(idx), op).unwrap();
+        }
+        ret.push_str("h\n</path>\n");
+    }
+    if let Some([a, b, c]) = data.selected_face.and_then(|x| data.triangles.get(x)) {
+        writeln!(
+            ret,
+            "<path layer=\"selection\" fill=\"tri-selection\" opacity=\"tri-selection\">\n\
+             {} m\n{} l\n{} l\nh\n</path>",
+            point(*a),
+            point(*b),
+            point(*c)
+        )
+        .unwrap();
+    }
+    for (a, b) in data.diagonals.iter() {
+        writeln!(
+            ret,
+            "<path layer=\"diagonals\" stroke=\"tri-diagonal\" pen=\"tri-pen\">\n{} m\n{} l\n</path>",
+            point(*a),
+            point(*b)
+        )
+        .unwrap();
+    }
+    for idx in 0..pts.len() {
+        let color = style.vertex_color_name(data, idx);
+        writeln!(
+            ret,
+            "<use layer=\"vertices\" name=\"mark/tri-disk(sx)\" pos=\"{}\" size=\"tri-mark\" stroke=\"{}\"/>",
+            point(idx),
+            color
+        )
+        .unwrap();
+    }
+    for idx in 0..pts.len() {
+        writeln!(
+            ret,
+            "<text layer=\"labels\" transformations=\"translations\" pos=\"{}\" stroke=\"tri-label\" \
+             type=\"label\" size=\"tri-text\" halign=\"left\" valign=\"top\">{}</text>",
+            point(idx),
+            idx
+        )
+        .unwrap();
+    }
+    ret.push_str("</page>\n</ipe>\n");
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_ipe, to_json, to_obj, to_ply, to_svg, to_tikz, ExportStyle, TriangulationData};
+    use crate::import::parse_ipe;
+    use crate::monotone_triangulation::polygon_triangulation;
+    use crate::triangle_base::signed_area;
+    use crate::vertex_coloring::three_coloring;
+    use egui::Pos2;
+
+    #[test]
+    fn test_export() {
+        let pts = vec![
+            Pos2::new(157., 29.),
+            Pos2::new(308., 173.),
+            Pos2::new(481., 49.),
+            Pos2::new(624., 180.),
+            Pos2::new(500., 349.),
+            Pos2::new(378., 286.),
+            Pos2::new(185., 333.),
+        ];
+        let poly = polygon_triangulation(&pts, None);
+        let classes = three_coloring(&poly, 0, &[]).unwrap();
+        let data = TriangulationData::new(&pts, &poly, &classes);
+        assert_eq!(data.triangles.len(), pts.len() - 2);
+        for [a, b, c] in data.triangles.iter() {
+            assert!(signed_area(&[pts[*a], pts[*b], pts[*c]]) > 0.);
+        }
+
+        let obj = to_obj(&data);
+        assert_eq!(obj.lines().filter(|x| x.starts_with("v ")).count(), 7);
+        assert_eq!(obj.lines().filter(|x| x.starts_with("f ")).count(), 5);
+
+        let ply = to_ply(&data, &ExportStyle::default());
+        assert!(ply.contains("element vertex 7\n"));
+        assert!(ply.contains("property uchar red\n"));
+        assert_eq!(ply.lines().filter(|x| x.starts_with("3 ")).count(), 5);
+
+        let svg = to_svg(&data, &ExportStyle::default());
+        for layer in ["outline", "diagonals", "vertices", "labels"] {
+            assert!(svg.contains(&format!("<g id=\"{}\"", layer)));
+        }
+        assert_eq!(svg.matches("<line ").count(), data.diagonals.len());
+
+        let json: TriangulationData = serde_json::from_str(&to_json(&data)).unwrap();
+        assert_eq!(json, data);
+    }
+
+    #[test]
+    fn test_figure_export() {
+        let pts = vec![
+            Pos2::new(157., 29.),
+            Pos2::new(308., 173.),
+            Pos2::new(481., 49.),
+            Pos2::new(624., 180.),
+            Pos2::new(500., 349.),
+            Pos2::new(378., 286.),
+            Pos2::new(185., 333.),
+        ];
+        let poly = polygon_triangulation(&pts, None);
+        let classes = three_coloring(&poly, 0, &[]).unwrap();
+        let mut data = TriangulationData::new(&pts, &poly, &classes);
+        data.selected_face = Some(0);
+
+        let tikz = to_tikz(&data, &ExportStyle::default());
+        assert!(tikz.starts_with("\\documentclass[tikz]{standalone}"));
+        assert_eq!(
+            tikz.matches("\\draw[diagonal]").count(),
+            data.diagonals.len()
+        );
+        assert_eq!(tikz.matches("\\fill[selection]").count(), 1);
+
+        // TikZ's own label key isn't overridden.
+        assert!(!tikz.contains(" label/.style"));
+
+        // Ipe figure is read back as the polygon, not as the selected triangle.
+        let ipe = to_ipe(&data, &ExportStyle::default());
+        let polygons = parse_ipe(&ipe).unwrap();
+        assert_eq!(polygons[0].exterior.len(), pts.len());
+        assert!(signed_area(&polygons[0].exterior) > 0.);
+        // Mark and sizes come from the figure's own stylesheet.
+        for defined in [
+            "<symbol name=\"mark/tri-disk(sx)\"",
+            "<symbolsize name=\"tri-mark\"",
+            "<textsize name=\"tri-text\"",
+        ] {
+            assert!(ipe.contains(defined));
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::import::ParseError;
+use std::f32::consts::{E, PI};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+}
+
+impl Function {
+    const ALL: [Function; 10] = [
+        Function::Sin,
+        Function::Cos,
+        Function::Tan,
+        Function::Asin,
+        Function::Acos,
+        Function::Atan,
+        Function::Abs,
+        Function::Sqrt,
+        Function::Exp,
+        Function::Ln,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Asin => "asin",
+            Function::Acos => "acos",
+            Function::Atan => "atan",
+            Function::Abs => "abs",
+            Function::Sqrt => "sqrt",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+        }
+    }
+
+    fn apply(&self, x: f32) -> f32 {
+        match self {
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Asin => x.asin(),
+            Function::Acos => x.acos(),
+            Function::Atan => x.atan(),
+            Function::Abs => x.abs(),
+            Function::Sqrt => x.sqrt(),
+            Function::Exp => x.exp(),
+            Function::Ln => x.ln(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// Arithmetic expression of one variable θ, used for polar curves r(θ).\
+/// Supports `+ - * / ^`, parentheses, `pi`, `e`, and functions like `sin(t)`,
+/// the variable is written `t`, `theta` or `θ`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Number(f32),
+    Theta,
+    Neg(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(Function, Box<Expression>),
+}
+
+impl Expression {
+    /// Parse a single line expression, errors point at the offending column.
+    pub fn parse(text: &str) -> Result<Expression, ParseError> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        let expression = parser.sum()?;
+        parser.skip_spaces();
+        match parser.peek() {
+            None => Ok(expression),
+            Some(c) => Err(parser.error(format!("unexpected `{}`", c))),
+        }
+    }
+
+    pub fn eval(&self, theta: f32) -> f32 {
+        match self {
+            Expression::Number(x) => *x,
+            Expression::Theta => theta,
+            Expression::Neg(x) => -x.eval(theta),
+            Expression::Binary(op, a, b) => {
+                let (a, b) = (a.eval(theta), b.eval(theta));
+                match op {
+                    Operator::Add => a + b,
+                    Operator::Sub => a - b,
+                    Operator::Mul => a * b,
+                    Operator::Div => a / b,
+                    Operator::Pow => a.powf(b),
+                }
+            }
+            Expression::Call(function, x) => function.apply(x.eval(theta)),
+        }
+    }
+}
+
+/// Recursive descent parser, one method per precedence level.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(1, self.pos + 1, message)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Consume `c` if it's the next non-space character.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn sum(&mut self) -> Result<Expression, ParseError> {
+        let mut lhs = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expression::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expression, ParseError> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                Operator::Mul
+            } else if self.eat('/') {
+                Operator::Div
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expression::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, ParseError> {
+        if self.eat('-') {
+            return Ok(Expression::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    /// `^` is right associative and binds tighter than unary minus on its left.
+    fn power(&mut self) -> Result<Expression, ParseError> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(Expression::Binary(
+                Operator::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expression, ParseError> {
+        self.skip_spaces();
+        let start = self.pos;
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.sum()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse().map(Expression::Number).map_err(|_| {
+                    self.pos = start;
+                    self.error(format!("invalid number `{}`", text))
+                })
+            }
+            Some(c) if c.is_alphabetic() => {
+                while self.peek().is_some_and(char::is_alphanumeric) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                match name.as_str() {
+                    "t" | "theta" | "θ" => return Ok(Expression::Theta),
+                    "pi" | "π" => return Ok(Expression::Number(PI)),
+                    "e" => return Ok(Expression::Number(E)),
+                    _ => (),
+                }
+                let Some(function) = Function::ALL.into_iter().find(|x| x.name() == name) else {
+                    self.pos = start;
+                    return Err(self.error(format!("unknown name `{}`", name)));
+                };
+                if !self.eat('(') {
+                    return Err(self.error(format!("expected `(` after {}", name)));
+                }
+                let argument = self.sum()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(Expression::Call(function, Box::new(argument)))
+            }
+            Some(c) => Err(self.error(format!("unexpected `{}`", c))),
+            None => Err(self.error("unexpected end of expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Expression;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_expression() {
+        let eval = |text: &str, theta: f32| Expression::parse(text).unwrap().eval(theta);
+        assert_eq!(eval("1 + 2 * 3", 0.), 7.);
+        assert_eq!(eval("(1 + 2) * 3", 0.), 9.);
+        assert_eq!(eval("2 ^ 3 ^ 2", 0.), 512.);
+        assert_eq!(eval("-2 ^ 2", 0.), -4.);
+        assert_eq!(eval("10 - 4 - 3", 0.), 3.);
+        assert_eq!(eval("2 * theta + t", 1.5), 4.5);
+        assert!((eval("1 + 0.5 * cos(3 * θ)", PI) - 0.5).abs() < 1e-6);
+        assert!((eval("sqrt(abs(-16)) / pi", 0.) - 4. / PI).abs() < 1e-6);
+
+        let err = Expression::parse("1 + foo(t)").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 5));
+        assert_eq!(Expression::parse("2 * (t + 1").unwrap_err().column, 11);
+        assert!(Expression::parse("2t").is_err());
+        assert!(Expression::parse("").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use egui::Pos2;
+
+use crate::import::PolygonWithHoles;
+
+/// An undoable edit of the painting.\
+/// Each command carries enough data to be applied again and to be reverted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    AddPoint {
+        idx: usize,
+        pos: Pos2,
+    },
+    MovePoint {
+        idx: usize,
+        from: Pos2,
+        to: Pos2,
+    },
+    DeletePoint {
+        idx: usize,
+        pos: Pos2,
+    },
+    /// Clear painting, the cleared state is kept for undo.
+    Clear {
+        points: Vec<Pos2>,
+        holes: Vec<Vec<Pos2>>,
+        other_polygons: Vec<PolygonWithHoles>,
+        triangulated: bool,
+        coloring_start: Option<usize>,
+        selected_face: Option<usize>,
+    },
+    /// Replace painting with loaded points, holes and other polygons,
+    /// `cleared` is the Clear command that dropped the previous painting.
+    Load {
+        points: Vec<Pos2>,
+        holes: Vec<Vec<Pos2>>,
+        other_polygons: Vec<PolygonWithHoles>,
+        cleared: Box<Command>,
+    },
+    Triangulate,
+    /// 3-coloring vertices starting from face `to`,
+    /// `from` is the start face of the previous coloring (if any).
+    Coloring {
+        from: Option<usize>,
+        to: usize,
+    },
+    SelectFace {
+        from: Option<usize>,
+        to: Option<usize>,
+    },
+}
+
+impl Command {
+    /// Short description shown in the history list.
+    pub fn description(&self) -> String {
+        match self {
+            Command::AddPoint { idx, pos } => {
+                format!("Add vertex{} ({:.0}, {:.0})", idx, pos.x, pos.y)
+            }
+            Command::MovePoint { idx, to, .. } => {
+                format!("Move vertex{} to ({:.0}, {:.0})", idx, to.x, to.y)
+            }
+            Command::DeletePoint { idx, .. } => format!("Delete vertex{}", idx),
+            Command::Clear { points, .. } => format!("Clear painting ({} vertices)", points.len()),
+            Command::Load { points, .. } => format!("Load polygon ({} vertices)", points.len()),
+            Command::Triangulate => "Triangulate polygon".to_string(),
+            Command::Coloring { to, .. } => format!("3-coloring from face{}", to),
+            Command::SelectFace { to: Some(to), .. } => format!("Select face{}", to),
+            Command::SelectFace { to: None, .. } => "Deselect face".to_string(),
+        }
+    }
+}
+
+/// Unbounded undo/redo history of commands.
+#[derive(Default)]
+pub struct History {
+    done: Vec<Command>,
+    undone: Vec<Command>,
+}
+
+impl History {
+    /// Record an executed command, which discards all undone commands.
+    pub fn push(&mut self, command: Command) {
+        self.done.push(command);
+        self.undone.clear();
+    }
+
+    /// Pop the last executed command, the caller is responsible for reverting it.
+    pub fn undo(&mut self) -> Option<Command> {
+        let command = self.done.pop()?;
+        self.undone.push(command.clone());
+        Some(command)
+    }
+
+    /// Pop the last undone command, the caller is responsible for applying it.
+    pub fn redo(&mut self) -> Option<Command> {
+        let command = self.undone.pop()?;
+        self.done.push(command.clone());
+        Some(command)
+    }
+
+    /// Last executed command, to amend it while it's still being edited.
+    pub fn last_mut(&mut self) -> Option<&mut Command> {
+        self.done.last_mut()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Executed commands, the oldest one comes first.
+    pub fn done(&self) -> &[Command] {
+        &self.done
+    }
+
+    /// Undone commands, the next one to redo comes last.
+    pub fn undone(&self) -> &[Command] {
+        &self.undone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, History};
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        history.push(Command::Triangulate);
+        history.push(Command::SelectFace {
+            from: None,
+            to: Some(1),
+        });
+        assert_eq!(
+            history.undo(),
+            Some(Command::SelectFace {
+                from: None,
+                to: Some(1)
+            })
+        );
+        assert!(history.can_redo());
+        assert_eq!(history.undo(), Some(Command::Triangulate));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(Command::Triangulate));
+
+        // A new command discards undone commands.
+        history.push(Command::Coloring { from: None, to: 0 });
+        assert!(!history.can_redo());
+        assert_eq!(history.done().len(), 2);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::monotone_triangulation::polygon_triangulation;
+use crate::monotone_y_partition::PartitionPolygon;
+use crate::polygon_validation::{validate_polygon, PolygonError};
+use crate::sweep_trace::AlgorithmTrace;
+use crate::triangle_base::*;
+#[cfg(debug_assertions)]
+use crate::triangulation_validation::verify_triangulation;
+use egui::Pos2;
+use std::cmp::Ordering;
+
+/// Single ring through the exterior and every hole of a polygon.\
+/// A hole is merged into the ring through a bridge, a segment between
+/// two mutually visible vertices which is walked down and back up, so both
+/// of its vertices appear twice in the ring.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BridgedPolygon {
+    pub vertices: Vec<Pos2>,
+    /// Index of every ring vertex among the exterior's vertices followed
+    /// by the vertices of every hole.
+    pub indices: Vec<usize>,
+}
+
+/// Rings of a polygon with holes, with the index of their first vertex
+/// among the exterior's vertices followed by the vertices of every hole.
+fn rings<'a>(exterior: &'a [Pos2], holes: &'a [Vec<Pos2>]) -> Vec<(usize, &'a [Pos2])> {
+    let mut offset = exterior.len();
+    let mut ret = vec![(0, exterior)];
+    for hole in holes.iter() {
+        ret.push((offset, hole.as_slice()));
+        offset += hole.len();
+    }
+    ret
+}
+
+/// Check that a hole is a simple polygon, in either order.\
+/// Indices of the error are offset by `offset`, and refer to the given
+/// order even if the hole is reversed to be checked.
+fn validate_hole(hole: &[Pos2], offset: usize) -> Result<(), PolygonError> {
+    let n = hole.len();
+    let reversed = signed_area(hole) < 0.;
+    let result = if reversed {
+        let ccw: Vec<Pos2> = hole.iter().rev().copied().collect();
+        validate_polygon(&ccw)
+    } else {
+        validate_polygon(hole)
+    };
+    let vertex = |idx: usize| offset + if reversed { n - 1 - idx } else { idx };
+    // Edge i of the reversed hole is the given edge starting at its vertex i+1.
+    let edge = |idx: usize| {
+        if reversed {
+            vertex((idx + 1) % n)
+        } else {
+            offset + idx
+        }
+    };
+    result.map_err(|err| match err {
+        PolygonError::NonFiniteVertex(i) => PolygonError::NonFiniteVertex(vertex(i)),
+        PolygonError::DuplicateVertex(i, j) => PolygonError::DuplicateVertex(vertex(i), vertex(j)),
+        PolygonError::SelfIntersection(i, j) => PolygonError::SelfIntersection(edge(i), edge(j)),
+        err => err,
+    })
+}
+
+/// Check that `exterior` is a simple polygon in ccw order and `holes` are
+/// simple polygons (in either order) inside it, with no ring touching
+/// another one.\
+/// Vertices are numbered like `BridgedPolygon::indices`, edge i goes from
+/// vertex i to the next vertex of its ring.\
+/// It takes O(n^2) time, n is the number of vertices of all rings.
+pub fn validate_polygon_with_holes(
+    exterior: &[Pos2],
+    holes: &[Vec<Pos2>],
+) -> Result<(), PolygonError> {
+    validate_polygon(exterior)?;
+    let rings = rings(exterior, holes);
+    for (offset, hole) in rings.iter().skip(1) {
+        validate_hole(hole, *offset)?;
+    }
+
+    for (k, (offset1, ring1)) in rings.iter().enumerate() {
+        for (offset2, ring2) in rings.iter().skip(k + 1) {
+            for (i, p1) in ring1.iter().enumerate() {
+                let p2 = &ring1[(i + 1) % ring1.len()];
+                for (j, q1) in ring2.iter().enumerate() {
+                    let q2 = &ring2[(j + 1) % ring2.len()];
+                    if !segments_intersect(p1, p2, q1, q2) {
+                        continue;
+                    }
+                    let (edge1, edge2) = (offset1 + i, offset2 + j);
+                    if let Some(k) = [q1, q2].iter().position(|q| *q == p1 || *q == p2) {
+                        let vertex1 = if *[q1, q2][k] == *p1 {
+                            edge1
+                        } else {
+                            offset1 + (i + 1) % ring1.len()
+                        };
+                        let vertex2 = offset2 + (j + k) % ring2.len();
+                        return Err(PolygonError::DuplicateVertex(vertex1, vertex2));
+                    }
+                    return Err(PolygonError::SelfIntersection(edge1, edge2));
+                }
+            }
+        }
+    }
+
+    // Rings don't touch, so a hole is inside a ring if any vertex is.
+    for (k, hole) in holes.iter().enumerate() {
+        let inside = |ring: &[Pos2]| point_in_polygon(&hole[0], ring);
+        let nested = holes
+            .iter()
+            .enumerate()
+            .any(|(j, other)| j != k && inside(other));
+        if !inside(exterior) || nested {
+            return Err(PolygonError::HoleOutside(k));
+        }
+    }
+    Ok(())
+}
+
+/// Check if the segment from ring vertex `a` to point `h` starts inside the
+/// polygon, i.e. `h` is strictly inside the angle between the neighbors
+/// `prev` and `next` of `a`.
+fn locally_inside(prev: &Pos2, a: &Pos2, next: &Pos2, h: &Pos2) -> bool {
+    if cross(prev, a, next) >= 0. {
+        cross(a, next, h) > 0. && cross(a, h, prev) > 0.
+    } else {
+        !(cross(a, prev, h) >= 0. && cross(a, h, next) >= 0.)
+    }
+}
+
+/// Index of a ring vertex visible from `h`, the top vertex of a hole
+/// that isn't merged yet, nearest to `h` among the vertices above it.\
+/// `others` are the holes that aren't merged yet, none of them is above
+/// `h`, so the segment can only touch them at `h`.
+fn visible_vertex(ring: &[Pos2], others: &[&[Pos2]], h: &Pos2) -> Option<usize> {
+    let n = ring.len();
+    let mut candidates: Vec<usize> = (0..n).filter(|idx| is_above(&ring[*idx], h)).collect();
+    let distance = |idx: &usize| {
+        let d = ring[*idx] - *h;
+        d.x as f64 * d.x as f64 + d.y as f64 * d.y as f64
+    };
+    candidates.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+
+    let edges = || {
+        let ring_edges = (0..n).map(|idx| (ring[idx], ring[(idx + 1) % n]));
+        let hole_edges = others
+            .iter()
+            .flat_map(|hole| (0..hole.len()).map(|idx| (hole[idx], hole[(idx + 1) % hole.len()])));
+        ring_edges.chain(hole_edges)
+    };
+    candidates.into_iter().find(|idx| {
+        let a = ring[*idx];
+        locally_inside(&ring[(idx + n - 1) % n], &a, &ring[(idx + 1) % n], h)
+            && !edges().any(|(p, q)| {
+                // Edges at either end only meet the segment there, those
+                // along it are ruled out by the angle test of their copy.
+                ![a, *h].contains(&p) && ![a, *h].contains(&q) && segments_intersect(&a, h, &p, &q)
+            })
+    })
+}
+
+/// Bridge every hole to the exterior, holes are merged from the one with
+/// the highest top vertex down, each to the nearest vertex visible from
+/// its top vertex.\
+/// The ring stays in ccw order, holes are walked in cw order.\
+/// The input must pass `validate_polygon_with_holes`, it takes O(n^2)
+/// time in the worst case, n is the number of vertices of all rings.
+pub fn bridge_holes(exterior: &[Pos2], holes: &[Vec<Pos2>]) -> BridgedPolygon {
+    let mut bridged = BridgedPolygon {
+        vertices: exterior.to_vec(),
+        indices: (0..exterior.len()).collect(),
+    };
+    // Holes in cw order with their vertex indices, starting at their top vertex.
+    let mut pending: Vec<(Vec<Pos2>, Vec<usize>)> = rings(exterior, holes)
+        .into_iter()
+        .skip(1)
+        .map(|(offset, hole)| {
+            let mut indices: Vec<usize> = (offset..offset + hole.len()).collect();
+            if signed_area(hole) > 0. {
+                indices.reverse();
+            }
+            let vertex = |idx: usize| hole[indices[idx] - offset];
+            let top = (1..indices.len()).fold(0, |top, idx| {
+                if is_above(&vertex(idx), &vertex(top)) {
+                    idx
+                } else {
+                    top
+                }
+            });
+            indices.rotate_left(top);
+            let vertices = indices.iter().map(|idx| hole[idx - offset]).collect();
+            (vertices, indices)
+        })
+        .collect();
+    // Highest hole last, it's merged first.
+    pending.sort_by(|(a, _), (b, _)| {
+        if is_above(&a[0], &b[0]) {
+            Ordering::Greater
+        } else if is_above(&b[0], &a[0]) {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    });
+
+    while let Some((hole, indices)) = pending.pop() {
+        let h = hole[0];
+        let others: Vec<&[Pos2]> = pending.iter().map(|(hole, _)| hole.as_slice()).collect();
+        let a = visible_vertex(&bridged.vertices, &others, &h)
+            .expect("a valid polygon has a vertex visible from the top of every hole");
+        // Down the bridge, around the hole and back up.
+        let vertices = hole.iter().copied().chain([h, bridged.vertices[a]]);
+        let hole_indices = indices
+            .iter()
+            .copied()
+            .chain([indices[0], bridged.indices[a]]);
+        bridged.vertices.splice(a + 1..a + 1, vertices);
+        bridged.indices.splice(a + 1..a + 1, hole_indices);
+    }
+    bridged
+}
+
+/// Largest polygon whose triangulation is verified in debug builds,
+/// verify_triangulation takes O(n²) time.
+#[cfg(debug_assertions)]
+const DEBUG_VERIFY_MAX_VERTICES: usize = 1_000;
+
+/// Triangulate a polygon with holes, the holes are bridged to the
+/// exterior and the bridged ring is triangulated like a simple polygon.\
+/// Faces of the returned PartitionPolygon are indices into the bridged
+/// ring, a polygon with n vertices in total and h holes has n + 2h - 2
+/// triangles.\
+/// Events of the sweeps are recorded in `trace` if given.
+pub fn try_polygon_with_holes_triangulation(
+    exterior: &[Pos2],
+    holes: &[Vec<Pos2>],
+    trace: Option<&mut AlgorithmTrace>,
+) -> Result<(BridgedPolygon, PartitionPolygon), PolygonError> {
+    validate_polygon_with_holes(exterior, holes)?;
+    let bridged = bridge_holes(exterior, holes);
+    let poly = polygon_triangulation(&bridged.vertices, trace);
+
+    #[cfg(debug_assertions)]
+    if bridged.vertices.len() <= DEBUG_VERIFY_MAX_VERTICES {
+        let report = verify_triangulation(&bridged.vertices, &poly);
+        debug_assert!(report.is_valid(), "invalid triangulation: {}", report);
+    }
+    Ok((bridged, poly))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bridge_holes, try_polygon_with_holes_triangulation, validate_polygon_with_holes};
+    use crate::polygon_validation::PolygonError;
+    use crate::random_polygon::{random_polygon_with_holes, RandomMethod};
+    use crate::triangle_base::{point_in_polygon, signed_area};
+    use crate::triangulation_validation::verify_triangulation;
+    use egui::Pos2;
+
+    fn to_pts(pts: &[(f32, f32)]) -> Vec<Pos2> {
+        pts.iter().map(|(x, y)| Pos2::new(*x, *y)).collect()
+    }
+
+    /// Triangulate and check that no triangle covers a hole.
+    fn check(exterior: &[Pos2], holes: &[Vec<Pos2>]) {
+        let (bridged, poly) = try_polygon_with_holes_triangulation(exterior, holes, None)
+            .unwrap_or_else(|err| panic!("{}: {:?} {:?}", err, exterior, holes));
+        let n = exterior.len() + holes.iter().map(|hole| hole.len()).sum::<usize>();
+        assert_eq!(bridged.vertices.len(), n + 2 * holes.len());
+        let report = verify_triangulation(&bridged.vertices, &poly);
+        assert!(report.is_valid(), "{}: {:?} {:?}", report, exterior, holes);
+        for face in poly.faces.iter() {
+            let face = face.borrow();
+            let centroid = face.vertices.iter().fold(Pos2::ZERO, |sum, idx| {
+                sum + bridged.vertices[*idx].to_vec2()
+            }) / 3.;
+            assert!(!holes.iter().any(|hole| point_in_polygon(&centroid, hole)));
+        }
+    }
+
+    #[test]
+    fn test_bridge_holes() {
+        let square = to_pts(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let holes = vec![to_pts(&[(4., 4.), (4., 6.), (6., 6.), (6., 4.)])];
+        let bridged = bridge_holes(&square, &holes);
+        // Top left corner of the hole is bridged to the top left corner.
+        assert_eq!(bridged.indices, vec![0, 1, 2, 3, 5, 6, 7, 4, 5, 3]);
+        assert_eq!(
+            signed_area(&bridged.vertices),
+            signed_area(&square) + signed_area(&holes[0])
+        );
+        check(&square, &holes);
+        // Holes in ccw order are walked in cw order.
+        let ccw = vec![holes[0].iter().rev().copied().collect()];
+        assert_eq!(bridge_holes(&square, &ccw).vertices, bridged.vertices);
+        check(&square, &ccw);
+
+        // Holes side by side and on top of each other, bridged to each other.
+        let grid: Vec<Vec<Pos2>> = (0..3)
+            .flat_map(|i| (0..3).map(move |j| (i as f32 * 3. + 1., j as f32 * 3. + 1.)))
+            .map(|(x, y)| to_pts(&[(x, y), (x, y + 1.), (x + 1., y + 1.), (x + 1., y)]))
+            .collect();
+        check(&square, &grid);
+        let tilted = to_pts(&[(0., 0.), (10., 1.), (9., 10.), (1., 9.)]);
+        check(&tilted, &[to_pts(&[(4., 4.), (5., 6.), (6., 5.)])]);
+    }
+
+    #[test]
+    fn test_random_holes() {
+        for method in RandomMethod::ALL {
+            for seed in 0..40 {
+                let polygon = random_polygon_with_holes(method, 40, 1 + seed as usize % 4, seed);
+                check(&polygon.exterior, &polygon.holes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_polygon_with_holes() {
+        let square = to_pts(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let hole = to_pts(&[(4., 4.), (4., 6.), (6., 6.), (6., 4.)]);
+        assert_eq!(
+            validate_polygon_with_holes(&square, std::slice::from_ref(&hole)),
+            Ok(())
+        );
+
+        let outside = to_pts(&[(14., 4.), (14., 6.), (16., 6.)]);
+        assert_eq!(
+            validate_polygon_with_holes(&square, &[hole.clone(), outside]),
+            Err(PolygonError::HoleOutside(1))
+        );
+        let nested = to_pts(&[(4.5, 4.5), (4.5, 5.5), (5.5, 5.5)]);
+        assert_eq!(
+            validate_polygon_with_holes(&square, &[hole.clone(), nested]),
+            Err(PolygonError::HoleOutside(1))
+        );
+        let crossing = to_pts(&[(5., 5.), (5., 12.), (7., 5.)]);
+        assert_eq!(
+            validate_polygon_with_holes(&square, &[crossing]),
+            Err(PolygonError::SelfIntersection(2, 4))
+        );
+        let touching = to_pts(&[(4., 4.), (0., 0.), (6., 4.)]);
+        assert_eq!(
+            validate_polygon_with_holes(&square, &[touching]),
+            Err(PolygonError::DuplicateVertex(0, 5))
+        );
+        // Indices of a hole refer to its given order.
+        let bowtie = to_pts(&[(4., 4.), (6., 6.), (4., 6.), (6., 4.)]);
+        assert_eq!(
+            validate_polygon_with_holes(&square, &[bowtie]),
+            Err(PolygonError::SelfIntersection(4, 6))
+        );
+    }
+}
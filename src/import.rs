@@ -0,0 +1,1108 @@
+use crate::transform_pos::TransformPos;
+use crate::triangle_base::{point_in_polygon, signed_area};
+use egui::{pos2, vec2, Pos2};
+use serde::de::{Deserializer, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+/// A polygon with an exterior ring and optional holes.\
+/// Exterior ring is in ccw order and holes are in cw order,
+/// rings are not closed (first vertex isn't repeated at the end).
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct PolygonWithHoles {
+    pub exterior: Vec<Pos2>,
+    pub holes: Vec<Vec<Pos2>>,
+}
+
+impl PolygonWithHoles {
+    /// Build a polygon, rings are reoriented if needed.
+    pub fn new(mut exterior: Vec<Pos2>, mut holes: Vec<Vec<Pos2>>) -> Self {
+        if signed_area(&exterior) < 0. {
+            exterior.reverse();
+        }
+        for hole in holes.iter_mut() {
+            if signed_area(hole) > 0. {
+                hole.reverse();
+            }
+        }
+        Self { exterior, holes }
+    }
+}
+
+/// Error of parsing an input file, line and column start from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Wkt,
+    GeoJson,
+    Csv,
+    /// Shewchuk's Triangle .poly file.
+    Poly,
+    /// Ipe XML figure.
+    Ipe,
+}
+
+impl InputFormat {
+    pub const ALL: [InputFormat; 5] = [
+        InputFormat::Wkt,
+        InputFormat::GeoJson,
+        InputFormat::Csv,
+        InputFormat::Poly,
+        InputFormat::Ipe,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputFormat::Wkt => "WKT",
+            InputFormat::GeoJson => "GeoJSON",
+            InputFormat::Csv => "CSV",
+            InputFormat::Poly => "Triangle .poly",
+            InputFormat::Ipe => "Ipe",
+        }
+    }
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            InputFormat::Wkt => &["wkt"],
+            InputFormat::GeoJson => &["geojson", "json"],
+            InputFormat::Csv => &["csv"],
+            InputFormat::Poly => &["poly"],
+            InputFormat::Ipe => &["ipe", "xml"],
+        }
+    }
+
+    /// Guess format from file extension.
+    pub fn from_path(path: &Path) -> Option<InputFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|x| x.extensions().contains(&extension.as_str()))
+    }
+}
+
+/// Parse all polygons in `text` of the given format.
+pub fn parse_polygons(
+    text: &str,
+    format: InputFormat,
+) -> Result<Vec<PolygonWithHoles>, ParseError> {
+    match format {
+        InputFormat::Wkt => parse_wkt(text),
+        InputFormat::GeoJson => parse_geojson(text),
+        InputFormat::Csv => parse_csv(text).map(|x| vec![x]),
+        InputFormat::Poly => parse_poly(text),
+        InputFormat::Ipe => parse_ipe(text),
+    }
+}
+
+/// Drop the closing vertex of a ring and check it still has enough vertices.
+fn open_ring(mut ring: Vec<Pos2>) -> Result<Vec<Pos2>, String> {
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    if ring.len() < 3 {
+        return Err(format!(
+            "ring needs at least 3 vertices, got {}",
+            ring.len()
+        ));
+    }
+    Ok(ring)
+}
+
+/* ---------------------------------- WKT ---------------------------------- */
+
+#[derive(Clone, Debug, PartialEq)]
+enum WktToken {
+    Word(String),
+    Number(f64),
+    LeftParen,
+    RightParen,
+    Comma,
+    End,
+}
+
+/// Tokenizer and recursive descent parser of WKT polygons.
+struct WktParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+    // Next token and where it starts
+    token: WktToken,
+    token_pos: (usize, usize),
+}
+
+impl<'a> WktParser<'a> {
+    fn new(text: &'a str) -> Result<Self, ParseError> {
+        let mut parser = Self {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+            token: WktToken::End,
+            token_pos: (1, 1),
+        };
+        parser.advance()?;
+        Ok(parser)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.token_pos.0, self.token_pos.1, message)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Read the next token.
+    fn advance(&mut self) -> Result<(), ParseError> {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+        self.token_pos = (self.line, self.column);
+        let Some(&c) = self.chars.peek() else {
+            self.token = WktToken::End;
+            return Ok(());
+        };
+        self.token = match c {
+            '(' | ')' | ',' => {
+                self.bump();
+                match c {
+                    '(' => WktToken::LeftParen,
+                    ')' => WktToken::RightParen,
+                    _ => WktToken::Comma,
+                }
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut word = String::new();
+                while let Some(c) = self
+                    .chars
+                    .peek()
+                    .copied()
+                    .filter(|c| c.is_ascii_alphabetic())
+                {
+                    word.push(c.to_ascii_uppercase());
+                    self.bump();
+                }
+                WktToken::Word(word)
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = self.chars.peek().copied() {
+                    let exponent_sign = (c == '-' || c == '+') && number.ends_with(['e', 'E']);
+                    if c.is_ascii_digit()
+                        || c == '.'
+                        || c == 'e'
+                        || c == 'E'
+                        || exponent_sign
+                        || number.is_empty()
+                    {
+                        number.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                match number.parse::<f64>() {
+                    Ok(x) if x.is_finite() => WktToken::Number(x),
+                    _ => return Err(self.error(format!("invalid number \"{}\"", number))),
+                }
+            }
+            c => return Err(self.error(format!("unexpected character '{}'", c))),
+        };
+        Ok(())
+    }
+
+    fn expect(&mut self, token: WktToken, what: &str) -> Result<(), ParseError> {
+        if self.token != token {
+            return Err(self.error(format!("expected {}", what)));
+        }
+        self.advance()
+    }
+
+    /// Skip optional Z, M or ZM dimension.
+    fn dimension(&mut self) -> Result<(), ParseError> {
+        if let WktToken::Word(word) = &self.token {
+            if ["Z", "M", "ZM"].contains(&word.as_str()) {
+                self.advance()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Number that fits in f32, coordinates are stored as f32.
+    fn number(&mut self) -> Result<f32, ParseError> {
+        match self.token {
+            WktToken::Number(x) if (x as f32).is_finite() => {
+                self.advance()?;
+                Ok(x as f32)
+            }
+            WktToken::Number(x) => Err(self.error(format!("number {:e} is out of range", x))),
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    /// Point with 2 to 4 coordinates, only x and y are kept.
+    fn point(&mut self) -> Result<Pos2, ParseError> {
+        let x = self.number()?;
+        let y = self.number()?;
+        for _ in 0..2 {
+            if let WktToken::Number(_) = self.token {
+                self.advance()?;
+            }
+        }
+        Ok(pos2(x, y))
+    }
+
+    fn ring(&mut self) -> Result<Vec<Pos2>, ParseError> {
+        let start = self.token_pos;
+        self.expect(WktToken::LeftParen, "'(' before ring")?;
+        let mut ring = vec![self.point()?];
+        while self.token == WktToken::Comma {
+            self.advance()?;
+            ring.push(self.point()?);
+        }
+        self.expect(WktToken::RightParen, "',' or ')' after point")?;
+        open_ring(ring).map_err(|message| ParseError::new(start.0, start.1, message))
+    }
+
+    /// Polygon text after POLYGON keyword and dimension, None if it's EMPTY.
+    fn polygon(&mut self) -> Result<Option<PolygonWithHoles>, ParseError> {
+        if self.token == WktToken::Word("EMPTY".to_string()) {
+            self.advance()?;
+            return Ok(None);
+        }
+        self.expect(WktToken::LeftParen, "'(' or EMPTY before polygon")?;
+        let exterior = self.ring()?;
+        let mut holes = Vec::new();
+        while self.token == WktToken::Comma {
+            self.advance()?;
+            holes.push(self.ring()?);
+        }
+        self.expect(WktToken::RightParen, "',' or ')' after ring")?;
+        Ok(Some(PolygonWithHoles::new(exterior, holes)))
+    }
+
+    /// Parse geometries until the end of text.
+    fn geometries(&mut self) -> Result<Vec<PolygonWithHoles>, ParseError> {
+        let mut ret = Vec::new();
+        while self.token != WktToken::End {
+            let keyword = match &self.token {
+                WktToken::Word(word) => word.clone(),
+                _ => return Err(self.error("expected POLYGON or MULTIPOLYGON")),
+            };
+            let keyword_pos = self.token_pos;
+            self.advance()?;
+            match keyword.as_str() {
+                "POLYGON" => {
+                    self.dimension()?;
+                    ret.extend(self.polygon()?);
+                }
+                "MULTIPOLYGON" => {
+                    self.dimension()?;
+                    if self.token == WktToken::Word("EMPTY".to_string()) {
+                        self.advance()?;
+                        continue;
+                    }
+                    self.expect(WktToken::LeftParen, "'(' or EMPTY before multipolygon")?;
+                    ret.extend(self.polygon()?);
+                    while self.token == WktToken::Comma {
+                        self.advance()?;
+                        ret.extend(self.polygon()?);
+                    }
+                    self.expect(WktToken::RightParen, "',' or ')' after polygon")?;
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        keyword_pos.0,
+                        keyword_pos.1,
+                        format!("unsupported geometry {}", keyword),
+                    ))
+                }
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// Parse WKT `POLYGON` and `MULTIPOLYGON` geometries, separated by whitespace.
+pub fn parse_wkt(text: &str) -> Result<Vec<PolygonWithHoles>, ParseError> {
+    WktParser::new(text)?.geometries()
+}
+
+/* -------------------------------- GeoJSON -------------------------------- */
+
+/// Nested arrays of numbers in GeoJSON "coordinates".\
+/// It's deserialized before "type" is known, so its shape is checked later.
+enum Coordinates {
+    Number(f64),
+    Array(Vec<Coordinates>),
+}
+
+impl<'de> Deserialize<'de> for Coordinates {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CoordinatesVisitor;
+
+        impl<'de> Visitor<'de> for CoordinatesVisitor {
+            type Value = Coordinates;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "a number or an array of coordinates")
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, x: f64) -> Result<Coordinates, E> {
+                // Coordinates are stored as f32.
+                if !(x as f32).is_finite() {
+                    return Err(E::custom(format!("number {:e} is out of range", x)));
+                }
+                Ok(Coordinates::Number(x))
+            }
+
+            fn visit_i64<E>(self, x: i64) -> Result<Coordinates, E> {
+                Ok(Coordinates::Number(x as f64))
+            }
+
+            fn visit_u64<E>(self, x: u64) -> Result<Coordinates, E> {
+                Ok(Coordinates::Number(x as f64))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Coordinates, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Coordinates::Array(items))
+            }
+        }
+
+        deserializer.deserialize_any(CoordinatesVisitor)
+    }
+}
+
+impl Coordinates {
+    fn array(&self, what: &str) -> Result<&[Coordinates], String> {
+        match self {
+            Coordinates::Array(items) => Ok(items),
+            Coordinates::Number(_) => Err(format!("expected an array of {}", what)),
+        }
+    }
+
+    /// Position with at least 2 numbers, extra ones (e.g. altitude) are ignored.
+    fn position(&self) -> Result<Pos2, String> {
+        match self.array("numbers")? {
+            [Coordinates::Number(x), Coordinates::Number(y), ..] => Ok(pos2(*x as f32, *y as f32)),
+            _ => Err("position needs at least 2 numbers".to_string()),
+        }
+    }
+
+    fn ring(&self) -> Result<Vec<Pos2>, String> {
+        let ring = self
+            .array("positions")?
+            .iter()
+            .map(|x| x.position())
+            .collect::<Result<Vec<Pos2>, String>>()?;
+        open_ring(ring)
+    }
+
+    /// Polygon from its rings, None if there's no ring at all.
+    fn polygon(&self) -> Result<Option<PolygonWithHoles>, String> {
+        let mut rings = self
+            .array("rings")?
+            .iter()
+            .map(|x| x.ring())
+            .collect::<Result<Vec<Vec<Pos2>>, String>>()?
+            .into_iter();
+        Ok(rings
+            .next()
+            .map(|exterior| PolygonWithHoles::new(exterior, rings.collect())))
+    }
+}
+
+/// Polygons in a GeoJSON object, which may be a geometry, feature
+/// or (feature/geometry) collection. Points, lines and so on are skipped.\
+/// Shape of "coordinates" is checked once the whole object is read,
+/// so errors are reported at the end of the object.
+struct GeoJsonPolygons(Vec<PolygonWithHoles>);
+
+impl<'de> Deserialize<'de> for GeoJsonPolygons {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct GeoJsonVisitor;
+
+        impl<'de> Visitor<'de> for GeoJsonVisitor {
+            type Value = GeoJsonPolygons;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "a GeoJSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<GeoJsonPolygons, A::Error> {
+                let mut kind: Option<String> = None;
+                let mut coordinates: Option<Coordinates> = None;
+                let mut children: Vec<GeoJsonPolygons> = Vec::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "type" => kind = Some(map.next_value()?),
+                        "coordinates" => coordinates = Some(map.next_value()?),
+                        "geometry" => children.extend(map.next_value::<Option<_>>()?),
+                        "features" | "geometries" => children = map.next_value()?,
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let kind = kind.ok_or_else(|| A::Error::missing_field("type"))?;
+                let mut ret = Vec::new();
+                match kind.as_str() {
+                    "Polygon" | "MultiPolygon" => {
+                        let coordinates =
+                            coordinates.ok_or_else(|| A::Error::missing_field("coordinates"))?;
+                        if kind == "Polygon" {
+                            ret.extend(coordinates.polygon().map_err(A::Error::custom)?);
+                        } else {
+                            for polygon in
+                                coordinates.array("polygons").map_err(A::Error::custom)?
+                            {
+                                ret.extend(polygon.polygon().map_err(A::Error::custom)?);
+                            }
+                        }
+                    }
+                    "Feature" | "FeatureCollection" | "GeometryCollection" => {
+                        ret.extend(children.into_iter().flat_map(|x| x.0));
+                    }
+                    _ => {}
+                }
+                Ok(GeoJsonPolygons(ret))
+            }
+        }
+
+        deserializer.deserialize_map(GeoJsonVisitor)
+    }
+}
+
+/// Parse `Polygon` and `MultiPolygon` geometries in a GeoJSON object,
+/// which may be a geometry, feature or (feature/geometry) collection.
+pub fn parse_geojson(text: &str) -> Result<Vec<PolygonWithHoles>, ParseError> {
+    let polygons: GeoJsonPolygons = serde_json::from_str(text).map_err(|err| {
+        // Position is already in line and column, drop it from the message.
+        let message = err.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(idx) => message[..idx].to_string(),
+            None => message,
+        };
+        ParseError::new(err.line().max(1), err.column().max(1), message)
+    })?;
+    Ok(polygons.0)
+}
+
+/* ---------------------------------- CSV ---------------------------------- */
+
+/// Split a line into fields, return each field's column and trimmed text.
+fn split_fields(line: &str, separators: &[char]) -> Vec<(usize, String)> {
+    let mut ret = Vec::new();
+    let mut start = 0;
+    for (idx, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ',')))
+    {
+        if idx == line.len() || separators.contains(&c) {
+            let field = &line[start..idx];
+            let leading = field.len() - field.trim_start().len();
+            let column = line[..start + leading].chars().count() + 1;
+            ret.push((column, field.trim().to_string()));
+            start = idx + c.len_utf8();
+        }
+    }
+    ret
+}
+
+/// Parse a single ring from lines of "x,y".\
+/// Comma, semicolon or tab separate fields, extra fields are ignored.
+/// Blank lines, lines starting with '#' and a header line are skipped.
+pub fn parse_csv(text: &str) -> Result<PolygonWithHoles, ParseError> {
+    let mut ring = Vec::new();
+    let mut first_line = None;
+    for (line_idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let fields = split_fields(line, &[',', ';', '\t']);
+        let is_header = first_line.is_none();
+        first_line.get_or_insert(line_idx + 1);
+        if fields.len() < 2 {
+            return Err(ParseError::new(
+                line_idx + 1,
+                line.chars().count() + 1,
+                "expected x and y separated by ','",
+            ));
+        }
+        let mut coordinates = [0.; 2];
+        for (i, (column, field)) in fields.iter().take(2).enumerate() {
+            match field.parse::<f64>() {
+                Ok(x) if (x as f32).is_finite() => coordinates[i] = x,
+                _ if is_header => break,
+                Ok(x) if x.is_finite() => {
+                    return Err(ParseError::new(
+                        line_idx + 1,
+                        *column,
+                        format!("number \"{}\" is out of range", field),
+                    ))
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        line_idx + 1,
+                        *column,
+                        format!("invalid number \"{}\"", field),
+                    ))
+                }
+            }
+            if i == 1 {
+                ring.push(pos2(coordinates[0] as f32, coordinates[1] as f32));
+            }
+        }
+    }
+    let ring =
+        open_ring(ring).map_err(|message| ParseError::new(first_line.unwrap_or(1), 1, message))?;
+    Ok(PolygonWithHoles::new(ring, Vec::new()))
+}
+
+/* ------------------------------ Triangle .poly ------------------------------ */
+
+/// Non-empty lines of a .poly file, split into (column, token).
+struct PolyLines {
+    lines: Vec<(usize, Vec<(usize, String)>)>,
+    cur: usize,
+    last_line: usize,
+}
+
+impl PolyLines {
+    fn new(text: &str) -> Self {
+        let lines = text
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| {
+                // Everything after '#' is a comment.
+                let line = line.split('#').next().unwrap_or_default();
+                let tokens = split_fields(line, &[' ', '\t', ','])
+                    .into_iter()
+                    .filter(|(_, token)| !token.is_empty())
+                    .collect::<Vec<_>>();
+                (idx + 1, tokens)
+            })
+            .filter(|(_, tokens)| !tokens.is_empty())
+            .collect();
+        Self {
+            lines,
+            cur: 0,
+            last_line: text.lines().count().max(1),
+        }
+    }
+
+    /// Next line, which must have at least `min_tokens` tokens.
+    fn next(
+        &mut self,
+        min_tokens: usize,
+        what: &str,
+    ) -> Result<(usize, Vec<(usize, String)>), ParseError> {
+        let Some((line, tokens)) = self.lines.get(self.cur).cloned() else {
+            return Err(ParseError::new(
+                self.last_line,
+                1,
+                format!("unexpected end of file, expected {}", what),
+            ));
+        };
+        self.cur += 1;
+        if tokens.len() < min_tokens {
+            let column = tokens
+                .last()
+                .map_or(1, |(column, token)| column + token.len());
+            return Err(ParseError::new(line, column, format!("expected {}", what)));
+        }
+        Ok((line, tokens))
+    }
+}
+
+/// Coordinate that fits in f32, coordinates are stored as f32.
+fn parse_coordinate(line: usize, token: &(usize, String)) -> Result<f32, ParseError> {
+    let x: f64 = parse_token(line, token)?;
+    if !x.is_finite() {
+        return Err(ParseError::new(
+            line,
+            token.0,
+            format!("invalid number \"{}\"", token.1),
+        ));
+    }
+    if !(x as f32).is_finite() {
+        return Err(ParseError::new(
+            line,
+            token.0,
+            format!("number \"{}\" is out of range", token.1),
+        ));
+    }
+    Ok(x as f32)
+}
+
+fn parse_token<T: std::str::FromStr>(
+    line: usize,
+    token: &(usize, String),
+) -> Result<T, ParseError> {
+    token
+        .1
+        .parse::<T>()
+        .map_err(|_| ParseError::new(line, token.0, format!("invalid number \"{}\"", token.1)))
+}
+
+/// Parse Shewchuk's Triangle .poly file.\
+/// Segments are chained into rings, a ring containing a hole point becomes
+/// a hole of the smallest ring around it. Without segments,
+/// vertices are taken as a single ring in their order.
+pub fn parse_poly(text: &str) -> Result<Vec<PolygonWithHoles>, ParseError> {
+    let mut lines = PolyLines::new(text);
+
+    let (line, header) = lines.next(1, "vertex count")?;
+    let vertex_count: usize = parse_token(line, &header[0])?;
+    if vertex_count == 0 {
+        return Err(ParseError::new(
+            line,
+            header[0].0,
+            "vertices in a separate .node file are not supported",
+        ));
+    }
+    if let Some(dimension) = header.get(1) {
+        if parse_token::<usize>(line, dimension)? != 2 {
+            return Err(ParseError::new(line, dimension.0, "dimension must be 2"));
+        }
+    }
+
+    // Vertex numbers may start from 0 or 1, depending on the first one.
+    let mut vertices: Vec<Pos2> = Vec::new();
+    let mut first_number = 0;
+    for i in 0..vertex_count {
+        let (line, tokens) = lines.next(3, "vertex number, x and y")?;
+        let number: usize = parse_token(line, &tokens[0])?;
+        if i == 0 {
+            first_number = number;
+        }
+        if number != first_number + i {
+            return Err(ParseError::new(
+                line,
+                tokens[0].0,
+                format!("expected vertex number {}", first_number + i),
+            ));
+        }
+        let x = parse_coordinate(line, &tokens[1])?;
+        let y = parse_coordinate(line, &tokens[2])?;
+        vertices.push(pos2(x, y));
+    }
+
+    let (line, header) = lines.next(1, "segment count")?;
+    let segment_count: usize = parse_token(line, &header[0])?;
+    let mut neighbors: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut segment_lines = Vec::new();
+    for _ in 0..segment_count {
+        let (line, tokens) = lines.next(3, "segment number and two endpoints")?;
+        let mut endpoints = [0; 2];
+        for (i, token) in tokens[1..3].iter().enumerate() {
+            let number: usize = parse_token(line, token)?;
+            if number < first_number || number - first_number >= vertex_count {
+                return Err(ParseError::new(
+                    line,
+                    token.0,
+                    format!("vertex {} doesn't exist", number),
+                ));
+            }
+            endpoints[i] = number - first_number;
+        }
+        neighbors
+            .entry(endpoints[0])
+            .or_default()
+            .push(endpoints[1]);
+        neighbors
+            .entry(endpoints[1])
+            .or_default()
+            .push(endpoints[0]);
+        segment_lines.push((line, endpoints[0]));
+    }
+
+    let mut hole_points = Vec::new();
+    if let Some(&(line, _)) = lines.lines.get(lines.cur) {
+        let (_, header) = lines.next(1, "hole count")?;
+        let hole_count: usize = parse_token(line, &header[0])?;
+        for _ in 0..hole_count {
+            let (line, tokens) = lines.next(3, "hole number, x and y")?;
+            let x = parse_coordinate(line, &tokens[1])?;
+            let y = parse_coordinate(line, &tokens[2])?;
+            hole_points.push(pos2(x, y));
+        }
+    }
+
+    // Chain segments into rings.
+    let mut rings: Vec<Vec<Pos2>> = Vec::new();
+    if segment_count == 0 {
+        rings.push(vertices.clone());
+    }
+    let mut visited = vec![false; vertex_count];
+    for (line, start) in segment_lines {
+        if visited[start] {
+            continue;
+        }
+        let mut ring = Vec::new();
+        let (mut prev, mut cur) = (usize::MAX, start);
+        loop {
+            let adjacent = &neighbors[&cur];
+            if adjacent.len() != 2 {
+                return Err(ParseError::new(
+                    line,
+                    1,
+                    format!(
+                        "segments don't form closed rings, vertex {} has {} segments",
+                        cur + first_number,
+                        adjacent.len()
+                    ),
+                ));
+            }
+            visited[cur] = true;
+            ring.push(vertices[cur]);
+            let next = if adjacent[0] != prev {
+                adjacent[0]
+            } else {
+                adjacent[1]
+            };
+            (prev, cur) = (cur, next);
+            if cur == start {
+                break;
+            }
+        }
+        if ring.len() < 3 {
+            return Err(ParseError::new(line, 1, "ring needs at least 3 vertices"));
+        }
+        rings.push(ring);
+    }
+
+    // The smallest ring around a hole point is a hole,
+    // which belongs to the smallest exterior ring around it.
+    let mut is_hole = vec![false; rings.len()];
+    for p in hole_points.iter() {
+        let smallest = (0..rings.len())
+            .filter(|idx| point_in_polygon(p, &rings[*idx]))
+            .min_by(|a, b| {
+                let area = |idx: &usize| signed_area(&rings[*idx]).abs();
+                area(a).total_cmp(&area(b))
+            });
+        if let Some(idx) = smallest {
+            is_hole[idx] = true;
+        }
+    }
+    let (holes, exteriors): (Vec<_>, Vec<_>) = rings
+        .into_iter()
+        .zip(is_hole)
+        .partition(|(_, is_hole)| *is_hole);
+    let holes = holes.into_iter().map(|(ring, _)| ring);
+    let exteriors = exteriors.into_iter().map(|(ring, _)| ring);
+    let mut ret: Vec<PolygonWithHoles> = exteriors
+        .map(|ring| PolygonWithHoles::new(ring, Vec::new()))
+        .collect();
+    for hole in holes {
+        let owner = ret
+            .iter_mut()
+            .filter(|x| point_in_polygon(&hole[0], &x.exterior))
+            .min_by(|a, b| signed_area(&a.exterior).total_cmp(&signed_area(&b.exterior)));
+        if let Some(owner) = owner {
+            let mut hole = hole;
+            if signed_area(&hole) > 0. {
+                hole.reverse();
+            }
+            owner.holes.push(hole);
+        }
+    }
+    Ok(ret)
+}
+
+/* ----------------------------------- Ipe ----------------------------------- */
+
+/// Line and column of a byte offset in `text`.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Value of attribute `name` in an XML start tag, with its byte offset in the tag.
+fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<(usize, &'a str)> {
+    let pattern = format!(" {}=\"", name);
+    let start = tag.find(&pattern)? + pattern.len();
+    let len = tag[start..].find('"')?;
+    Some((start, &tag[start..start + len]))
+}
+
+/// Parse closed polygonal `<path>` objects on the pages of an Ipe XML document.\
+/// The first subpath of a path is its exterior, the other ones are holes.
+/// Paths with curves or without closing `h` are skipped, so are
+/// group transformations.
+pub fn parse_ipe(text: &str) -> Result<Vec<PolygonWithHoles>, ParseError> {
+    let error = |offset: usize, message: String| {
+        let (line, column) = line_column(text, offset);
+        ParseError::new(line, column, message)
+    };
+
+    let mut ret = Vec::new();
+    let mut cursor = 0;
+    // Style sheets also have paths (e.g. in symbols), only paths in pages count.
+    while let Some(page_start) = text[cursor..].find("<page").map(|x| x + cursor) {
+        let page_end = text[page_start..]
+            .find("</page>")
+            .map(|x| x + page_start)
+            .ok_or_else(|| error(page_start, "<page> is not closed".to_string()))?;
+        cursor = page_end;
+
+        let mut path_cursor = page_start;
+        while let Some(tag_start) = text[path_cursor..page_end]
+            .find("<path")
+            .map(|x| x + path_cursor)
+        {
+            let tag_end = text[tag_start..page_end]
+                .find('>')
+                .map(|x| x + tag_start)
+                .ok_or_else(|| error(tag_start, "<path> tag is not closed".to_string()))?;
+            path_cursor = tag_end;
+            let tag = &text[tag_start..tag_end];
+            if tag.ends_with('/') {
+                continue;
+            }
+            let content_start = tag_end + 1;
+            let content_end = text[content_start..page_end]
+                .find("</path>")
+                .map(|x| x + content_start)
+                .ok_or_else(|| error(tag_start, "<path> is not closed".to_string()))?;
+            path_cursor = content_end;
+
+            let mut transform = TransformPos::IDENTITY;
+            if let Some((offset, matrix)) = xml_attribute(tag, "matrix") {
+                let values = matrix
+                    .split_whitespace()
+                    .map(|x| x.parse::<f32>())
+                    .collect::<Result<Vec<f32>, _>>()
+                    .ok()
+                    .filter(|x| x.len() == 6 && x.iter().all(|x| x.is_finite()))
+                    .ok_or_else(|| {
+                        error(tag_start + offset, format!("invalid matrix \"{}\"", matrix))
+                    })?;
+                transform = TransformPos {
+                    x_axis: vec2(values[0], values[1]),
+                    y_axis: vec2(values[2], values[3]),
+                    translation: vec2(values[4], values[5]),
+                };
+            }
+
+            // Path construction operators come after their operands.
+            let mut rings: Vec<Vec<Pos2>> = Vec::new();
+            let mut current: Vec<Pos2> = Vec::new();
+            let mut operands: Vec<f32> = Vec::new();
+            let mut polygonal = true;
+            let content = &text[content_start..content_end];
+            let mut offset = 0;
+            for token in content.split_whitespace() {
+                let token_offset = content[offset..].find(token).unwrap() + offset;
+                offset = token_offset + token.len();
+                let at = content_start + token_offset;
+                match token {
+                    "m" | "l" => {
+                        let &[x, y] = operands.as_slice() else {
+                            return Err(error(at, format!("'{}' needs 2 operands", token)));
+                        };
+                        if token == "m" && !current.is_empty() {
+                            // Previous subpath isn't closed.
+                            polygonal = false;
+                        }
+                        let pos = transform * pos2(x, y);
+                        if !pos.x.is_finite() || !pos.y.is_finite() {
+                            return Err(error(at, "point is out of range".to_string()));
+                        }
+                        current.push(pos);
+                        operands.clear();
+                    }
+                    "h" => {
+                        if !operands.is_empty() {
+                            return Err(error(at, "'h' takes no operands".to_string()));
+                        }
+                        rings.push(std::mem::take(&mut current));
+                    }
+                    _ => match token.parse::<f32>() {
+                        Ok(x) if x.is_finite() => operands.push(x),
+                        // Curves, arcs and ellipses, "inf" or "nan" isn't a number either.
+                        _ if token.chars().all(|c| c.is_ascii_alphabetic()) => {
+                            polygonal = false;
+                            operands.clear();
+                        }
+                        Ok(_) => {
+                            return Err(error(at, format!("number \"{}\" is out of range", token)))
+                        }
+                        Err(_) => return Err(error(at, format!("invalid number \"{}\"", token))),
+                    },
+                }
+            }
+            if !polygonal || !current.is_empty() || rings.is_empty() {
+                continue;
+            }
+            let mut rings = rings.into_iter();
+            let exterior = open_ring(rings.next().unwrap())
+                .map_err(|message| error(content_start, message))?;
+            let holes = rings
+                .map(|ring| open_ring(ring).map_err(|message| error(content_start, message)))
+                .collect::<Result<Vec<Vec<Pos2>>, ParseError>>()?;
+            ret.push(PolygonWithHoles::new(exterior, holes));
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_csv, parse_geojson, parse_ipe, parse_poly, parse_wkt, ParseError};
+    use crate::triangle_base::signed_area;
+    use egui::pos2;
+
+    #[test]
+    fn test_parse_wkt() {
+        let text = "POLYGON ((0 0, 0 10, 10 10, 10 0, 0 0), (2 2, 4 2, 4 4, 2 2))\n\
+                    MULTIPOLYGON Z (((0 0 1, 1 0 1, 1 1 1, 0 0 1)), EMPTY)";
+        let polygons = parse_wkt(text).unwrap();
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0].exterior.len(), 4);
+        // Exterior is reoriented to ccw, holes to cw.
+        assert!(signed_area(&polygons[0].exterior) > 0.);
+        assert!(signed_area(&polygons[0].holes[0]) < 0.);
+        assert_eq!(polygons[1].exterior[1], pos2(1., 0.));
+
+        let err = parse_wkt("POLYGON ((0 0, 1 0,\n 1 x, 0 0))").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+        // Numbers beyond f32 would become infinite.
+        let err = parse_wkt("POLYGON((0 0, 1e39 1, 3 5, 0 0))").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 15));
+    }
+
+    #[test]
+    fn test_parse_geojson() {
+        let text = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [1, 2]}},
+            {"type": "Feature", "properties": {"name": "a"},
+             "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [4, 0], [4, 3], [0, 0]]]}}
+        ]}"#;
+        let polygons = parse_geojson(text).unwrap();
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].exterior.len(), 3);
+
+        let err = parse_geojson("{\"type\": \"Polygon\",\n \"coordinates\": [[[0, 0], [1]]]}")
+            .unwrap_err();
+        assert_eq!(err.line, 2);
+        let err = parse_geojson(
+            "{\"type\": \"Polygon\",\n \"coordinates\": [[[0, 0], [1e39, 1], [3, 5]]]}",
+        )
+        .unwrap_err();
+        assert_eq!((err.line, err.message.contains("out of range")), (2, true));
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let polygon = parse_csv("# comment\nx,y\n0,0\n5, 1\n3;4\n").unwrap();
+        assert_eq!(
+            polygon.exterior,
+            vec![pos2(0., 0.), pos2(5., 1.), pos2(3., 4.)]
+        );
+
+        let err = parse_csv("x,y\n0,0\n5, one\n").unwrap_err();
+        assert_eq!(
+            err,
+            ParseError {
+                line: 3,
+                column: 4,
+                message: "invalid number \"one\"".to_string()
+            }
+        );
+        let err = parse_csv("0,0\n1e39,1\n3,5\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
+
+    #[test]
+    fn test_parse_poly() {
+        let text = "# square with a triangular hole\n\
+                    7 2 0 0\n\
+                    1 0 0\n2 10 0\n3 10 10\n4 0 10\n\
+                    5 2 2\n6 5 2\n7 2 5\n\
+                    7 0\n\
+                    1 1 2\n2 2 3\n3 3 4\n4 4 1\n\
+                    5 5 6\n6 6 7\n7 7 5\n\
+                    1\n1 3 3\n";
+        let polygons = parse_poly(text).unwrap();
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].exterior.len(), 4);
+        assert_eq!(polygons[0].holes.len(), 1);
+        assert!(signed_area(&polygons[0].holes[0]) < 0.);
+
+        let err = parse_poly("3 2 0 0\n1 0 0\n2 1 0\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        let err = parse_poly("3 2 0 0\n1 0 0\n2 1e39 0\n3 0 1\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+        // Counts in headers aren't trusted, the file ends long before.
+        let err = parse_poly("99999999999999999 2 0 0\n1 0 0\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        let err = parse_poly("3 2 0 0\n1 0 0\n2 1 1\n3 0 2\n18446744073709551615 0\n").unwrap_err();
+        assert_eq!(err.line, 5);
+    }
+
+    #[test]
+    fn test_parse_ipe() {
+        let text = "<ipe version=\"70218\">\n<ipestyle>\n\
+                    <symbol name=\"mark\"><path>0 0 m 1 0 l 1 1 l h</path></symbol>\n\
+                    </ipestyle>\n<page>\n\
+                    <path stroke=\"black\" matrix=\"1 0 0 1 10 20\">\n\
+                    0 0 m\n0 10 l\n10 10 l\n10 0 l\nh\n2 2 m\n4 2 l\n4 4 l\nh\n</path>\n\
+                    <path stroke=\"black\">0 0 m 5 5 l</path>\n\
+                    <path stroke=\"black\">0 0 m 5 5 5 0 9 9 c h</path>\n\
+                    </page>\n</ipe>";
+        let polygons = parse_ipe(text).unwrap();
+        assert_eq!(polygons.len(), 1);
+        assert!(polygons[0].exterior.contains(&pos2(10., 30.)));
+        assert!(signed_area(&polygons[0].exterior) > 0.);
+        assert_eq!(polygons[0].holes.len(), 1);
+
+        let err =
+            parse_ipe("<ipe>\n<page>\n<path>0 0 m\n1 2.5.5 l</path></page></ipe>").unwrap_err();
+        assert_eq!((err.line, err.column), (4, 3));
+        let err =
+            parse_ipe("<ipe>\n<page>\n<path>0 0 m\n1e39 2 l</path></page></ipe>").unwrap_err();
+        assert_eq!((err.line, err.column), (4, 1));
+    }
+}
@@ -2,13 +2,79 @@
 
 //https://doc.rust-lang.org/book/ch07-03-paths-for-referring-to-an-item-in-the-module-tree.html#best-practices-for-packages-with-a-binary-and-a-library
 mod app;
+#[cfg(not(target_arch = "wasm32"))]
+mod batch;
+mod camera;
+mod earcut;
+mod export;
+mod expression;
+mod history;
+mod holes;
+mod import;
 mod monotone_triangulation;
 mod monotone_y_partition;
+mod polygon_validation;
+mod presets;
+mod project;
+mod random_polygon;
+mod shapes;
+mod snapping;
+mod sweep_trace;
 mod transform_pos;
 mod triangle_base;
+mod triangulation_validation;
 mod vertex_coloring;
 
 pub use app::Painting;
+#[cfg(not(target_arch = "wasm32"))]
+pub use batch::{
+    batch_report, file_report, polygon_report, reports_to_csv, reports_to_json, PhaseTimings,
+    PolygonReport, TriangleQuality, VertexTypeCounts,
+};
+pub use earcut::{earcut, earcut_f64, parse_earcut_fixture, EarcutData, EarcutError};
+pub use export::{
+    export, to_ipe, to_json, to_obj, to_ply, to_svg, to_tikz, ExportFormat, ExportStyle,
+    TriangulationData,
+};
+pub use holes::{
+    bridge_holes, try_polygon_with_holes_triangulation, validate_polygon_with_holes, BridgedPolygon,
+};
+pub use import::{
+    parse_csv, parse_geojson, parse_ipe, parse_poly, parse_polygons, parse_wkt, InputFormat,
+    ParseError, PolygonWithHoles,
+};
+pub use monotone_triangulation::{
+    partition_into_monotone, polygon_triangulation, triangulate_monotone_polygons,
+    try_polygon_triangulation,
+};
+pub use monotone_y_partition::{
+    monotone_partition_polygon, monotone_polygon_partition, vertex_types, PartitionPolygon,
+    VertexType,
+};
+pub use polygon_validation::{validate_polygon, PolygonError};
+pub use presets::{find_preset, presets, Preset, PresetCategory, DEFAULT_PRESET};
+pub use project::{
+    Algorithm, Annotation, Project, ViewSettings, PROJECT_EXTENSION, PROJECT_VERSION,
+};
+pub use random_polygon::{
+    random_polygon, random_polygon_with_holes, RandomMethod, RandomSettings, SeededRng,
+    TWO_OPT_MAX_VERTICES,
+};
+pub use shapes::{comb, polar, regular_polygon, sawtooth, spiral, star, zigzag, ParametricShape};
+pub use sweep_trace::{AlgorithmTrace, TracePhase, TraceStep};
+pub use transform_pos::{transform_polygon, TransformPos};
+pub use triangulation_validation::{
+    shrink_polygon, verify_triangulation, TriangulationReport, TriangulationViolation,
+};
+pub use vertex_coloring::{
+    art_gallery_guards, three_coloring, verify_coloring, ColoringError, COLOR_CLASSES, UNCOLORED,
+};
+
+// Single phases of the pipeline, public for the benchmarks in benches/.
+#[doc(hidden)]
+pub use monotone_y_partition::{monotone_partition, to_event_queue};
+#[doc(hidden)]
+pub use vertex_coloring::traverse_faces;
 
 pub const NIL: usize = !0;
 
@@ -1,5 +1,9 @@
 use crate::monotone_y_partition::{monotone_partition, PartitionPolygon};
+use crate::polygon_validation::{validate_polygon, PolygonError};
+use crate::sweep_trace::{AlgorithmTrace, TracePhase, TraceStep};
 use crate::triangle_base::*;
+#[cfg(debug_assertions)]
+use crate::triangulation_validation::verify_triangulation;
 use egui::Pos2;
 use log::{debug, info};
 use std::cmp::Ordering;
@@ -79,12 +83,33 @@ fn inside_mono_poly(
     )
 }
 
+/// Record the state of monotone polygon triangulation after
+/// handling an event vertex.
+fn record_step(
+    trace: Option<&mut AlgorithmTrace>,
+    event_vertex: usize,
+    monotone_poly: &[usize],
+    process_stack: &[usize],
+    event_stack: &[usize],
+    diagonals: &[(usize, usize)],
+) {
+    if let Some(trace) = trace {
+        let mut step = TraceStep::new(TracePhase::TriangulateMonotone, event_vertex);
+        step.monotone_polygon = monotone_poly.to_vec();
+        step.process_stack = process_stack.to_vec();
+        step.event_stack = event_stack.to_vec();
+        step.diagonals = diagonals.to_vec();
+        trace.push(step);
+    }
+}
+
 /// Triangulate monotone polygon by
 /// adding new diagonals in PartitionPolygon
 fn triangulate_monotone(
     partition_poly: &mut PartitionPolygon,
     monotone_poly: &[usize],
     vertices: &[Pos2],
+    mut trace: Option<&mut AlgorithmTrace>,
 ) {
     // Partition is already a triangle
     if monotone_poly.len() <= 3 {
@@ -112,13 +137,13 @@ fn triangulate_monotone(
 
     let mut process_stack: Vec<usize> = Vec::new();
     let top_vertex = event_stack.pop().unwrap(); // top vertex of monotone polygon
-    // NOTE: I can't figure out why dereference works here. 
     let bottom_vertex = *event_stack.first().unwrap(); // bottom vertex of monotone polygon
     let mut prev_event_vertex = event_stack.pop().unwrap();
     process_stack.push(top_vertex); // push last vertex in event stack
     process_stack.push(prev_event_vertex); // push lastlast vertex in event stack
 
     while let Some(event_vertex) = event_stack.pop() {
+        let diag_count = partition_poly.diagonals.len();
         debug!("processing event vertex{}", event_vertex);
         debug!("process stack: {:?}", process_stack);
         debug!("event stack: {:?}", event_stack);
@@ -159,6 +184,14 @@ fn triangulate_monotone(
             process_stack.push(prev_event_vertex);
             process_stack.push(event_vertex);
         }
+        record_step(
+            trace.as_deref_mut(),
+            event_vertex,
+            monotone_poly,
+            &process_stack,
+            &event_stack,
+            &partition_poly.diagonals[diag_count..],
+        );
 
         if event_stack.len() == 1 {
             break;
@@ -168,35 +201,175 @@ fn triangulate_monotone(
 
     // Insert diagonals between the bottom event vertex and
     // all vertices left in process stack.
+    let diag_count = partition_poly.diagonals.len();
     let event_stack_bottom = event_stack.pop().unwrap();
     if process_stack.len() > 2 {
         for idx in process_stack[1..process_stack.len() - 1].iter() {
             partition_poly.insert_diagonal(event_stack_bottom, *idx);
         }
     }
+    record_step(
+        trace,
+        event_stack_bottom,
+        monotone_poly,
+        &process_stack,
+        &event_stack,
+        &partition_poly.diagonals[diag_count..],
+    );
 }
 
-/// Triangulate all monotone polygon partititons
-pub fn polygon_triangulation(
-    vertices: &Vec<Pos2>,
-    partition_poly: &mut PartitionPolygon,
-) -> Vec<Vec<Pos2>> {
-    // let mut partition_poly = PartitionPolygon::new();
+/// First phase of polygon_triangulation, partition `vertices` into
+/// monotone polygons.\
+/// Return the PartitionPolygon with the partition's diagonals and the
+/// monotone polygons described by vertex indices.
+pub fn partition_into_monotone(
+    vertices: &[Pos2],
+    trace: Option<&mut AlgorithmTrace>,
+) -> (PartitionPolygon, Vec<Vec<usize>>) {
+    let mut partition_poly = PartitionPolygon::new();
     partition_poly.build_from_pts(vertices);
 
     info!("---start monotone partition---");
-    monotone_partition(partition_poly);
-    partition_poly.sort_diagonals(vertices);
-    let mut monotone_polygons: Vec<Vec<usize>> = Vec::new();
-    partition_poly.make_polygons(0, &mut monotone_polygons, vertices);
-    partition_poly.reset_unused_diag_counts();
+    monotone_partition(&mut partition_poly, trace);
+    let monotone_polygons = partition_poly.make_faces(vertices);
+    (partition_poly, monotone_polygons)
+}
 
+/// Second phase of polygon_triangulation, insert the diagonals
+/// triangulating every monotone polygon, last polygon first.
+pub fn triangulate_monotone_polygons(
+    partition_poly: &mut PartitionPolygon,
+    mut monotone_polygons: Vec<Vec<usize>>,
+    vertices: &[Pos2],
+    mut trace: Option<&mut AlgorithmTrace>,
+) {
     info!("---start triangulate monotone polygon---");
     while let Some(monotone_poly) = monotone_polygons.pop() {
         info!("processing mono polygon: {:?}", monotone_poly);
-        triangulate_monotone(partition_poly, &monotone_poly, vertices);
+        triangulate_monotone(
+            partition_poly,
+            &monotone_poly,
+            vertices,
+            trace.as_deref_mut(),
+        );
+    }
+}
+
+/// Largest polygon whose triangulation is verified in debug builds,
+/// verify_triangulation takes O(n²) time.
+#[cfg(debug_assertions)]
+const DEBUG_VERIFY_MAX_VERTICES: usize = 1_000;
+
+/// Triangulate all monotone polygon partititons.\
+/// Return a new PartitionPolygon whose faces are the triangles,
+/// so calling it again never touches a previous result.\
+/// Events of both monotone partition and monotone polygon triangulation
+/// are recorded in `trace` if given.
+pub fn polygon_triangulation(
+    vertices: &[Pos2],
+    mut trace: Option<&mut AlgorithmTrace>,
+) -> PartitionPolygon {
+    let (mut partition_poly, monotone_polygons) =
+        partition_into_monotone(vertices, trace.as_deref_mut());
+    triangulate_monotone_polygons(&mut partition_poly, monotone_polygons, vertices, trace);
+
+    // Generate triangle partitions and link them with diagonals.
+    partition_poly.partition(vertices);
+
+    // Garbage in is garbage out, only the result of a valid polygon is checked.
+    #[cfg(debug_assertions)]
+    if vertices.len() <= DEBUG_VERIFY_MAX_VERTICES && validate_polygon(vertices).is_ok() {
+        let report = verify_triangulation(vertices, &partition_poly);
+        debug_assert!(report.is_valid(), "invalid triangulation: {}", report);
     }
+    partition_poly
+}
+
+/// Same as polygon_triangulation, but `vertices` are validated first,
+/// so an invalid polygon gives an error instead of a panic or garbage.
+pub fn try_polygon_triangulation(
+    vertices: &[Pos2],
+    trace: Option<&mut AlgorithmTrace>,
+) -> Result<PartitionPolygon, PolygonError> {
+    validate_polygon(vertices)?;
+    Ok(polygon_triangulation(vertices, trace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::polygon_triangulation;
+    use crate::monotone_y_partition::monotone_partition_polygon;
+    use crate::sweep_trace::{AlgorithmTrace, TracePhase};
+    use egui::Pos2;
 
-    // Generate monotone polygon partition and output coordinates
-    partition_poly.partition(vertices)
+    fn init_pts() -> Vec<Pos2> {
+        vec![
+            Pos2::new(218., 60.),
+            Pos2::new(251., 197.),
+            Pos2::new(362., 97.),
+            Pos2::new(460., 127.),
+            Pos2::new(527., 250.),
+            Pos2::new(628., 111.),
+            Pos2::new(688., 38.),
+            Pos2::new(739., 257.),
+            Pos2::new(646., 395.),
+            Pos2::new(530., 406.),
+            Pos2::new(380., 365.),
+            Pos2::new(257., 413.),
+        ]
+    }
+
+    #[test]
+    fn test_polygon_triangulation() {
+        let pts = init_pts();
+        let poly = polygon_triangulation(&pts, None);
+        assert_eq!(poly.faces.len(), pts.len() - 2);
+        assert_eq!(poly.diagonals.len(), pts.len() - 3);
+        for face in poly.face_coordinates() {
+            assert_eq!(face.len(), 3);
+        }
+
+        // Triangulating again gives the same result instead of
+        // growing the previous one.
+        let again = polygon_triangulation(&pts, None);
+        assert_eq!(again.vertices.len(), pts.len());
+        assert_eq!(again.face_coordinates(), poly.face_coordinates());
+    }
+
+    #[test]
+    fn test_triangulate_monotone_trace() {
+        let pts = init_pts();
+        let mut trace = AlgorithmTrace::default();
+        let poly = polygon_triangulation(&pts, Some(&mut trace));
+
+        // Every monotone polygon with m > 3 vertices takes m - 2 steps,
+        // the last one handles the bottom vertex.
+        let expected_steps: usize = monotone_partition_polygon(&pts)
+            .faces
+            .iter()
+            .map(|face| face.borrow().vertices.len())
+            .filter(|len| *len > 3)
+            .map(|len| len - 2)
+            .sum();
+        let steps = trace
+            .steps
+            .iter()
+            .filter(|step| step.phase == TracePhase::TriangulateMonotone)
+            .collect::<Vec<_>>();
+        assert!(expected_steps > 0);
+        assert_eq!(steps.len(), expected_steps);
+        // Triangulation steps come after the partition and add the rest of
+        // the diagonals.
+        let partition_steps = trace.len() - steps.len();
+        assert!(trace.steps[partition_steps..]
+            .iter()
+            .all(|step| step.phase == TracePhase::TriangulateMonotone));
+        let partition_diagonals = trace.diagonals_until(partition_steps - 1).len();
+        let diagonals = steps
+            .iter()
+            .flat_map(|step| step.diagonals.iter().copied())
+            .collect::<Vec<_>>();
+        assert_eq!(diagonals, poly.diagonals[partition_diagonals..]);
+        assert_eq!(trace.diagonals_until(trace.len() - 1), poly.diagonals);
+    }
 }
@@ -1,16 +1,20 @@
+use crate::sweep_trace::{AlgorithmTrace, TracePhase, TraceStep};
 use crate::triangle_base::*;
-use crate::Circulator;
+use crate::{Circulator, NIL};
 use core::panic;
 use egui::{Color32, Pos2};
+use kd_tree::KdMap;
 use log::{debug, info};
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::zip;
 use std::rc::Rc;
 
 use MiddleVertexStatus::{Concave, Convex};
 use Orientation::{ClockWise, CounterClockWise};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum VertexType {
     StartVertex,
     EndVertex,
@@ -21,7 +25,7 @@ pub enum VertexType {
 
 // HACK: A copy of PartitionPolygon vertices,
 // used as auxilary reference in sort_daig() and partition()
-type Vertices = Vec<Pos2>;
+type Vertices = [Pos2];
 
 pub struct PartitionVertex {
     // coordinates of point
@@ -45,7 +49,7 @@ impl PartitionVertex {
     }
 
     fn insert_diagonal(&mut self, vertex_idx: usize, half_diag: Rc<RefCell<HalfDiag>>) {
-        if self.diag_points.iter().any(|&x| x == vertex_idx) {
+        if self.diag_points.contains(&vertex_idx) {
             return;
         }
         self.diag_points.push(vertex_idx);
@@ -53,10 +57,6 @@ impl PartitionVertex {
         self.unused_diag_count += 1;
     }
 
-    fn magnified_pos_x(&self) -> i32 {
-        (self.point.x * 100.).round() as i32
-    }
-
     /// Sort diagonals in ccw order, by their agnle relative to the line,\
     /// formed by current vertex and its' next vertex in polygon.
     fn sort_diag(&mut self, next: &Pos2, vertices: &Vertices) {
@@ -166,6 +166,8 @@ impl Face {
 pub struct PartitionPolygon {
     pub vertices: Vec<PartitionVertex>,
     pub faces: Vec<Rc<RefCell<Face>>>,
+    // diagonals in the order of insertion
+    pub diagonals: Vec<(usize, usize)>,
     // pub faces: Vec<Face>,
 }
 
@@ -184,11 +186,18 @@ impl Circulator for PartitionPolygon {
     }
 }
 
+impl Default for PartitionPolygon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PartitionPolygon {
     pub fn new() -> Self {
         PartitionPolygon {
             vertices: Vec::new(),
             faces: Vec::new(),
+            diagonals: Vec::new(),
         }
     }
 
@@ -198,15 +207,20 @@ impl PartitionPolygon {
         if self.next(idx1) == idx2 || self.prev(idx1) == idx2 {
             return;
         }
+        if self.vertices[idx1].diag_points.contains(&idx2) {
+            return;
+        }
         let (halfdiag1, halfdiag2) = HalfDiag::spwan_twin(idx1, idx2);
         info!("insert diagonal between {} and {}", idx1, idx2);
+        self.diagonals.push((idx1, idx2));
         self.vertices[idx1].insert_diagonal(idx2, halfdiag1);
         self.vertices[idx2].insert_diagonal(idx1, halfdiag2);
     }
 
     /// Build a partition polygon from a list of vertices
-    /// in ccw order.
+    /// in ccw order, previous vertices, diagonals and faces are dropped.
     pub fn build_from_pts(&mut self, input: &[Pos2]) {
+        *self = PartitionPolygon::new();
         let input_iter = input.iter();
         for item in input_iter {
             self.vertices.push(PartitionVertex::new(item));
@@ -225,7 +239,7 @@ impl PartitionPolygon {
         &mut self,
         start: usize,
         result: &mut Vec<Vec<usize>>,
-        _vertices: &Vec<Pos2>,
+        _vertices: &Vertices,
     ) -> usize {
         let mut new_polygon: Vec<usize> = Vec::new();
         let mut idx: usize = start;
@@ -280,8 +294,9 @@ impl PartitionPolygon {
         }
     }
 
-    /// Output partitions described by a vector of vertices' coordinates
-    pub fn partition(&mut self, vertices: &Vertices) -> Vec<Vec<Pos2>> {
+    /// Sub-polygons cut by the diagonals, described by vertex indices.\
+    /// Diagonals are sorted first, so it can be called after inserting more.
+    pub fn make_faces(&mut self, vertices: &Vertices) -> Vec<Vec<usize>> {
         // HACK: change sort_diagonals's arg from PartitionVertex to Pos2,
         // since Vec<T> in PartitionVertex will cause multiple mutable
         // borrow of self, here, in this function.
@@ -291,12 +306,45 @@ impl PartitionPolygon {
         let ret = self.make_polygons(0, &mut result, vertices);
         debug!("the return of make_polygons:{}", ret);
         self.reset_unused_diag_counts();
+        result
+    }
+
+    /// Output partitions described by a vector of vertices' coordinates
+    pub fn partition(&mut self, vertices: &Vertices) -> Vec<Vec<Pos2>> {
+        let result = self.make_faces(vertices);
         self.link_face(&result, vertices);
         debug!("the num of polygon partition:{}", result.len());
         self.output_coordinates(&result)
     }
 
-    fn link_face(&mut self, result: &[Vec<usize>], vertices: &Vec<Pos2>) {
+    /// Kd-tree of the faces' centroids, mapping to the index of the face,
+    /// used to pick the triangle under the cursor.
+    pub fn face_kd_tree(&self) -> KdMap<[f32; 2], usize> {
+        let centroids = self.faces.iter().map(|face| {
+            let centroid = face.as_ref().borrow().centroid;
+            [centroid.x, centroid.y]
+        });
+        KdMap::build_by_ordered_float(Vec::from_iter(zip(centroids, 0..self.faces.len())))
+    }
+
+    /// Output faces described by their vertices' coordinates
+    pub fn face_coordinates(&self) -> Vec<Vec<Pos2>> {
+        self.faces
+            .iter()
+            .map(|face| {
+                face.as_ref()
+                    .borrow()
+                    .vertices
+                    .iter()
+                    .map(|idx| self.vertices[*idx].point)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Make faces of the partitions from make_polygons, and link them
+    /// with the half diagonals bounding them.
+    pub fn link_face(&mut self, result: &[Vec<usize>], vertices: &Vertices) {
         info!("---start link diag to face---");
         for partition in result.iter() {
             debug!("linking face{:?}", partition);
@@ -319,131 +367,194 @@ impl PartitionPolygon {
                         half_diag.borrow_mut().bounding_face = Some(new_face.clone());
                     }
                 }
-                self.faces.push(new_face.clone());
             }
+            self.faces.push(new_face);
         }
     }
 }
 
-struct PartitionTreeEntry {
-    edge_origin: usize, // idx to edge's origin in PartitionPolygon
-    helper: usize,      // idx to edge's helper in PartitionPlolygon
+/// Edge crossing the sweep line, ordered from left to right by its x at
+/// the highest height both compared edges span, or their lowest one if
+/// they start at the same point.\
+/// Edges of a simple polygon never cross, so the order of two edges holds
+/// while both are in the tree.
+#[derive(Clone, Copy, Debug)]
+struct SweepEdge {
+    top: Pos2,
+    bottom: Pos2,
+    origin: usize, // idx to edge's origin in PartitionPolygon
 }
 
-impl PartitionTreeEntry {
-    fn new(origin_idx: usize, helper_idx: usize) -> Self {
-        PartitionTreeEntry {
-            edge_origin: origin_idx,
-            helper: helper_idx,
+impl SweepEdge {
+    fn new(origin: usize, poly: &PartitionPolygon) -> Self {
+        let p = poly.vertices[origin].point;
+        let q = poly.vertices[poly.next(origin)].point;
+        let (top, bottom) = if is_above(&p, &q) { (p, q) } else { (q, p) };
+        SweepEdge {
+            top,
+            bottom,
+            origin,
+        }
+    }
+
+    /// A point on the sweep line, ordered after the edges through it.
+    fn probe(point: Pos2) -> Self {
+        SweepEdge {
+            top: point,
+            bottom: point,
+            origin: NIL,
         }
     }
-}
 
-impl Clone for PartitionTreeEntry {
-    fn clone(&self) -> Self {
-        PartitionTreeEntry {
-            edge_origin: self.edge_origin,
-            helper: self.helper,
+    /// x coordinate of the edge at height y, exact at its endpoints.\
+    /// A horizontal edge gives the x of its top (left) endpoint.
+    fn x_at(&self, y: f32) -> f32 {
+        if y == self.top.y {
+            return self.top.x;
         }
+        if y == self.bottom.y {
+            return self.bottom.x;
+        }
+        self.top.x + (self.bottom.x - self.top.x) * (y - self.top.y) / (self.bottom.y - self.top.y)
+    }
+}
+
+impl Ord for SweepEdge {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let cmp_at = |y: f32| self.x_at(y).total_cmp(&other.x_at(y));
+        cmp_at(self.top.y.min(other.top.y))
+            .then_with(|| cmp_at(self.bottom.y.max(other.bottom.y)))
+            .then(self.origin.cmp(&other.origin))
     }
 }
 
+impl PartialOrd for SweepEdge {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SweepEdge {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SweepEdge {}
+
+#[derive(Clone, Copy)]
+struct PartitionTreeEntry {
+    edge: SweepEdge,
+    helper: usize, // idx to edge's helper in PartitionPlolygon
+}
+
+/// Edges crossing the sweep line, which have the polygon's interior
+/// to their right, with their helpers.\
+/// Edges are looked up by their origin's idx or by their position on the
+/// sweep line, both in O(log n).
 struct PartitionTree {
-    search_tree: BTreeMap<i32, PartitionTreeEntry>,
-    keys: Vec<i32>,
+    /// Entries keyed by the edge's origin idx.
+    search_tree: BTreeMap<usize, PartitionTreeEntry>,
+    /// Edges ordered from left to right.
+    sweep_line: BTreeSet<SweepEdge>,
 }
 
 impl PartitionTree {
     fn new() -> Self {
         PartitionTree {
             search_tree: BTreeMap::new(),
-            keys: Vec::new(), // keys of binary tree map
+            sweep_line: BTreeSet::new(),
         }
     }
 
-    fn update_keys(&mut self) {
-        self.keys = self.search_tree.clone().into_keys().collect::<Vec<i32>>();
-    }
-
     /// Add a new edge and its helper by inserting its origin idx and its helper's idx.
     pub fn insert(&mut self, edge_origin_idx: usize, helper_idx: usize, poly: &PartitionPolygon) {
-        debug!("tree before insert:{:?}", self.keys);
-        let key = poly.vertices[edge_origin_idx].magnified_pos_x();
-        self.search_tree
-            .insert(key, PartitionTreeEntry::new(edge_origin_idx, helper_idx));
-        self.update_keys();
-        debug!("tree after insert:{:?}", self.keys);
-    }
-
-    /// Find the edge in the tree whose origin is edge_origin
-    pub fn find(&self, edge_origin: &PartitionVertex) -> i32 {
-        let vertex_pos_x = edge_origin.magnified_pos_x();
-        match self.keys.binary_search(&vertex_pos_x) {
-            Ok(a) => self.keys[a],
-            _ => {
-                panic!("can't find vertex in Tree")
-            }
+        let edge = SweepEdge::new(edge_origin_idx, poly);
+        let entry = PartitionTreeEntry {
+            edge,
+            helper: helper_idx,
+        };
+        if let Some(old) = self.search_tree.insert(edge_origin_idx, entry) {
+            self.sweep_line.remove(&old.edge);
         }
+        self.sweep_line.insert(edge);
+        debug!("tree after insert:{:?}", self.search_tree.keys());
     }
 
-    /// Erase an edge from tree
-    pub fn erase(&mut self, entry_key: i32) -> Result<(), i32> {
-        debug!("tree before erase:{:?}", self.keys);
-        if self.search_tree.remove(&entry_key).is_none() {
-            return Err(entry_key);
-        }
-        self.update_keys();
-        debug!("tree after erase:{:?}", self.keys);
-        Ok(())
+    /// Helper of the edge whose origin is edge_origin_idx, if it's in the tree.
+    pub fn helper(&self, edge_origin_idx: usize) -> Option<usize> {
+        self.search_tree
+            .get(&edge_origin_idx)
+            .map(|entry| entry.helper)
     }
 
-    /// Find the a vertex's nearest neighbor in tree
-    pub fn lower_bound(&self, vertex: &PartitionVertex, poly: &PartitionPolygon) -> i32 {
-        // return 0 when search tree is empty
-        if self.search_tree.is_empty() {
-            return 0;
+    /// Replace the helper of an edge, return false if it isn't in the tree.
+    pub fn update_helper(&mut self, edge_origin_idx: usize, helper_idx: usize) -> bool {
+        match self.search_tree.get_mut(&edge_origin_idx) {
+            Some(entry) => {
+                entry.helper = helper_idx;
+                true
+            }
+            None => false,
         }
-        // let pred = vertex.magnified_pos_x();
-        let low = self.keys.partition_point(|x| {
-            indirect_edge_compare(self.search_tree.get(x).unwrap(), vertex, poly)
-        }) - 1;
-        self.keys[low]
     }
-}
 
-/// Compare the x coordinate of the most left vertex of a line with a vertex.
-fn indirect_edge_compare(
-    a: &PartitionTreeEntry,
-    b: &PartitionVertex,
-    poly: &PartitionPolygon,
-) -> bool {
-    let edge_origin_x = poly.vertices[a.edge_origin].point.x;
-    let edge_end_x = poly.vertices[poly.next(a.edge_origin)].point.x;
-    let most_left_vertex_x = if edge_origin_x.le(&edge_end_x) {
-        edge_origin_x
-    } else {
-        edge_end_x
-    };
-    most_left_vertex_x.le(&b.point.x)
+    /// Erase an edge from tree, return its helper.
+    pub fn erase(&mut self, edge_origin_idx: usize) -> Option<usize> {
+        let entry = self.search_tree.remove(&edge_origin_idx)?;
+        self.sweep_line.remove(&entry.edge);
+        debug!("tree after erase:{:?}", self.search_tree.keys());
+        Some(entry.helper)
+    }
+
+    /// Find the edge nearest to the left of a point on the sweep line,
+    /// i.e. the largest x at the point's height not right of the point.\
+    /// An edge ending at the point belongs to another copy of a vertex
+    /// bridging a hole, it's right of this copy, so it's skipped.\
+    /// Falls back to the leftmost edge if rounding puts every edge to the
+    /// right, None if the tree is empty.
+    pub fn lower_bound(&self, point: Pos2) -> Option<usize> {
+        let probe = SweepEdge::probe(point);
+        self.sweep_line
+            .range(..=probe)
+            .rev()
+            .find(|edge| edge.top != point && edge.bottom != point)
+            .or_else(|| self.sweep_line.first())
+            .map(|edge| edge.origin)
+    }
 }
 
-/// Get event vertex's left neighbor in the search tree
-fn get_left_neighbor(
-    vertex: &PartitionVertex,
-    tree: &PartitionTree,
-    poly: &PartitionPolygon,
-) -> (i32, usize) {
-    let key = tree.lower_bound(vertex, poly);
-    (key, tree.search_tree[&key].helper)
+/// Get event vertex's left neighbor in the search tree and its helper.\
+/// A split, merge or regular vertex with the interior to its left always
+/// has one in a simple polygon, None means the input is invalid.
+fn get_left_neighbor(vertex: &PartitionVertex, tree: &PartitionTree) -> Option<(usize, usize)> {
+    let neighbor = tree
+        .lower_bound(vertex.point)
+        .and_then(|key| Some((key, tree.helper(key)?)));
+    debug_assert!(
+        neighbor.is_some(),
+        "no edge left of {:?} in the status tree",
+        vertex.point
+    );
+    neighbor
 }
 
-/// Update an edge's helper in the search tree\
-/// given it's origin's x coordinates(key)
-fn update_helper(key: i32, new_helper: usize, tree: &mut PartitionTree) {
-    if let Some(tree_entry) = tree.search_tree.get_mut(&key) {
-        tree_entry.helper = new_helper;
-    } else {
-        panic!("can't update helper");
+/// Erase the edge ending at `vertex_idx` and connect `vertex_idx` to its
+/// helper if that's a merge vertex.\
+/// The edge was inserted at its upper endpoint, so it's missing only if
+/// the input isn't a simple polygon.
+fn erase_prev_edge(vertex_idx: usize, tree: &mut PartitionTree, poly: &mut PartitionPolygon) {
+    let prev = poly.prev(vertex_idx);
+    let helper_prev_idx = tree.erase(prev);
+    debug_assert!(
+        helper_prev_idx.is_some(),
+        "edge{} isn't in the status tree",
+        prev
+    );
+    if let Some(helper_prev_idx) = helper_prev_idx {
+        if let VertexType::MergeVertex = monoton_vertex_type(poly, helper_prev_idx) {
+            poly.insert_diagonal(vertex_idx, helper_prev_idx);
+        }
     }
 }
 
@@ -468,7 +579,7 @@ fn monoton_vertex_type(poly: &PartitionPolygon, idx: usize) -> VertexType {
 /// All vertices are sorted by their y coordinates (from bottom to top, a.k.a, **incrementatl**).\
 /// If vertices are at the same height, they will
 /// be sorted by x coordinates (from right to left).
-fn to_event_queue(input: &[PartitionVertex]) -> Vec<usize> {
+pub fn to_event_queue(input: &[PartitionVertex]) -> Vec<usize> {
     let mut output = Vec::from_iter(0..input.len());
     output.sort_by(|a, b| {
         let a_pos = input[*a].point;
@@ -493,13 +604,7 @@ fn handle_start_vertex(vertex_idx: usize, tree: &mut PartitionTree, poly: &Parti
 }
 
 fn handle_end_vertex(vertex_idx: usize, tree: &mut PartitionTree, poly: &mut PartitionPolygon) {
-    let prev = poly.prev(vertex_idx);
-    let search_key = tree.find(&poly.vertices[prev]);
-    let helper_prev_idx = tree.search_tree[&search_key].helper;
-    if let VertexType::MergeVertex = monoton_vertex_type(poly, helper_prev_idx) {
-        poly.insert_diagonal(vertex_idx, helper_prev_idx);
-    }
-    let _ = tree.erase(search_key);
+    erase_prev_edge(vertex_idx, tree, poly);
 }
 
 /// Check if the polygon interior is in the right of **regular** vertex.\
@@ -524,60 +629,60 @@ fn handle_regular_vertex(vertex_idx: usize, tree: &mut PartitionTree, poly: &mut
     };
     if interior_to_right {
         debug!("interior is right to vertex{}", vertex_idx);
-        let prev = poly.prev(vertex_idx);
-        let search_key = tree.find(&poly.vertices[prev]);
-        let helper_prev_idx = tree.search_tree[&search_key].helper;
-        if let VertexType::MergeVertex = monoton_vertex_type(poly, helper_prev_idx) {
-            poly.insert_diagonal(vertex_idx, helper_prev_idx);
-        }
-        let _ = tree.erase(search_key);
+        erase_prev_edge(vertex_idx, tree, poly);
         tree.insert(vertex_idx, vertex_idx, poly);
     } else {
         debug!("interior is left to vertex{}", vertex_idx);
-        // BUG: If a regular vertex is colinear to a neighbor end vertex,
-        // then "no entry found for key" or "attempt to subtract with overflow in line 364"
-        let (left_neighbor_edge_key, left_neigbor_edge_helper) =
-            get_left_neighbor(&poly.vertices[vertex_idx], tree, poly);
-        if let VertexType::MergeVertex = monoton_vertex_type(poly, left_neigbor_edge_helper) {
-            poly.insert_diagonal(vertex_idx, left_neigbor_edge_helper);
+        // NOTE: A regular vertex colinear to a neighbor end vertex used to
+        // miss its left neighbor edge, see tests/golden/collinear_*.wkt.
+        if let Some((left_neighbor_edge_key, left_neigbor_edge_helper)) =
+            get_left_neighbor(&poly.vertices[vertex_idx], tree)
+        {
+            if let VertexType::MergeVertex = monoton_vertex_type(poly, left_neigbor_edge_helper) {
+                poly.insert_diagonal(vertex_idx, left_neigbor_edge_helper);
+            }
+            tree.update_helper(left_neighbor_edge_key, vertex_idx);
         }
-        update_helper(left_neighbor_edge_key, vertex_idx, tree);
     }
 }
 
 fn handle_split_vertex(vertex_idx: usize, tree: &mut PartitionTree, poly: &mut PartitionPolygon) {
-    let (left_neighbor_edge_key, left_neigbor_edge_helper) =
-        get_left_neighbor(&poly.vertices[vertex_idx], tree, poly);
-    poly.insert_diagonal(vertex_idx, left_neigbor_edge_helper);
-    update_helper(left_neighbor_edge_key, vertex_idx, tree);
+    if let Some((left_neighbor_edge_key, left_neigbor_edge_helper)) =
+        get_left_neighbor(&poly.vertices[vertex_idx], tree)
+    {
+        poly.insert_diagonal(vertex_idx, left_neigbor_edge_helper);
+        tree.update_helper(left_neighbor_edge_key, vertex_idx);
+    }
     tree.insert(vertex_idx, vertex_idx, poly);
 }
 
 fn handle_merge_vertex(vertex_idx: usize, tree: &mut PartitionTree, poly: &mut PartitionPolygon) {
-    let prev = poly.prev(vertex_idx);
-    let search_key = tree.find(&poly.vertices[prev]);
-    let helper_prev_idx = tree.search_tree[&search_key].helper;
-    if let VertexType::MergeVertex = monoton_vertex_type(poly, helper_prev_idx) {
-        poly.insert_diagonal(vertex_idx, helper_prev_idx);
-    }
-    let _ = tree.erase(search_key);
-    let (left_neighbor_edge_key, left_neigbor_edge_helper) =
-        get_left_neighbor(&poly.vertices[vertex_idx], tree, poly);
-    if let VertexType::MergeVertex = monoton_vertex_type(poly, left_neigbor_edge_helper) {
-        poly.insert_diagonal(vertex_idx, left_neigbor_edge_helper);
+    erase_prev_edge(vertex_idx, tree, poly);
+    if let Some((left_neighbor_edge_key, left_neigbor_edge_helper)) =
+        get_left_neighbor(&poly.vertices[vertex_idx], tree)
+    {
+        if let VertexType::MergeVertex = monoton_vertex_type(poly, left_neigbor_edge_helper) {
+            poly.insert_diagonal(vertex_idx, left_neigbor_edge_helper);
+        }
+        tree.update_helper(left_neighbor_edge_key, vertex_idx);
     }
-    update_helper(left_neighbor_edge_key, vertex_idx, tree);
 }
 
 /// Monotone partition a polygon by inserting diagonals in PartitionPolygon
 /// NOTE: I can't figure out if it's the right way to define a function
-/// that requires a &mut parameter.
-pub fn monotone_partition(partition_poly: &mut PartitionPolygon) {
+/// that requires a &mut parameter.\
+/// Every handled event vertex is recorded in `trace` if given.
+pub fn monotone_partition(
+    partition_poly: &mut PartitionPolygon,
+    mut trace: Option<&mut AlgorithmTrace>,
+) {
     let mut tree = PartitionTree::new();
     let mut event_queue = to_event_queue(&partition_poly.vertices);
     debug!("monotone partition event queue:{:?}", event_queue);
     while let Some(event_idx) = event_queue.pop() {
-        match monoton_vertex_type(partition_poly, event_idx) {
+        let diag_count = partition_poly.diagonals.len();
+        let vertex_type = monoton_vertex_type(partition_poly, event_idx);
+        match vertex_type {
             VertexType::StartVertex => {
                 info!("vertex{} is start vertex", event_idx);
                 handle_start_vertex(event_idx, &mut tree, partition_poly);
@@ -599,16 +704,53 @@ pub fn monotone_partition(partition_poly: &mut PartitionPolygon) {
                 handle_merge_vertex(event_idx, &mut tree, partition_poly);
             }
         }
+
+        if let Some(trace) = trace.as_deref_mut() {
+            let mut step = TraceStep::new(TracePhase::MonotonePartition, event_idx);
+            step.vertex_type = Some(vertex_type);
+            step.status = tree
+                .search_tree
+                .iter()
+                .map(|(origin, entry)| (*origin, entry.helper))
+                .collect();
+            step.event_stack = event_queue.clone();
+            step.diagonals = partition_poly.diagonals[diag_count..].to_vec();
+            trace.push(step);
+        }
+    }
+}
+
+/// Classify every vertex of a polygon defined in ccw order into
+/// start/end/split/merge/regular vertex.\
+/// Return empty vector if there are less than 3 vertices.
+pub fn vertex_types(vertices: &[Pos2]) -> Vec<VertexType> {
+    if vertices.len() < 3 {
+        return Vec::new();
     }
+    let mut partition_poly = PartitionPolygon::new();
+    partition_poly.build_from_pts(vertices);
+    (0..vertices.len())
+        .map(|idx| monoton_vertex_type(&partition_poly, idx))
+        .collect()
+}
+
+/// Monotone partition a polygon, return a PartitionPolygon whose faces
+/// are the y-monotone pieces.
+pub fn monotone_partition_polygon(vertices: &[Pos2]) -> PartitionPolygon {
+    let mut partition_poly = PartitionPolygon::new();
+    partition_poly.build_from_pts(vertices);
+    monotone_partition(&mut partition_poly, None);
+    partition_poly.partition(vertices);
+    partition_poly
 }
 
 /// Monotone partition a polygon and output partitions' vertices coordinates.
-pub fn monotone_polygon_partition(vertices: &Vec<Pos2>) -> Vec<Vec<Pos2>> {
+pub fn monotone_polygon_partition(vertices: &[Pos2]) -> Vec<Vec<Pos2>> {
     let mut partition_poly = PartitionPolygon::new();
     // let vertices_rc = vertices.iter().map(|x| Rc::new(x.clone()));
     partition_poly.build_from_pts(vertices);
 
-    monotone_partition(&mut partition_poly);
+    monotone_partition(&mut partition_poly, None);
     // Debug only
     // diagonals: 5<->3, 1<->3
     // assert_eq!(partition_poly.vertices[4].diag_points, Vec::new());
@@ -626,10 +768,11 @@ mod tests {
     use std::vec;
 
     use super::{
-        monotone_polygon_partition, to_event_queue, PartitionPolygon, PartitionTree,
-        PartitionVertex, VertexType,
+        monotone_partition, monotone_polygon_partition, to_event_queue, vertex_types,
+        PartitionPolygon, PartitionTree, PartitionVertex, SweepEdge, VertexType,
     };
     use crate::monotone_y_partition::monoton_vertex_type;
+    use crate::sweep_trace::AlgorithmTrace;
     use egui::Pos2;
 
     #[test]
@@ -715,16 +858,133 @@ mod tests {
         assert_eq!(results_string, gts);
     }
 
+    #[test]
+    fn test_monotone_partition_trace() {
+        let mut poly = init_poly();
+        let mut trace = AlgorithmTrace::default();
+        monotone_partition(&mut poly, Some(&mut trace));
+
+        let event_order = trace
+            .steps
+            .iter()
+            .map(|step| step.event_vertex)
+            .collect::<Vec<usize>>();
+        assert_eq!(event_order, vec![4, 6, 5, 3, 1, 0, 2]);
+        let results_string = trace
+            .steps
+            .iter()
+            .map(|step| vertex_type_to_string(&step.vertex_type.unwrap()))
+            .collect::<Vec<String>>();
+        let gts = vec!["start", "start", "merge", "regular", "split", "end", "end"];
+        assert_eq!(results_string, gts);
+        assert_eq!(trace.diagonals_until(trace.len() - 1), poly.diagonals);
+        // Status tree is empty after the last end vertex
+        assert!(trace.steps.last().unwrap().status.is_empty());
+    }
+
+    #[test]
+    fn test_vertex_types() {
+        let pts = init_poly()
+            .vertices
+            .iter()
+            .map(|vertex| vertex.point)
+            .collect::<Vec<Pos2>>();
+        let results_string = vertex_types(&pts)
+            .iter()
+            .map(vertex_type_to_string)
+            .collect::<Vec<String>>();
+        let gts = vec!["end", "split", "end", "regular", "start", "merge", "start"];
+        assert_eq!(results_string, gts);
+        assert!(vertex_types(&pts[..2]).is_empty());
+    }
+
     #[test]
     fn test_lowerbound() {
         let poly = init_poly();
         let mut tree = PartitionTree::new();
-        for idx in 3..7 {
+        assert_eq!(tree.lower_bound(poly.vertices[3].point), None);
+        for idx in [4, 6] {
             tree.insert(idx, idx, &poly);
         }
-        assert_eq!(tree.keys, vec![100, 150, 350, 500]);
-        // binary search for vertex(2., 1.)'s nearset left neighbor
-        assert_eq!(tree.lower_bound(&poly.vertices[1], &poly), 350);
+        assert_eq!(tree.search_tree.keys().collect::<Vec<_>>(), vec![&4, &6]);
+        // edge 4->5 is at x 1.5 on the sweep line through vertex(5., 1.5),
+        // right of edge 6->0 at x 1.
+        assert_eq!(tree.lower_bound(poly.vertices[3].point), Some(4));
+        assert_eq!(tree.lower_bound(Pos2::new(1.2, 1.5)), Some(6));
+        // An edge through the point is left of it.
+        assert_eq!(tree.lower_bound(Pos2::new(2.5, 2.25)), Some(4));
+        // An edge ending at the point is right of it.
+        assert_eq!(tree.lower_bound(poly.vertices[5].point), Some(6));
+        // Every edge is right of the point, the leftmost one is nearest.
+        assert_eq!(tree.lower_bound(Pos2::new(0.5, 1.5)), Some(6));
+    }
+
+    #[test]
+    fn test_partition_tree() {
+        let poly = init_poly();
+        let mut tree = PartitionTree::new();
+        for idx in [4, 6, 2] {
+            tree.insert(idx, idx, &poly);
+        }
+        // Left to right on the sweep line, whatever the insertion order.
+        let origins = |tree: &PartitionTree| {
+            tree.sweep_line
+                .iter()
+                .map(|edge| edge.origin)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(origins(&tree), vec![6, 4, 2]);
+
+        assert_eq!(tree.helper(4), Some(4));
+        assert!(tree.update_helper(4, 5));
+        assert_eq!(tree.helper(4), Some(5));
+        assert_eq!(tree.helper(3), None);
+        assert!(!tree.update_helper(3, 5));
+
+        assert_eq!(tree.erase(4), Some(5));
+        assert_eq!(tree.erase(4), None);
+        assert_eq!(tree.helper(4), None);
+        assert_eq!(origins(&tree), vec![6, 2]);
+        assert_eq!(tree.lower_bound(Pos2::new(4., 1.5)), Some(6));
+    }
+
+    #[test]
+    fn test_sweep_edge_order() {
+        let edge = |top: (f32, f32), bottom: (f32, f32), origin: usize| SweepEdge {
+            top: Pos2::new(top.0, top.1),
+            bottom: Pos2::new(bottom.0, bottom.1),
+            origin,
+        };
+        let left = edge((0., 2.), (0., 0.), 0);
+        assert_eq!(left.x_at(1.), 0.);
+        let slanted = edge((1., 2.), (3., 0.), 1);
+        assert_eq!(slanted.x_at(1.), 2.);
+        assert!(left < slanted);
+        // Sharing the top vertex, the one going down to the left comes first.
+        let from_top = edge((1., 2.), (2., 0.), 2);
+        assert!(from_top < slanted);
+        // Edges are compared where their y ranges overlap.
+        let short = edge((2.5, 1.), (2.5, 0.), 3);
+        assert!(slanted < short);
+        // An edge through the probe point is left of it.
+        assert!(slanted < SweepEdge::probe(Pos2::new(2., 1.)));
+        assert!(SweepEdge::probe(Pos2::new(1.9, 1.)) < slanted);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "status tree")]
+    fn test_missing_status_edge() {
+        // Clockwise, every edge is inserted on the wrong side.
+        let mut pts = init_poly()
+            .vertices
+            .iter()
+            .map(|vertex| vertex.point)
+            .collect::<Vec<_>>();
+        pts.reverse();
+        let mut poly = PartitionPolygon::new();
+        poly.build_from_pts(&pts);
+        monotone_partition(&mut poly, None);
     }
 
     #[test]
@@ -775,4 +1035,37 @@ mod tests {
         ];
         assert_eq!(res_iter.next().unwrap(), &gt);
     }
+
+    #[test]
+    fn test_link_face() {
+        // Vertices with several diagonals used to add their face once per
+        // diagonal.
+        for pts in [
+            init_poly().vertices.iter().map(|x| x.point).collect(),
+            crate::shapes::regular_polygon(12, 100.),
+            crate::shapes::comb(6),
+        ] {
+            let poly = crate::monotone_triangulation::polygon_triangulation(&pts, None);
+            assert_eq!(poly.faces.len(), pts.len() - 2);
+            let mut faces = poly
+                .faces
+                .iter()
+                .map(|face| {
+                    let mut vertices = face.borrow().vertices.clone();
+                    vertices.sort();
+                    vertices
+                })
+                .collect::<Vec<_>>();
+            faces.sort();
+            faces.dedup();
+            assert_eq!(faces.len(), pts.len() - 2);
+            // Every diagonal bounds two faces.
+            let bounding_diags: usize = poly
+                .faces
+                .iter()
+                .map(|face| face.borrow().bounding_diags.len())
+                .sum();
+            assert_eq!(bounding_diags, 2 * poly.diagonals.len());
+        }
+    }
 }
@@ -0,0 +1,154 @@
+use crate::triangle_base::*;
+use egui::Pos2;
+use std::fmt::Display;
+
+/// Reasons why a vertex ring can't be triangulated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolygonError {
+    /// Vertex given by its index has a NaN or infinite coordinate.
+    NonFiniteVertex(usize),
+    /// A polygon needs at least 3 vertices.
+    TooFewVertices(usize),
+    /// Two vertices share the same coordinates.
+    DuplicateVertex(usize, usize),
+    /// Two edges, given by the index of their origin, cross or touch each other.
+    SelfIntersection(usize, usize),
+    /// Vertices are in cw order, or the polygon has no area.
+    NotCounterClockWise,
+    /// Hole given by its index isn't inside the exterior, or is inside another hole.
+    HoleOutside(usize),
+}
+
+impl Display for PolygonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolygonError::NonFiniteVertex(i) => {
+                write!(f, "vertex{} has a non-finite coordinate", i)
+            }
+            PolygonError::TooFewVertices(n) => {
+                write!(f, "polygon needs at least 3 vertices, got {}", n)
+            }
+            PolygonError::DuplicateVertex(i, j) => {
+                write!(f, "vertex{} and vertex{} are at the same position", i, j)
+            }
+            PolygonError::SelfIntersection(i, j) => {
+                write!(f, "edge{} intersects edge{}", i, j)
+            }
+            PolygonError::NotCounterClockWise => {
+                write!(f, "vertices are not in counter-clockwise order")
+            }
+            PolygonError::HoleOutside(i) => {
+                write!(f, "hole{} isn't inside the polygon", i)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+/// Check that `vertices` form a simple polygon in ccw order,
+/// which is what polygon triangulation expects.\
+/// Edge i goes from vertex i to vertex i+1.\
+/// Self intersection is checked by comparing all edge pairs,
+/// so it takes O(n^2) time.
+pub fn validate_polygon(vertices: &[Pos2]) -> Result<(), PolygonError> {
+    if let Some(i) = vertices
+        .iter()
+        .position(|p| !p.x.is_finite() || !p.y.is_finite())
+    {
+        return Err(PolygonError::NonFiniteVertex(i));
+    }
+    let n = vertices.len();
+    if n < 3 {
+        return Err(PolygonError::TooFewVertices(n));
+    }
+
+    for i in 0..n {
+        let (p, q) = (&vertices[i], &vertices[(i + 1) % n]);
+        if p == q {
+            return Err(PolygonError::DuplicateVertex(i, (i + 1) % n));
+        }
+    }
+
+    for i in 0..n {
+        let (p1, p2) = (&vertices[i], &vertices[(i + 1) % n]);
+        // Adjacent edges share a vertex, they only intersect elsewhere
+        // if they're colinear and fold back onto each other.
+        let p3 = &vertices[(i + 2) % n];
+        let folds_back = (p1.x - p2.x) * (p3.x - p2.x) + (p1.y - p2.y) * (p3.y - p2.y) > 0.;
+        if cross(p1, p2, p3) == 0. && folds_back {
+            return Err(PolygonError::SelfIntersection(i, (i + 1) % n));
+        }
+        for j in (i + 2)..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (q1, q2) = (&vertices[j], &vertices[(j + 1) % n]);
+            if segments_intersect(p1, p2, q1, q2) {
+                if let Some(k) = [q1, q2].iter().position(|q| *q == p1 || *q == p2) {
+                    let other = if *[q1, q2][k] == *p1 { i } else { (i + 1) % n };
+                    return Err(PolygonError::DuplicateVertex(other, (j + k) % n));
+                }
+                return Err(PolygonError::SelfIntersection(i, j));
+            }
+        }
+    }
+
+    if signed_area(vertices) <= 0. {
+        return Err(PolygonError::NotCounterClockWise);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_polygon, PolygonError};
+    use egui::Pos2;
+
+    fn to_pts(pts: &[(f32, f32)]) -> Vec<Pos2> {
+        pts.iter().map(|(x, y)| Pos2::new(*x, *y)).collect()
+    }
+
+    #[test]
+    fn test_validate_polygon() {
+        let ccw = to_pts(&[(0., 0.), (4., 1.), (5., 4.), (1., 3.)]);
+        assert_eq!(validate_polygon(&ccw), Ok(()));
+
+        let cw = ccw.iter().rev().copied().collect::<Vec<Pos2>>();
+        assert_eq!(
+            validate_polygon(&cw),
+            Err(PolygonError::NotCounterClockWise)
+        );
+
+        assert_eq!(
+            validate_polygon(&ccw[..2]),
+            Err(PolygonError::TooFewVertices(2))
+        );
+
+        let bowtie = to_pts(&[(0., 0.), (4., 1.), (1., 3.), (5., 4.)]);
+        assert_eq!(
+            validate_polygon(&bowtie),
+            Err(PolygonError::SelfIntersection(1, 3))
+        );
+
+        let square = to_pts(&[(0., 0.), (4., 0.), (4., 4.), (0., 4.)]);
+        assert_eq!(validate_polygon(&square), Ok(()));
+
+        let pinched = to_pts(&[(0., 0.), (4., 1.), (2., 2.), (5., 4.), (1., 3.), (2., 2.)]);
+        assert_eq!(
+            validate_polygon(&pinched),
+            Err(PolygonError::DuplicateVertex(2, 5))
+        );
+
+        let nan = to_pts(&[(0., 0.), (4., 1.), (f32::NAN, 4.)]);
+        assert_eq!(
+            validate_polygon(&nan),
+            Err(PolygonError::NonFiniteVertex(2))
+        );
+        let infinite = to_pts(&[(0., 0.), (4., f32::INFINITY)]);
+        assert_eq!(
+            validate_polygon(&infinite),
+            Err(PolygonError::NonFiniteVertex(1))
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use crate::import::PolygonWithHoles;
+use crate::shapes::{comb, spiral};
+use crate::transform_pos::{transform_polygon, TransformPos};
+use egui::{pos2, vec2, Pos2};
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresetCategory {
+    Textbook,
+    /// Polygons used by unit tests.
+    UnitTest,
+    Comb,
+    Spiral,
+    Rectilinear,
+    Holes,
+    /// Invalid or tricky inputs, which are expected to be rejected
+    /// or to trigger known bugs.
+    Degenerate,
+}
+
+impl PresetCategory {
+    pub const ALL: [PresetCategory; 7] = [
+        PresetCategory::Textbook,
+        PresetCategory::UnitTest,
+        PresetCategory::Comb,
+        PresetCategory::Spiral,
+        PresetCategory::Rectilinear,
+        PresetCategory::Holes,
+        PresetCategory::Degenerate,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PresetCategory::Textbook => "Textbook",
+            PresetCategory::UnitTest => "Unit tests",
+            PresetCategory::Comb => "Combs",
+            PresetCategory::Spiral => "Spirals",
+            PresetCategory::Rectilinear => "Rectilinear",
+            PresetCategory::Holes => "With holes",
+            PresetCategory::Degenerate => "Degenerate",
+        }
+    }
+}
+
+/// A named built-in test polygon, in model space around the default view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub category: PresetCategory,
+    pub description: &'static str,
+    pub polygon: PolygonWithHoles,
+}
+
+/// Name of the preset shown when the app starts.
+pub const DEFAULT_PRESET: &str = "Two split vertices";
+
+fn preset(
+    name: &'static str,
+    category: PresetCategory,
+    description: &'static str,
+    polygon: PolygonWithHoles,
+) -> Preset {
+    Preset {
+        name,
+        category,
+        description,
+        polygon,
+    }
+}
+
+/// Ring from a list of coordinates.
+fn ring(coordinates: &[(f32, f32)]) -> Vec<Pos2> {
+    coordinates.iter().map(|(x, y)| pos2(*x, *y)).collect()
+}
+
+/// Scale a ring given in small units up to the default view.
+fn scaled(coordinates: &[(f32, f32)], scale: f32, offset: (f32, f32)) -> Vec<Pos2> {
+    let transform = TransformPos::new(vec2(offset.0, offset.1), vec2(scale, scale));
+    transform_polygon(&ring(coordinates), &transform)
+}
+
+/// All built-in presets, grouped by category.
+pub fn presets() -> Vec<Preset> {
+    use PresetCategory::*;
+    vec![
+        preset(
+            DEFAULT_PRESET,
+            Textbook,
+            "Split and merge vertices on both sides, the app's long-time default.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (218., 60.),
+                    (251., 197.),
+                    (362., 97.),
+                    (460., 127.),
+                    (527., 250.),
+                    (628., 111.),
+                    (688., 38.),
+                    (739., 257.),
+                    (646., 395.),
+                    (530., 406.),
+                    (380., 365.),
+                    (257., 413.),
+                ]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Seven vertices",
+            Textbook,
+            "Every vertex type appears, used by most unit tests.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (157., 29.),
+                    (308., 173.),
+                    (481., 49.),
+                    (624., 180.),
+                    (500., 349.),
+                    (378., 286.),
+                    (185., 333.),
+                ]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Convex octagon",
+            Textbook,
+            "Already monotone, no diagonal is needed before triangulating.",
+            PolygonWithHoles::new(
+                (0..8)
+                    .map(|i| {
+                        let angle = (i as f32 + 0.3) * PI / 4.;
+                        pos2(400. + 180. * angle.cos(), 250. + 180. * angle.sin())
+                    })
+                    .collect(),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Quadrilateral",
+            UnitTest,
+            "Smallest polygon of the partition tests.",
+            PolygonWithHoles::new(
+                scaled(&[(1., 0.), (2., 1.), (2., 2.), (0., 1.)], 150., (150., 50.)),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Split vertex",
+            UnitTest,
+            "Polygon of the monotone partition test with a split vertex.",
+            PolygonWithHoles::new(
+                scaled(
+                    &[
+                        (1., 0.),
+                        (2., 1.),
+                        (3., 0.),
+                        (5., 1.5),
+                        (3.5, 3.),
+                        (1.5, 1.5),
+                        (1., 2.4),
+                    ],
+                    120.,
+                    (100., 60.),
+                ),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Comb (5 teeth)",
+            Comb,
+            "Every valley is a merge vertex.",
+            PolygonWithHoles::new(comb(5), Vec::new()),
+        ),
+        preset(
+            "Comb (20 teeth)",
+            Comb,
+            "Many thin teeth, lots of merge vertices.",
+            PolygonWithHoles::new(comb(20), Vec::new()),
+        ),
+        preset(
+            "Spiral",
+            Spiral,
+            "One and a half turns, a long chain of regular vertices.",
+            PolygonWithHoles::new(spiral(1.5, 40), Vec::new()),
+        ),
+        preset(
+            "Tight spiral",
+            Spiral,
+            "Two and a half turns sampled densely.",
+            PolygonWithHoles::new(spiral(2.5, 120), Vec::new()),
+        ),
+        preset(
+            "L shape",
+            Rectilinear,
+            "One reflex corner, every edge is axis aligned.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (200., 80.),
+                    (600., 80.),
+                    (600., 200.),
+                    (320., 200.),
+                    (320., 420.),
+                    (200., 420.),
+                ]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Staircase",
+            Rectilinear,
+            "Steps of horizontal and vertical edges.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (150., 80.),
+                    (650., 80.),
+                    (650., 180.),
+                    (530., 180.),
+                    (530., 260.),
+                    (410., 260.),
+                    (410., 340.),
+                    (290., 340.),
+                    (290., 420.),
+                    (150., 420.),
+                ]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "H shape",
+            Rectilinear,
+            "Notches at the top and the bottom, every edge is axis aligned.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (200., 60.),
+                    (320., 60.),
+                    (320., 200.),
+                    (480., 200.),
+                    (480., 60.),
+                    (600., 60.),
+                    (600., 440.),
+                    (480., 440.),
+                    (480., 300.),
+                    (320., 300.),
+                    (320., 440.),
+                    (200., 440.),
+                ]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Square with a hole",
+            Holes,
+            "The hole is bridged to the exterior before triangulating.",
+            PolygonWithHoles::new(
+                ring(&[(200., 50.), (600., 50.), (600., 450.), (200., 450.)]),
+                vec![ring(&[
+                    (330., 180.),
+                    (470., 180.),
+                    (470., 320.),
+                    (330., 320.),
+                ])],
+            ),
+        ),
+        preset(
+            "Two holes",
+            Holes,
+            "Both holes are bridged to the exterior, no triangle covers them.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (120., 60.),
+                    (680., 90.),
+                    (650., 430.),
+                    (400., 380.),
+                    (150., 440.),
+                ]),
+                vec![
+                    ring(&[(220., 150.), (330., 170.), (300., 300.)]),
+                    ring(&[(450., 180.), (580., 160.), (560., 320.), (470., 300.)]),
+                ],
+            ),
+        ),
+        preset(
+            "Horizontal edges",
+            Degenerate,
+            "Axis aligned rectangle, equal heights are ordered by x.",
+            PolygonWithHoles::new(
+                ring(&[(200., 100.), (600., 100.), (600., 400.), (200., 400.)]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Collinear vertices",
+            Degenerate,
+            "Several vertices on one slanted line.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (200., 80.),
+                    (600., 120.),
+                    (500., 220.),
+                    (400., 320.),
+                    (300., 420.),
+                ]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Duplicate vertex",
+            Degenerate,
+            "Two vertices at the same position.",
+            PolygonWithHoles::new(
+                ring(&[
+                    (200., 80.),
+                    (600., 120.),
+                    (520., 300.),
+                    (520., 300.),
+                    (300., 420.),
+                ]),
+                Vec::new(),
+            ),
+        ),
+        preset(
+            "Bowtie",
+            Degenerate,
+            "Self-intersecting, two edges cross.",
+            PolygonWithHoles {
+                exterior: ring(&[(200., 80.), (600., 420.), (600., 100.), (200., 400.)]),
+                holes: Vec::new(),
+            },
+        ),
+        preset(
+            "Clockwise",
+            Degenerate,
+            "Vertices in clockwise order.",
+            PolygonWithHoles {
+                exterior: ring(&[(200., 80.), (350., 420.), (600., 100.)]),
+                holes: Vec::new(),
+            },
+        ),
+    ]
+}
+
+/// Look up a preset by name.
+pub fn find_preset(name: &str) -> Option<Preset> {
+    presets().into_iter().find(|x| x.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{presets, PresetCategory};
+    use crate::holes::try_polygon_with_holes_triangulation;
+
+    #[test]
+    fn test_presets() {
+        for preset in presets() {
+            let polygon = &preset.polygon;
+            let result =
+                try_polygon_with_holes_triangulation(&polygon.exterior, &polygon.holes, None);
+            if preset.category == PresetCategory::Degenerate {
+                // Collinear vertices and horizontal edges are valid,
+                // the rest are rejected.
+                assert_eq!(
+                    result.is_ok(),
+                    ["Collinear vertices", "Horizontal edges"].contains(&preset.name),
+                    "{}",
+                    preset.name
+                );
+                continue;
+            }
+            let (_, poly) = result.unwrap_or_else(|err| panic!("{}: {}", preset.name, err));
+            let vertices =
+                polygon.exterior.len() + polygon.holes.iter().map(Vec::len).sum::<usize>();
+            assert_eq!(
+                poly.faces.len(),
+                vertices + 2 * polygon.holes.len() - 2,
+                "{}",
+                preset.name
+            );
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::camera::Camera;
+use crate::import::{ParseError, PolygonWithHoles};
+use crate::snapping::SnapSettings;
+use egui::Pos2;
+use serde::{Deserialize, Serialize};
+
+/// Version of project files written by this build.\
+/// Files of an older version are read with missing fields defaulted,
+/// files of a newer version are rejected.
+pub const PROJECT_VERSION: u32 = 1;
+
+/// File extension of project files, the content is JSON.
+pub const PROJECT_EXTENSION: &str = "trproj";
+
+/// What is run on a polygon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    /// Monotone partition only.
+    Partition,
+    Triangulate,
+    /// Triangulate and 3-color vertices.
+    Color,
+    /// Place art gallery guards on the smallest color class.
+    Guards,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 4] = [
+        Algorithm::Partition,
+        Algorithm::Triangulate,
+        Algorithm::Color,
+        Algorithm::Guards,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Partition => "partition",
+            Algorithm::Triangulate => "triangulate",
+            Algorithm::Color => "color",
+            Algorithm::Guards => "guards",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+}
+
+/// A text note pinned to a position in model space.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Annotation {
+    pub pos: Pos2,
+    pub text: String,
+}
+
+/// How the polygon is looked at.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ViewSettings {
+    pub camera: Camera,
+    pub show_vertex_types: bool,
+    pub show_guards: bool,
+    pub snap_settings: SnapSettings,
+    pub stroke_width: f32,
+    pub radius: f32,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            camera: Camera::default(),
+            show_vertex_types: false,
+            show_guards: false,
+            snap_settings: SnapSettings::default(),
+            stroke_width: 2.,
+            radius: 5.,
+        }
+    }
+}
+
+/// Everything needed to reopen exactly the same state of work.\
+/// The exterior of the first polygon is the one being triangulated,
+/// other rings are carried along untouched.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Project {
+    pub version: u32,
+    pub polygons: Vec<PolygonWithHoles>,
+    /// None if nothing has been run yet.
+    pub algorithm: Option<Algorithm>,
+    /// Re-run the algorithm on every edit.
+    pub live_mode: bool,
+    /// Start face of 3-coloring.
+    pub start_face: Option<usize>,
+    pub selected_face: Option<usize>,
+    /// Color class of every vertex of the first polygon, empty if not colored.
+    pub color_classes: Vec<u8>,
+    pub annotations: Vec<Annotation>,
+    pub view: ViewSettings,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            version: PROJECT_VERSION,
+            polygons: Vec::new(),
+            algorithm: None,
+            live_mode: false,
+            start_face: None,
+            selected_face: None,
+            color_classes: Vec::new(),
+            annotations: Vec::new(),
+            view: ViewSettings::default(),
+        }
+    }
+}
+
+impl Project {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("project is serializable")
+    }
+
+    /// Read a project file, the version is checked before anything else.
+    pub fn from_json(text: &str) -> Result<Project, ParseError> {
+        let json_error =
+            |err: serde_json::Error| ParseError::new(err.line(), err.column(), err.to_string());
+        #[derive(Deserialize)]
+        struct Header {
+            version: Option<u32>,
+        }
+        let header: Header = serde_json::from_str(text).map_err(json_error)?;
+        match header.version {
+            None => Err(ParseError::new(
+                1,
+                1,
+                "not a project file, version is missing",
+            )),
+            Some(version) if version > PROJECT_VERSION => Err(ParseError::new(
+                1,
+                1,
+                format!(
+                    "project file version {} is newer than supported version {}",
+                    version, PROJECT_VERSION
+                ),
+            )),
+            Some(_) => serde_json::from_str(text).map_err(json_error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, Annotation, Project, PROJECT_VERSION};
+    use crate::import::PolygonWithHoles;
+    use egui::pos2;
+
+    #[test]
+    fn test_project() {
+        let mut project = Project {
+            polygons: vec![PolygonWithHoles::new(
+                vec![pos2(0., 0.), pos2(10., 1.), pos2(5., 10.)],
+                vec![vec![pos2(4., 2.), pos2(5., 5.), pos2(6., 2.5)]],
+            )],
+            algorithm: Some(Algorithm::Guards),
+            start_face: Some(0),
+            color_classes: vec![0, 1, 2],
+            annotations: vec![Annotation {
+                pos: pos2(5., 5.),
+                text: "look here".to_owned(),
+            }],
+            ..Default::default()
+        };
+        project.view.camera.zoom = 2.;
+        let text = project.to_json();
+        assert!(text.contains("\"algorithm\": \"guards\""));
+        assert_eq!(Project::from_json(&text), Ok(project));
+
+        // Older files miss fields added later.
+        let old = Project::from_json(r#"{"version": 1, "live_mode": true}"#).unwrap();
+        assert!(old.live_mode && old.polygons.is_empty());
+
+        let newer = format!("{{\"version\": {}}}", PROJECT_VERSION + 1);
+        assert!(Project::from_json(&newer).is_err());
+        assert!(Project::from_json(r#"{"type": "FeatureCollection"}"#).is_err());
+        let err = Project::from_json("{\"version\": 1,\n\"algorithm\": \"magic\"}").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,361 @@
+use crate::import::PolygonWithHoles;
+use crate::triangle_base::{cross, segments_intersect, signed_area};
+use egui::{pos2, Pos2, Rect};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::f32::consts::PI;
+
+/// Area random vertices are sampled from, the default view's canvas.
+const BOUNDS: Rect = Rect {
+    min: pos2(100., 50.),
+    max: pos2(700., 450.),
+};
+
+/// Small seedable random number generator (SplitMix64).\
+/// Unlike generators of external crates, its output never changes,
+/// so a seed reproduces the same polygon in every build.
+#[derive(Clone, Debug)]
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in [min, max).
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Uniform in 0..n, n must be positive.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn point_in(&mut self, rect: Rect) -> Pos2 {
+        pos2(
+            self.range(rect.min.x, rect.max.x),
+            self.range(rect.min.y, rect.max.y),
+        )
+    }
+}
+
+/// How a random simple polygon is built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RandomMethod {
+    /// Auer & Held's space partitioning of random points.
+    SpacePartition,
+    /// Random tour of random points, untangled by 2-opt moves.\
+    /// Every pass is O(n²) and O(n) passes may be needed, see
+    /// [`TWO_OPT_MAX_VERTICES`].
+    TwoOpt,
+    /// Random radii at jittered angles around a center.
+    StarShaped,
+}
+
+impl RandomMethod {
+    pub const ALL: [RandomMethod; 3] = [
+        RandomMethod::SpacePartition,
+        RandomMethod::TwoOpt,
+        RandomMethod::StarShaped,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RandomMethod::SpacePartition => "Space partitioning",
+            RandomMethod::TwoOpt => "2-opt untangling",
+            RandomMethod::StarShaped => "Star-shaped",
+        }
+    }
+
+    /// Name used on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RandomMethod::SpacePartition => "space-partition",
+            RandomMethod::TwoOpt => "2-opt",
+            RandomMethod::StarShaped => "star",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RandomMethod> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+}
+
+/// Largest vertex count offered for [`RandomMethod::TwoOpt`], untangling
+/// takes seconds around it and a minute for 20,000 vertices.
+pub const TWO_OPT_MAX_VERTICES: usize = 5_000;
+
+/// Random simple polygon with `n` (at least 3) vertices in ccw order.\
+/// The same method, vertex count and seed always give the same polygon.
+pub fn random_polygon(method: RandomMethod, n: usize, seed: u64) -> Vec<Pos2> {
+    let mut rng = SeededRng::new(seed);
+    random_ring(method, n.max(3), BOUNDS, &mut rng)
+}
+
+/// Random polygon with up to `holes` star-shaped holes, which are placed
+/// by rejection sampling, so fewer holes are made if they don't fit.\
+/// Exterior ring is ccw and holes are cw.
+pub fn random_polygon_with_holes(
+    method: RandomMethod,
+    n: usize,
+    holes: usize,
+    seed: u64,
+) -> PolygonWithHoles {
+    let mut rng = SeededRng::new(seed);
+    let exterior = random_ring(method, n.max(3), BOUNDS, &mut rng);
+    let edge_distance = |p: Pos2| {
+        (0..exterior.len())
+            .map(|i| segment_distance(p, exterior[i], exterior[(i + 1) % exterior.len()]))
+            .fold(f32::MAX, f32::min)
+    };
+
+    let mut circles: Vec<(Pos2, f32)> = Vec::new();
+    for _ in 0..holes * 50 {
+        if circles.len() == holes {
+            break;
+        }
+        let center = rng.point_in(BOUNDS);
+        if !crate::triangle_base::point_in_polygon(&center, &exterior) {
+            continue;
+        }
+        // Keep a gap to the exterior and other holes.
+        let radius = (edge_distance(center) * 0.7).min(60.);
+        let overlaps = circles
+            .iter()
+            .any(|(other, r)| other.distance(center) < (radius + r) * 1.2);
+        if radius > 8. && !overlaps {
+            circles.push((center, radius));
+        }
+    }
+    let holes = circles
+        .iter()
+        .map(|(center, radius)| {
+            let bounds = Rect::from_center_size(*center, egui::Vec2::splat(radius * 2.));
+            let count = 3 + rng.below(6);
+            random_ring(RandomMethod::StarShaped, count, bounds, &mut rng)
+        })
+        .collect();
+    PolygonWithHoles::new(exterior, holes)
+}
+
+/// Parameters of a random polygon, as picked in the GUI.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RandomSettings {
+    pub method: RandomMethod,
+    pub vertices: usize,
+    pub holes: usize,
+    pub seed: u64,
+}
+
+impl Default for RandomSettings {
+    fn default() -> Self {
+        Self {
+            method: RandomMethod::SpacePartition,
+            vertices: 20,
+            holes: 0,
+            seed: 0,
+        }
+    }
+}
+
+impl RandomSettings {
+    pub fn generate(&self) -> PolygonWithHoles {
+        if self.holes == 0 {
+            let exterior = random_polygon(self.method, self.vertices, self.seed);
+            return PolygonWithHoles::new(exterior, Vec::new());
+        }
+        random_polygon_with_holes(self.method, self.vertices, self.holes, self.seed)
+    }
+}
+
+fn random_ring(method: RandomMethod, n: usize, bounds: Rect, rng: &mut SeededRng) -> Vec<Pos2> {
+    let mut ring = match method {
+        RandomMethod::StarShaped => star_shaped(n, bounds, rng),
+        RandomMethod::SpacePartition => space_partition(random_points(n, bounds, rng), rng),
+        RandomMethod::TwoOpt => two_opt(random_points(n, bounds, rng)),
+    };
+    if signed_area(&ring) < 0. {
+        ring.reverse();
+    }
+    ring
+}
+
+/// Random points at pairwise different positions, so that the ring
+/// through them has no duplicate vertex.
+fn random_points(n: usize, bounds: Rect, rng: &mut SeededRng) -> Vec<Pos2> {
+    let mut seen = HashSet::new();
+    (0..n)
+        .map(|_| loop {
+            let point = rng.point_in(bounds);
+            if seen.insert((point.x.to_bits(), point.y.to_bits())) {
+                break point;
+            }
+        })
+        .collect()
+}
+
+fn star_shaped(n: usize, bounds: Rect, rng: &mut SeededRng) -> Vec<Pos2> {
+    // Jittered even spacing keeps every gap below pi, larger gaps
+    // would let an edge pass the center and cross other edges.
+    let step = 2. * PI / n as f32;
+    let offset = rng.range(0., step);
+    let angles: Vec<f32> = (0..n)
+        .map(|i| offset + step * (i as f32 + rng.range(0., 0.5)))
+        .collect();
+    let center = bounds.center();
+    let half = bounds.size() / 2.;
+    angles
+        .iter()
+        .map(|angle| {
+            let radius = rng.range(0.3, 1.);
+            pos2(
+                center.x + half.x * radius * angle.cos(),
+                center.y + half.y * radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Which side of line pq point r is on.
+fn side(p: &Pos2, q: &Pos2, r: &Pos2) -> bool {
+    cross(p, q, r) > 0.
+}
+
+/// Two random points split the others into the two sides of the line
+/// through them, each side is turned into a chain by `partition_chain`.
+fn space_partition(mut points: Vec<Pos2>, rng: &mut SeededRng) -> Vec<Pos2> {
+    let a = points.swap_remove(rng.below(points.len()));
+    let b = points.swap_remove(rng.below(points.len()));
+    let (left, right): (Vec<Pos2>, Vec<Pos2>) = points.into_iter().partition(|x| side(&a, &b, x));
+    let mut ret = Vec::new();
+    partition_chain(a, b, right, rng, &mut ret);
+    partition_chain(b, a, left, rng, &mut ret);
+    ret
+}
+
+/// Chain from `first` (included) to `last` (excluded) through `points`,
+/// which lie in a convex region with both ends on its boundary.\
+/// A random line through a random point s and a random point of segment
+/// first-last splits the region in two, chains of both parts meet at s.
+fn partition_chain(
+    first: Pos2,
+    last: Pos2,
+    mut points: Vec<Pos2>,
+    rng: &mut SeededRng,
+    ret: &mut Vec<Pos2>,
+) {
+    if points.is_empty() {
+        ret.push(first);
+        return;
+    }
+    let s = points.swap_remove(rng.below(points.len()));
+    // Side of line qs for q = first + t (last - first), the orientation is
+    // affine in q, so q isn't rounded to f32, which misplaces points of
+    // dense polygons lying close to the line.
+    let t = rng.range(0.05, 0.95) as f64;
+    let side_of_qs = |x: &Pos2| (1. - t) * cross(&first, &s, x) + t * cross(&last, &s, x) > 0.;
+    let first_side = side_of_qs(&first);
+    let (near_first, near_last): (Vec<Pos2>, Vec<Pos2>) = points
+        .into_iter()
+        .partition(|x| side_of_qs(x) == first_side);
+    partition_chain(first, s, near_first, rng, ret);
+    partition_chain(s, last, near_last, rng, ret);
+}
+
+/// Reverse the part of tour between crossing edges until no edges cross,
+/// every move makes the tour shorter so it terminates.
+fn two_opt(mut tour: Vec<Pos2>) -> Vec<Pos2> {
+    let n = tour.len();
+    let mut untangled = false;
+    while !untangled {
+        untangled = true;
+        for i in 0..n {
+            for j in i + 2..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                let (p1, p2) = (tour[i], tour[i + 1]);
+                let (q1, q2) = (tour[j], tour[(j + 1) % n]);
+                if segments_intersect(&p1, &p2, &q1, &q2) {
+                    tour[i + 1..=j].reverse();
+                    untangled = false;
+                }
+            }
+        }
+    }
+    tour
+}
+
+fn segment_distance(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.length_sq().max(f32::EPSILON)).clamp(0., 1.);
+    p.distance(a + ab * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{random_polygon, random_polygon_with_holes, RandomMethod};
+    use crate::monotone_triangulation::try_polygon_triangulation;
+    use crate::polygon_validation::validate_polygon;
+    use crate::triangle_base::{point_in_polygon, segments_intersect};
+
+    #[test]
+    fn test_random_polygon() {
+        for method in RandomMethod::ALL {
+            for (n, seed) in [(3, 1), (10, 2), (50, 3), (120, 4)] {
+                let pts = random_polygon(method, n, seed);
+                assert_eq!(pts.len(), n);
+                assert_eq!(
+                    validate_polygon(&pts),
+                    Ok(()),
+                    "{:?} {} {}",
+                    method,
+                    n,
+                    seed
+                );
+                let poly = try_polygon_triangulation(&pts, None).unwrap();
+                assert_eq!(
+                    poly.faces.len(),
+                    n - 2,
+                    "{:?} {} {} {:?}",
+                    method,
+                    n,
+                    seed,
+                    pts
+                );
+            }
+            assert_eq!(random_polygon(method, 30, 7), random_polygon(method, 30, 7));
+            assert_ne!(random_polygon(method, 30, 7), random_polygon(method, 30, 8));
+        }
+
+        let polygon = random_polygon_with_holes(RandomMethod::StarShaped, 40, 3, 5);
+        assert!(!polygon.holes.is_empty());
+        for hole in polygon.holes.iter() {
+            assert!(validate_polygon(&hole.iter().rev().copied().collect::<Vec<_>>()).is_ok());
+            assert!(hole.iter().all(|p| point_in_polygon(p, &polygon.exterior)));
+            let n = polygon.exterior.len();
+            for i in 0..n {
+                let (a, b) = (polygon.exterior[i], polygon.exterior[(i + 1) % n]);
+                for j in 0..hole.len() {
+                    let (c, d) = (hole[j], hole[(j + 1) % hole.len()]);
+                    assert!(!segments_intersect(&a, &b, &c, &d));
+                }
+            }
+        }
+    }
+}
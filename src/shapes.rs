@@ -0,0 +1,272 @@
+use crate::expression::Expression;
+use crate::import::ParseError;
+use egui::{pos2, Pos2};
+use std::f32::consts::PI;
+
+/// Center of generated shapes, the center of the default view.
+const CENTER: Pos2 = pos2(400., 250.);
+
+/// `n` angles going once around the circle in ccw order, starting at the top.
+fn ring_angles(n: usize) -> impl Iterator<Item = f32> {
+    let step = 2. * PI / n as f32;
+    (0..n).map(move |i| PI / 2. + step * i as f32)
+}
+
+fn polar_point(angle: f32, radius: f32) -> Pos2 {
+    pos2(
+        CENTER.x + radius * angle.cos(),
+        CENTER.y + radius * angle.sin(),
+    )
+}
+
+/// Regular polygon with `sides` (at least 3) vertices on a circle.
+pub fn regular_polygon(sides: usize, radius: f32) -> Vec<Pos2> {
+    ring_angles(sides.max(3))
+        .map(|angle| polar_point(angle, radius))
+        .collect()
+}
+
+/// Star with `points` (at least 2) tips, alternating between the outer
+/// and the inner radius.
+pub fn star(points: usize, outer: f32, inner: f32) -> Vec<Pos2> {
+    ring_angles(2 * points.max(2))
+        .enumerate()
+        .map(|(i, angle)| polar_point(angle, if i % 2 == 0 { outer } else { inner }))
+        .collect()
+}
+
+/// Comb with `teeth` triangular teeth pointing up.\
+/// Every valley is a merge vertex, the worst case of the monotone sweep.
+pub fn comb(teeth: usize) -> Vec<Pos2> {
+    let (left, right) = (100., 700.);
+    let width = (right - left) / teeth.max(1) as f32;
+    let mut ret = vec![pos2(left, 100.), pos2(right, 100.)];
+    for i in (0..teeth.max(1)).rev() {
+        let x = left + width * i as f32;
+        ret.push(pos2(x + width / 2., 420.));
+        if i > 0 {
+            ret.push(pos2(x, 200.));
+        }
+    }
+    ret
+}
+
+/// Thick archimedean spiral band winding `turns` times.
+pub fn spiral(turns: f32, samples: usize) -> Vec<Pos2> {
+    let samples = samples.max(2);
+    let (start, end) = (0.3, 0.3 + turns * 2. * PI);
+    let radius = |angle: f32| 30. + angle * 22.;
+    let angles: Vec<f32> = (0..samples)
+        .map(|i| start + (end - start) * i as f32 / (samples - 1) as f32)
+        .collect();
+    let mut ret: Vec<Pos2> = angles
+        .iter()
+        .map(|a| polar_point(*a, radius(*a) + 14.))
+        .collect();
+    ret.extend(
+        angles
+            .iter()
+            .rev()
+            .map(|a| polar_point(*a, radius(*a) - 14.)),
+    );
+    ret
+}
+
+/// Band of width `thickness` following a zigzag line with `teeth` peaks.
+pub fn zigzag(teeth: usize, amplitude: f32, thickness: f32) -> Vec<Pos2> {
+    let count = 2 * teeth.max(1) + 1;
+    let step = 600. / (count - 1) as f32;
+    let bottom: Vec<Pos2> = (0..count)
+        .map(|i| pos2(100. + step * i as f32, 150. + (i % 2) as f32 * amplitude))
+        .collect();
+    let mut ret = bottom.clone();
+    ret.extend(bottom.iter().rev().map(|p| pos2(p.x, p.y + thickness)));
+    ret
+}
+
+/// Slab whose top is a sawtooth with `teeth` ramps and vertical drops.
+pub fn sawtooth(teeth: usize, amplitude: f32) -> Vec<Pos2> {
+    let teeth = teeth.max(1);
+    let step = 600. / teeth as f32;
+    let top = 300.;
+    let mut ret = vec![pos2(100., 100.), pos2(700., 100.)];
+    for i in (1..=teeth).rev() {
+        let x = 100. + step * i as f32;
+        ret.push(pos2(x, top + amplitude));
+        ret.push(pos2(x - step, top));
+    }
+    ret
+}
+
+/// Polar curve r(θ) sampled at `samples` angles around the center and
+/// scaled so that the largest radius is `radius`.\
+/// r must be positive and finite everywhere, so the curve is star-shaped.
+pub fn polar(expression: &str, samples: usize, radius: f32) -> Result<Vec<Pos2>, ParseError> {
+    let expression = Expression::parse(expression)?;
+    let samples: Vec<(f32, f32)> = ring_angles(samples.max(3))
+        .map(|angle| (angle, expression.eval(angle)))
+        .collect();
+    if let Some((angle, r)) = samples.iter().find(|(_, r)| !(r.is_finite() && *r > 0.)) {
+        return Err(ParseError::new(
+            1,
+            1,
+            format!("r({:.3}) = {}, r must be positive", angle, r),
+        ));
+    }
+    let max = samples.iter().map(|(_, r)| *r).fold(0., f32::max);
+    Ok(samples
+        .iter()
+        .map(|(angle, r)| polar_point(*angle, r / max * radius))
+        .collect())
+}
+
+/// A parametric shape and its parameters, as edited in the shapes panel.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParametricShape {
+    RegularPolygon {
+        sides: usize,
+        radius: f32,
+    },
+    Star {
+        points: usize,
+        outer: f32,
+        inner: f32,
+    },
+    Comb {
+        teeth: usize,
+    },
+    Spiral {
+        turns: f32,
+        samples: usize,
+    },
+    Zigzag {
+        teeth: usize,
+        amplitude: f32,
+        thickness: f32,
+    },
+    Sawtooth {
+        teeth: usize,
+        amplitude: f32,
+    },
+    Polar {
+        expression: String,
+        samples: usize,
+    },
+}
+
+impl Default for ParametricShape {
+    fn default() -> Self {
+        Self::defaults().remove(0)
+    }
+}
+
+impl ParametricShape {
+    /// Every kind of shape with default parameters.
+    pub fn defaults() -> Vec<ParametricShape> {
+        vec![
+            ParametricShape::RegularPolygon {
+                sides: 6,
+                radius: 180.,
+            },
+            ParametricShape::Star {
+                points: 5,
+                outer: 200.,
+                inner: 80.,
+            },
+            ParametricShape::Comb { teeth: 8 },
+            ParametricShape::Spiral {
+                turns: 1.5,
+                samples: 40,
+            },
+            ParametricShape::Zigzag {
+                teeth: 6,
+                amplitude: 120.,
+                thickness: 60.,
+            },
+            ParametricShape::Sawtooth {
+                teeth: 6,
+                amplitude: 100.,
+            },
+            ParametricShape::Polar {
+                expression: "1 + 0.5 * cos(5 * t)".to_owned(),
+                samples: 100,
+            },
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ParametricShape::RegularPolygon { .. } => "Regular polygon",
+            ParametricShape::Star { .. } => "Star",
+            ParametricShape::Comb { .. } => "Comb",
+            ParametricShape::Spiral { .. } => "Spiral",
+            ParametricShape::Zigzag { .. } => "Zigzag",
+            ParametricShape::Sawtooth { .. } => "Sawtooth",
+            ParametricShape::Polar { .. } => "Polar r(θ)",
+        }
+    }
+
+    /// Ccw ring of the shape, only a polar curve can fail.
+    pub fn generate(&self) -> Result<Vec<Pos2>, ParseError> {
+        Ok(match self {
+            ParametricShape::RegularPolygon { sides, radius } => regular_polygon(*sides, *radius),
+            ParametricShape::Star {
+                points,
+                outer,
+                inner,
+            } => star(*points, *outer, *inner),
+            ParametricShape::Comb { teeth } => comb(*teeth),
+            ParametricShape::Spiral { turns, samples } => spiral(*turns, *samples),
+            ParametricShape::Zigzag {
+                teeth,
+                amplitude,
+                thickness,
+            } => zigzag(*teeth, *amplitude, *thickness),
+            ParametricShape::Sawtooth { teeth, amplitude } => sawtooth(*teeth, *amplitude),
+            ParametricShape::Polar {
+                expression,
+                samples,
+            } => polar(expression, *samples, 200.)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{comb, polar, sawtooth, star, zigzag, ParametricShape};
+    use crate::monotone_triangulation::try_polygon_triangulation;
+    use crate::triangle_base::signed_area;
+
+    #[test]
+    fn test_shapes() {
+        let mut rings: Vec<_> = ParametricShape::defaults()
+            .iter()
+            .map(|shape| shape.generate().unwrap())
+            .collect();
+        for n in 3..13 {
+            rings.push(
+                ParametricShape::RegularPolygon {
+                    sides: n,
+                    radius: 100.,
+                }
+                .generate()
+                .unwrap(),
+            );
+            rings.push(star(n, 200., 60.));
+            rings.push(zigzag(n, 80., 30.));
+            rings.push(sawtooth(n, 120.));
+            rings.push(comb(n));
+            rings.push(polar("2 + sin(t)", n * 10, 150.).unwrap());
+        }
+        for ring in rings.iter() {
+            assert!(signed_area(ring) > 0.);
+            let poly = try_polygon_triangulation(ring, None).unwrap_or_else(|err| {
+                panic!("{}: {:?}", err, ring);
+            });
+            assert_eq!(poly.faces.len(), ring.len() - 2, "{:?}", ring);
+        }
+
+        assert!(polar("sin(t)", 50, 100.).is_err());
+        assert_eq!(polar("2 * (t", 50, 100.).unwrap_err().column, 7);
+    }
+}
@@ -0,0 +1,273 @@
+use egui::{pos2, vec2, Pos2};
+use serde::{Deserialize, Serialize};
+
+/// Which snapping aids are enabled when placing or dragging vertices.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SnapSettings {
+    pub grid: bool,
+    /// Grid spacing in model units.
+    pub grid_spacing: f32,
+    /// Snap to vertices of holes and other polygons.
+    pub vertices: bool,
+    /// Horizontal/vertical alignment with other vertices.
+    pub alignment: bool,
+    /// Edge angle increments w.r.t. the previous vertex.
+    pub angle: bool,
+    /// Angle increment in degrees.
+    pub angle_step: f32,
+    /// Snapping distance in screen pixels.
+    pub tolerance: f32,
+    /// Reject a new vertex closer than this to the last one, in model units.
+    pub min_spacing: f32,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            grid: false,
+            grid_spacing: 50.,
+            vertices: false,
+            alignment: false,
+            angle: false,
+            angle_step: 15.,
+            tolerance: 8.,
+            // Same as the spacing that used to be hardcoded, sqrt(1000).
+            min_spacing: 31.6,
+        }
+    }
+}
+
+/// What a snapped position is attached to, used to draw hover guides.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnapGuide {
+    Grid,
+    /// Index of the vertex in `targets` of `snap`.
+    Vertex(usize),
+    /// Same y coordinate as the vertex.
+    Horizontal(usize),
+    /// Same x coordinate as the vertex.
+    Vertical(usize),
+    /// On a ray from the vertex, angle is in degrees.
+    Angle(usize, f32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snap {
+    pub pos: Pos2,
+    pub guides: Vec<SnapGuide>,
+}
+
+/// Snap `pos` according to `settings`, all positions are in model space.\
+/// `anchor` is the vertex that angle increments are measured from,
+/// vertex `exclude` (e.g. the one being dragged) is ignored.\
+/// `targets` are the vertices of other rings that vertex snapping goes to,
+/// snapping to a vertex of the ring itself would always duplicate it.\
+/// `tolerance` is the snapping distance converted to model units.\
+/// Vertex snapping wins over everything else, alignment and angle snapping
+/// win over the grid. Targets that land on another vertex are skipped.
+pub fn snap(
+    pos: Pos2,
+    points: &[Pos2],
+    targets: &[Pos2],
+    anchor: Option<usize>,
+    exclude: Option<usize>,
+    settings: &SnapSettings,
+    tolerance: f32,
+) -> Snap {
+    let others = || {
+        points
+            .iter()
+            .enumerate()
+            .filter(move |(idx, _)| Some(*idx) != exclude)
+    };
+    let valid = |pos: Pos2| !others().any(|(_, point)| *point == pos);
+
+    if settings.vertices {
+        let nearest = targets
+            .iter()
+            .enumerate()
+            .filter(|(_, target)| valid(**target))
+            .map(|(idx, target)| (idx, target.distance(pos)))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((idx, _)) = nearest {
+            return Snap {
+                pos: targets[idx],
+                guides: vec![SnapGuide::Vertex(idx)],
+            };
+        }
+    }
+
+    let mut ret = Snap {
+        pos,
+        guides: Vec::new(),
+    };
+    if settings.alignment {
+        let nearest_x = others()
+            .map(|(idx, point)| (idx, (point.x - pos.x).abs()))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((idx, _)) = nearest_x {
+            ret.pos.x = points[idx].x;
+            ret.guides.push(SnapGuide::Vertical(idx));
+        }
+        let nearest_y = others()
+            .map(|(idx, point)| (idx, (point.y - pos.y).abs()))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((idx, _)) = nearest_y {
+            ret.pos.y = points[idx].y;
+            ret.guides.push(SnapGuide::Horizontal(idx));
+        }
+        if !valid(ret.pos) {
+            ret = Snap {
+                pos,
+                guides: Vec::new(),
+            };
+        }
+    }
+
+    let anchor = anchor.filter(|idx| *idx < points.len() && Some(*idx) != exclude);
+    if let (true, true, Some(anchor)) = (settings.angle, ret.guides.is_empty(), anchor) {
+        let origin = points[anchor];
+        let offset = pos - origin;
+        let step = settings.angle_step.max(1.).to_radians();
+        let angle = (offset.y.atan2(offset.x) / step).round() * step;
+        let direction = vec2(angle.cos(), angle.sin());
+        let projected = origin + direction * offset.dot(direction).max(0.);
+        if projected.distance(pos) <= tolerance && valid(projected) {
+            ret.pos = projected;
+            ret.guides.push(SnapGuide::Angle(
+                anchor,
+                angle.to_degrees().rem_euclid(360.),
+            ));
+        }
+    }
+
+    if settings.grid && ret.guides.is_empty() && settings.grid_spacing > 0. {
+        let spacing = settings.grid_spacing;
+        let grid_pos = pos2(
+            (pos.x / spacing).round() * spacing,
+            (pos.y / spacing).round() * spacing,
+        );
+        if valid(grid_pos) {
+            ret.pos = grid_pos;
+            ret.guides.push(SnapGuide::Grid);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{snap, SnapGuide, SnapSettings};
+    use egui::pos2;
+
+    #[test]
+    fn test_snap() {
+        let points = vec![pos2(0., 0.), pos2(100., 30.)];
+        let targets = vec![pos2(200., 200.)];
+        let settings = SnapSettings {
+            grid: true,
+            vertices: true,
+            alignment: true,
+            angle: true,
+            ..Default::default()
+        };
+
+        let ret = snap(
+            pos2(197., 203.),
+            &points,
+            &targets,
+            Some(1),
+            None,
+            &settings,
+            5.,
+        );
+        assert_eq!(ret.pos, pos2(200., 200.));
+        assert_eq!(ret.guides, vec![SnapGuide::Vertex(0)]);
+
+        // Aligned with both vertices, the closing edge is horizontal.
+        let ret = snap(
+            pos2(97., 3.),
+            &points,
+            &targets,
+            Some(1),
+            None,
+            &settings,
+            5.,
+        );
+        assert_eq!(ret.pos, pos2(100., 0.));
+        assert_eq!(
+            ret.guides,
+            vec![SnapGuide::Vertical(1), SnapGuide::Horizontal(0)]
+        );
+
+        // The dragged vertex doesn't snap to itself.
+        let ret = snap(
+            pos2(97., 33.),
+            &points,
+            &targets,
+            None,
+            Some(1),
+            &settings,
+            5.,
+        );
+        assert_eq!(ret.guides, vec![SnapGuide::Grid]);
+        assert_eq!(ret.pos, pos2(100., 50.));
+
+        let ret = snap(
+            pos2(2., 80.),
+            &points,
+            &targets,
+            Some(1),
+            None,
+            &settings,
+            5.,
+        );
+        assert_eq!(ret.pos, pos2(0., 80.));
+        assert_eq!(ret.guides, vec![SnapGuide::Vertical(0)]);
+
+        // 45 degrees ray from vertex 1.
+        let ret = snap(
+            pos2(152., 79.),
+            &points,
+            &targets,
+            Some(1),
+            None,
+            &settings,
+            5.,
+        );
+        assert!(ret.pos.distance(pos2(150.5, 80.5)) < 1e-3);
+        assert!(matches!(ret.guides[..], [SnapGuide::Angle(1, x)] if (x - 45.).abs() < 1e-3));
+
+        // 0 degrees ray gives a horizontal edge.
+        let angle = SnapSettings {
+            grid: true,
+            angle: true,
+            ..Default::default()
+        };
+        let ret = snap(
+            pos2(150., 31.),
+            &points,
+            &targets,
+            Some(1),
+            None,
+            &angle,
+            5.,
+        );
+        assert_eq!(ret.pos, pos2(150., 30.));
+        assert!(matches!(ret.guides[..], [SnapGuide::Angle(1, x)] if x.abs() < 1e-3));
+
+        // Grid point on another vertex isn't used.
+        let grid = SnapSettings {
+            grid: true,
+            ..Default::default()
+        };
+        let points = vec![pos2(0., 0.), pos2(100., 30.), pos2(50., 100.)];
+        let ret = snap(pos2(2., 3.), &points, &[], None, Some(2), &grid, 5.);
+        assert_eq!(ret.pos, pos2(2., 3.));
+        assert!(ret.guides.is_empty());
+    }
+}
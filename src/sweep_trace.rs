@@ -0,0 +1,76 @@
+use crate::monotone_y_partition::VertexType;
+
+/// The phase of polygon triangulation a trace step belongs to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TracePhase {
+    MonotonePartition,
+    TriangulateMonotone,
+}
+
+/// Snapshot of the sweep's state right after an event vertex is handled.
+#[derive(Clone, Debug)]
+pub struct TraceStep {
+    pub phase: TracePhase,
+    pub event_vertex: usize,
+    /// Type of event vertex, only recorded in monotone partition.
+    pub vertex_type: Option<VertexType>,
+    /// Edges in the status tree from left to right,
+    /// described by (edge origin, helper).
+    pub status: Vec<(usize, usize)>,
+    /// The monotone polygon being triangulated.
+    pub monotone_polygon: Vec<usize>,
+    pub process_stack: Vec<usize>,
+    /// Remaining event vertices, the next event vertex is the last one.
+    pub event_stack: Vec<usize>,
+    /// Diagonals inserted while handling the event vertex.
+    pub diagonals: Vec<(usize, usize)>,
+}
+
+impl TraceStep {
+    pub fn new(phase: TracePhase, event_vertex: usize) -> Self {
+        TraceStep {
+            phase,
+            event_vertex,
+            vertex_type: None,
+            status: Vec::new(),
+            monotone_polygon: Vec::new(),
+            process_stack: Vec::new(),
+            event_stack: Vec::new(),
+            diagonals: Vec::new(),
+        }
+    }
+}
+
+/// Event trace of monotone partition and monotone polygon triangulation,
+/// used to replay the algorithm step by step.
+#[derive(Clone, Debug, Default)]
+pub struct AlgorithmTrace {
+    pub steps: Vec<TraceStep>,
+}
+
+impl AlgorithmTrace {
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    pub fn push(&mut self, step: TraceStep) {
+        self.steps.push(step);
+    }
+
+    /// All diagonals inserted from the first step up to (including) `step`.
+    pub fn diagonals_until(&self, step: usize) -> Vec<(usize, usize)> {
+        self.steps
+            .iter()
+            .take(step + 1)
+            .flat_map(|x| x.diagonals.iter().copied())
+            .collect()
+    }
+}
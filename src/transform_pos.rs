@@ -1,10 +1,16 @@
-use egui::{pos2, vec2, Pos2, Vec2};
+use egui::{vec2, Pos2, Vec2};
+use serde::{Deserialize, Serialize};
 
-/// TransformPos stores the translation and scaling for 
-/// transposing coordinates.
+/// TransformPos is a 2D affine transform for transposing coordinates,
+/// which maps a point p to `x_axis * p.x + y_axis * p.y + translation`.\
+/// `a * b` is the transform that applies `b` first, then `a`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct TransformPos {
+    /// Image of unit vector (1, 0), the first column of linear part.
+    pub x_axis: Vec2,
+    /// Image of unit vector (0, 1), the second column of linear part.
+    pub y_axis: Vec2,
     pub translation: Vec2,
-    pub scaling: Vec2,
 }
 
 impl Default for TransformPos {
@@ -15,31 +21,81 @@ impl Default for TransformPos {
 
 impl TransformPos {
     pub const IDENTITY: Self = Self {
+        x_axis: Vec2::X,
+        y_axis: Vec2::Y,
         translation: Vec2::ZERO,
-        scaling: Vec2::new(1., 1.),
     };
 
+    /// Per-axis scaling followed by translation.
     pub fn new(translation: Vec2, scaling: Vec2) -> Self {
         Self {
+            x_axis: vec2(scaling.x, 0.),
+            y_axis: vec2(0., scaling.y),
             translation,
-            scaling,
         }
     }
 
-    pub fn inverse(&self) -> Self {
-        Self::new(
-            vec2(
-                -self.translation.x / self.scaling.x,
-                -self.translation.y / self.scaling.y,
-            ),
-            vec2(1. / self.scaling.x, 1. / self.scaling.y),
-        )
+    pub fn from_translation(translation: Vec2) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scaling(scaling: Vec2) -> Self {
+        Self::new(Vec2::ZERO, scaling)
+    }
+
+    /// Rotation around the origin, counter-clockwise in conventional
+    /// coordinate system for positive `angle` (in radians).
+    pub fn from_rotation(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            x_axis: vec2(cos, sin),
+            y_axis: vec2(-sin, cos),
+            translation: Vec2::ZERO,
+        }
+    }
+
+    /// Shear which maps (x, y) to (x + shear.x * y, y + shear.y * x).
+    pub fn from_shear(shear: Vec2) -> Self {
+        Self {
+            x_axis: vec2(1., shear.y),
+            y_axis: vec2(shear.x, 1.),
+            translation: Vec2::ZERO,
+        }
+    }
+
+    /// Determinant of linear part, negative if the transform mirrors.
+    pub fn determinant(&self) -> f32 {
+        self.x_axis.x * self.y_axis.y - self.y_axis.x * self.x_axis.y
     }
 
-    // pub fn mul_pos(&self, pos: Pos2) -> Pos2 {
-    //     let ret = pos2(self.scaling.x * pos.x, self.scaling.y * pos.y);
-    //     ret + self.translation
-    // }
+    /// Inverse transform, None if the transform is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+        let linear = Self {
+            x_axis: vec2(self.y_axis.y, -self.x_axis.y) / det,
+            y_axis: vec2(-self.y_axis.x, self.x_axis.x) / det,
+            translation: Vec2::ZERO,
+        };
+        Some(Self {
+            translation: -linear.transform_vec(self.translation),
+            ..linear
+        })
+    }
+
+    pub fn transform_pos(&self, pos: Pos2) -> Pos2 {
+        (self.x_axis * pos.x + self.y_axis * pos.y + self.translation).to_pos2()
+    }
+
+    /// Vectors are only affected by linear part of the transform.
+    pub fn transform_vec(&self, vec: Vec2) -> Vec2 {
+        self.x_axis * vec.x + self.y_axis * vec.y
+    }
 }
 
 /// Define * operator for TransformPos
@@ -47,7 +103,91 @@ impl std::ops::Mul<Pos2> for TransformPos {
     type Output = Pos2;
 
     fn mul(self, rhs: Pos2) -> Self::Output {
-        let ret = pos2(self.scaling.x * rhs.x, self.scaling.y * rhs.y);
-        ret + self.translation
+        self.transform_pos(rhs)
+    }
+}
+
+impl std::ops::Mul<Vec2> for TransformPos {
+    type Output = Vec2;
+
+    fn mul(self, rhs: Vec2) -> Self::Output {
+        self.transform_vec(rhs)
+    }
+}
+
+impl std::ops::Mul for TransformPos {
+    type Output = TransformPos;
+
+    fn mul(self, rhs: TransformPos) -> Self::Output {
+        Self {
+            x_axis: self.transform_vec(rhs.x_axis),
+            y_axis: self.transform_vec(rhs.y_axis),
+            translation: self.transform_pos(rhs.translation.to_pos2()).to_vec2(),
+        }
+    }
+}
+
+/// Transform every vertex of a polygon.\
+/// A mirroring transform (negative determinant) turns a ccw polygon into a cw one,
+/// so vertex order is reversed to keep the result ready for triangulation.
+pub fn transform_polygon(vertices: &[Pos2], transform: &TransformPos) -> Vec<Pos2> {
+    let mut ret: Vec<Pos2> = vertices.iter().map(|x| *transform * *x).collect();
+    if transform.determinant() < 0. {
+        ret.reverse();
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{transform_polygon, TransformPos};
+    use crate::monotone_triangulation::polygon_triangulation;
+    use crate::polygon_validation::validate_polygon;
+    use egui::{pos2, vec2, Pos2};
+
+    #[test]
+    fn test_transform_pos() {
+        let transform = TransformPos::from_translation(vec2(3., -2.))
+            * TransformPos::from_rotation(0.7)
+            * TransformPos::from_shear(vec2(0.5, -0.25))
+            * TransformPos::from_scaling(vec2(2., 3.));
+        assert!((transform.determinant() - 6. * (1. + 0.125)).abs() < 1e-4);
+
+        let p = pos2(5., 7.);
+        let inverse = transform.inverse().unwrap();
+        assert!((inverse * (transform * p)).distance(p) < 1e-4);
+        let identity = inverse * transform;
+        assert!((identity.x_axis - vec2(1., 0.)).length() < 1e-5);
+        assert!((identity.y_axis - vec2(0., 1.)).length() < 1e-5);
+        assert!(identity.translation.length() < 1e-5);
+
+        // Vectors aren't translated.
+        let v = vec2(1., 0.);
+        assert_eq!(TransformPos::from_translation(vec2(3., 4.)) * v, v);
+        assert_eq!(TransformPos::from_scaling(vec2(1., 0.)).inverse(), None);
+    }
+
+    #[test]
+    fn test_transform_polygon() {
+        let pts = vec![
+            Pos2::new(157., 29.),
+            Pos2::new(308., 173.),
+            Pos2::new(481., 49.),
+            Pos2::new(624., 180.),
+            Pos2::new(500., 349.),
+            Pos2::new(378., 286.),
+            Pos2::new(185., 333.),
+        ];
+        let transforms = [
+            TransformPos::from_rotation(0.3),
+            TransformPos::from_shear(vec2(0.2, 0.1)),
+            TransformPos::from_scaling(vec2(-1., 1.)),
+        ];
+        for transform in transforms.iter() {
+            let transformed = transform_polygon(&pts, transform);
+            assert_eq!(validate_polygon(&transformed), Ok(()));
+            let poly = polygon_triangulation(&transformed, None);
+            assert_eq!(poly.faces.len(), pts.len() - 2);
+        }
     }
 }
@@ -1,6 +1,6 @@
 use egui::Pos2;
 use std::cmp::Ordering;
-use std::f32::consts::PI;
+use std::f64::consts::PI;
 
 pub enum Orientation {
     ClockWise,
@@ -11,8 +11,8 @@ pub enum Orientation {
 pub enum MiddleVertexStatus {
     Convex,       // middle vertex is the heighest
     Concave,      // middle veretx is the lowest
-    GradientUp,   // the y coordinates of vertex gradually increase
-    GradientDown, // the y coordinates of vertex gradually decrease
+    GradientUp,   // vertices gradually go up
+    GradientDown, // vertices gradually go down
 }
 
 /// Assessing 3 adjacent vertices' orientation by comparing their slopes.
@@ -32,39 +32,140 @@ pub fn cmp_slope(p: &Pos2, q: &Pos2, r: &Pos2) -> Orientation {
     }
 }
 
+/// Check if p is above q, i.e. p has larger y coordinate,
+/// or the same y coordinate and smaller x coordinate.\
+/// Sweeping in this order handles horizontal edges like slightly
+/// tilted ones, it's the order of the event queue.
+pub fn is_above(p: &Pos2, q: &Pos2) -> bool {
+    p.y > q.y || (p.y == q.y && p.x < q.x)
+}
+
 /// Compare vertex's height with its left and right neighbors
 pub fn cmp_vertex_height(p: &Pos2, q: &Pos2, r: &Pos2) -> MiddleVertexStatus {
-    match (q.y.ge(&p.y), q.y.le(&r.y)) {
-        (true, false) => MiddleVertexStatus::Convex,
-        (false, true) => MiddleVertexStatus::Concave,
-        (true, true) => MiddleVertexStatus::GradientUp,
-        (false, false) => MiddleVertexStatus::GradientDown,
+    match (is_above(q, p), is_above(q, r)) {
+        (true, true) => MiddleVertexStatus::Convex,
+        (false, false) => MiddleVertexStatus::Concave,
+        (true, false) => MiddleVertexStatus::GradientUp,
+        (false, true) => MiddleVertexStatus::GradientDown,
     }
 }
 
-
-pub fn vector_length(vector: (f32, f32)) -> f32 {
-    (vector.0.powi(2) + vector.1.powi(2)).sqrt()
-}
-
 /// Compute the angle between vector1(cur -> next)
 /// and vector2 (cur -> target).\
 /// The angle is in 0 to 2pi, from vector1 to vector2.\
 /// cur: current vertex\
 /// next: cur's next vertex in partition polygon (in ccw order)
-pub fn compute_angle(cur: &Pos2, next: &Pos2, target: &Pos2) -> f32 {
-    let ref_vector = (next.x - cur.x, next.y - cur.y);
-    let target_vector = (target.x - cur.x, target.y - cur.y);
-    let cos = (ref_vector.0 * target_vector.0 + ref_vector.1 * target_vector.1)
-        / (vector_length(ref_vector) * vector_length(target_vector));
-    let sin = (ref_vector.0 * target_vector.1 - ref_vector.1 * target_vector.0)
-        / (vector_length(ref_vector) * vector_length(target_vector));
-    if let Some(res) = sin.partial_cmp(&0.0) {
-        match res {
-            Ordering::Less => 2. * PI - cos.acos(),
-            _ => cos.acos(),
-        }
+pub fn compute_angle(cur: &Pos2, next: &Pos2, target: &Pos2) -> f64 {
+    // atan2 in f64 keeps nearly collinear diagonals apart,
+    // where acos of their f32 cosine rounds to the same angle.
+    let dot = (next.x as f64 - cur.x as f64) * (target.x as f64 - cur.x as f64)
+        + (next.y as f64 - cur.y as f64) * (target.y as f64 - cur.y as f64);
+    let angle = cross(cur, next, target).atan2(dot);
+    if angle < 0. {
+        angle + 2. * PI
     } else {
-        panic!("Bro, what can i say")
+        angle
+    }
+}
+
+/// Twice the signed area of triangle (p, q, r), computed in f64.\
+/// Positive if p, q, r are in ccw order, negative if in cw order.
+pub fn cross(p: &Pos2, q: &Pos2, r: &Pos2) -> f64 {
+    let (px, py) = (p.x as f64, p.y as f64);
+    (q.x as f64 - px) * (r.y as f64 - py) - (q.y as f64 - py) * (r.x as f64 - px)
+}
+
+/// Signed area of a polygon, positive if its vertices are in ccw order.
+pub fn signed_area(vertices: &[Pos2]) -> f64 {
+    let mut area = 0.;
+    for (i, p) in vertices.iter().enumerate() {
+        let q = &vertices[(i + 1) % vertices.len()];
+        area += p.x as f64 * q.y as f64 - q.x as f64 * p.y as f64;
+    }
+    area / 2.
+}
+
+/// Check if r lies on segment pq, given that p, q, r are colinear.
+fn on_segment(p: &Pos2, q: &Pos2, r: &Pos2) -> bool {
+    r.x >= p.x.min(q.x) && r.x <= p.x.max(q.x) && r.y >= p.y.min(q.y) && r.y <= p.y.max(q.y)
+}
+
+/// Check if segment p1p2 and q1q2 intersect, touching counts as intersection.
+pub fn segments_intersect(p1: &Pos2, p2: &Pos2, q1: &Pos2, q2: &Pos2) -> bool {
+    let d1 = cross(q1, q2, p1);
+    let d2 = cross(q1, q2, p2);
+    let d3 = cross(p1, p2, q1);
+    let d4 = cross(p1, p2, q2);
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.))
+        && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.))
+    {
+        return true;
+    }
+    (d1 == 0. && on_segment(q1, q2, p1))
+        || (d2 == 0. && on_segment(q1, q2, p2))
+        || (d3 == 0. && on_segment(p1, p2, q1))
+        || (d4 == 0. && on_segment(p1, p2, q2))
+}
+
+/// Check if point p lies inside polygon, using the crossing number.
+pub fn point_in_polygon(p: &Pos2, vertices: &[Pos2]) -> bool {
+    let mut inside = false;
+    for (i, a) in vertices.iter().enumerate() {
+        let b = &vertices[(i + 1) % vertices.len()];
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if p.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cmp_vertex_height, compute_angle, is_above, MiddleVertexStatus};
+    use egui::Pos2;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_compute_angle() {
+        let (cur, next) = (Pos2::new(0., 0.), Pos2::new(1., 0.));
+        let angle = |x: f32, y: f32| compute_angle(&cur, &next, &Pos2::new(x, y));
+        assert_eq!(angle(1., 0.), 0.);
+        assert!((angle(0., 1.) - PI / 2.).abs() < 1e-12);
+        assert!((angle(-1., 0.) - PI).abs() < 1e-12);
+        assert!((angle(0., -1.) - 1.5 * PI).abs() < 1e-12);
+
+        // Cosines of these nearly collinear diagonals are equal in f32,
+        // so acos used to give both the same angle and sort them randomly.
+        assert!(angle(1000., 1.) < angle(1000., 1.001));
+        assert!(angle(1000., -1.001) < angle(1000., -1.));
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_cmp_vertex_height() {
+        let (p, q) = (Pos2::new(0., 1.), Pos2::new(1., 1.));
+        // Same height, the left one is above.
+        assert!(is_above(&p, &q) && !is_above(&q, &p));
+        assert!(is_above(&p, &Pos2::new(-5., 0.)));
+
+        // Both ends of a horizontal edge on top of a rectangle.
+        let (r, s) = (Pos2::new(1., 0.), Pos2::new(0., 0.));
+        assert!(matches!(
+            cmp_vertex_height(&s, &p, &q),
+            MiddleVertexStatus::Convex
+        ));
+        assert!(matches!(
+            cmp_vertex_height(&p, &q, &r),
+            MiddleVertexStatus::GradientDown
+        ));
+        assert!(matches!(
+            cmp_vertex_height(&q, &r, &s),
+            MiddleVertexStatus::Concave
+        ));
+        assert!(matches!(
+            cmp_vertex_height(&r, &s, &p),
+            MiddleVertexStatus::GradientUp
+        ));
+    }
+}
@@ -0,0 +1,408 @@
+use crate::monotone_y_partition::PartitionPolygon;
+use crate::polygon_validation::validate_polygon;
+use crate::triangle_base::{cross, segments_intersect, signed_area};
+use egui::Pos2;
+use std::fmt::Display;
+
+/// An invariant of a triangulation that doesn't hold.\
+/// Faces are given by their index in `PartitionPolygon::faces`,
+/// diagonals by their index in `PartitionPolygon::diagonals`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriangulationViolation {
+    /// A simple polygon with n vertices has n - 2 triangles.
+    TriangleCount { expected: usize, found: usize },
+    /// A simple polygon with n vertices has n - 3 diagonals.
+    DiagonalCount { expected: usize, found: usize },
+    /// Face doesn't have exactly 3 vertices.
+    NotTriangle { face: usize, vertices: Vec<usize> },
+    /// Triangle has no area.
+    Degenerate { face: usize, vertices: Vec<usize> },
+    /// Triangle vertices are in cw order.
+    ClockWise { face: usize, vertices: Vec<usize> },
+    /// Triangles don't add up to the polygon.
+    AreaMismatch { polygon: f64, triangles: f64 },
+    /// Two diagonals cross each other.
+    DiagonalsCross(usize, usize),
+    /// Diagonal crosses or touches the boundary edge given by its origin.
+    DiagonalCrossesBoundary { diagonal: usize, edge: usize },
+    /// Diagonal isn't an edge of exactly two triangles.
+    DiagonalNotShared { diagonal: usize, faces: usize },
+}
+
+impl Display for TriangulationViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriangulationViolation::TriangleCount { expected, found } => {
+                write!(f, "expected {} triangles, found {}", expected, found)
+            }
+            TriangulationViolation::DiagonalCount { expected, found } => {
+                write!(f, "expected {} diagonals, found {}", expected, found)
+            }
+            TriangulationViolation::NotTriangle { face, vertices } => {
+                write!(
+                    f,
+                    "face{} has vertices {:?}, not a triangle",
+                    face, vertices
+                )
+            }
+            TriangulationViolation::Degenerate { face, vertices } => {
+                write!(f, "face{} {:?} has no area", face, vertices)
+            }
+            TriangulationViolation::ClockWise { face, vertices } => {
+                write!(f, "face{} {:?} is clockwise", face, vertices)
+            }
+            TriangulationViolation::AreaMismatch { polygon, triangles } => write!(
+                f,
+                "triangles cover area {}, but the polygon's area is {}",
+                triangles, polygon
+            ),
+            TriangulationViolation::DiagonalsCross(i, j) => {
+                write!(f, "diagonal{} crosses diagonal{}", i, j)
+            }
+            TriangulationViolation::DiagonalCrossesBoundary { diagonal, edge } => {
+                write!(f, "diagonal{} crosses edge{}", diagonal, edge)
+            }
+            TriangulationViolation::DiagonalNotShared { diagonal, faces } => {
+                write!(
+                    f,
+                    "diagonal{} is an edge of {} triangles, not 2",
+                    diagonal, faces
+                )
+            }
+        }
+    }
+}
+
+/// Result of `verify_triangulation`, valid if there are no violations.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TriangulationReport {
+    pub vertices: usize,
+    pub triangles: usize,
+    pub diagonals: usize,
+    pub polygon_area: f64,
+    pub triangle_area: f64,
+    pub violations: Vec<TriangulationViolation>,
+}
+
+impl TriangulationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for TriangulationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} vertices, {} triangles, {} diagonals, polygon area {}, triangle area {}",
+            self.vertices, self.triangles, self.diagonals, self.polygon_area, self.triangle_area
+        )?;
+        if self.is_valid() {
+            return write!(f, ", all invariants hold");
+        }
+        write!(f, ", {} violations:", self.violations.len())?;
+        for violation in self.violations.iter() {
+            write!(f, "\n  {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check the result of `polygon_triangulation` on a valid ccw polygon,
+/// or on a ring whose holes are bridged to it.\
+/// Segments meeting at an endpoint don't cross, endpoints are compared
+/// by position since bridged vertices appear twice in the ring.\
+/// Crossings are checked by comparing all pairs of diagonals and edges,
+/// so it takes O(n^2) time.
+pub fn verify_triangulation(vertices: &[Pos2], poly: &PartitionPolygon) -> TriangulationReport {
+    let n = vertices.len();
+    let faces: Vec<Vec<usize>> = poly
+        .faces
+        .iter()
+        .map(|face| face.as_ref().borrow().vertices.clone())
+        .collect();
+    let mut report = TriangulationReport {
+        vertices: n,
+        triangles: faces.len(),
+        diagonals: poly.diagonals.len(),
+        polygon_area: signed_area(vertices),
+        ..Default::default()
+    };
+    let violations = &mut report.violations;
+    if faces.len() + 2 != n {
+        violations.push(TriangulationViolation::TriangleCount {
+            expected: n.saturating_sub(2),
+            found: faces.len(),
+        });
+    }
+    if poly.diagonals.len() + 3 != n {
+        violations.push(TriangulationViolation::DiagonalCount {
+            expected: n.saturating_sub(3),
+            found: poly.diagonals.len(),
+        });
+    }
+
+    for (face, face_vertices) in faces.iter().enumerate() {
+        let ring: Vec<Pos2> = face_vertices.iter().map(|idx| vertices[*idx]).collect();
+        let vertices = face_vertices.clone();
+        if ring.len() != 3 {
+            violations.push(TriangulationViolation::NotTriangle { face, vertices });
+            continue;
+        }
+        let area = cross(&ring[0], &ring[1], &ring[2]) / 2.;
+        report.triangle_area += area;
+        if area == 0. {
+            violations.push(TriangulationViolation::Degenerate { face, vertices });
+        } else if area < 0. {
+            violations.push(TriangulationViolation::ClockWise { face, vertices });
+        }
+    }
+    // Areas are summed in f64, so only a tiny relative error is tolerated.
+    let tolerance = 1e-9 * report.polygon_area.abs().max(1.);
+    if (report.triangle_area - report.polygon_area).abs() > tolerance {
+        violations.push(TriangulationViolation::AreaMismatch {
+            polygon: report.polygon_area,
+            triangles: report.triangle_area,
+        });
+    }
+
+    let segment = |(a, b): (usize, usize)| (vertices[a], vertices[b]);
+    for (i, diagonal) in poly.diagonals.iter().enumerate() {
+        let (a, b) = *diagonal;
+        let (p, q) = segment(*diagonal);
+        let shares_end = |r: &Pos2, s: &Pos2| [p, q].contains(r) || [p, q].contains(s);
+        for (j, other) in poly.diagonals.iter().enumerate().skip(i + 1) {
+            let (r, s) = segment(*other);
+            if !shares_end(&r, &s) && segments_intersect(&p, &q, &r, &s) {
+                violations.push(TriangulationViolation::DiagonalsCross(i, j));
+            }
+        }
+        for edge in 0..n {
+            let (r, s) = (vertices[edge], vertices[(edge + 1) % n]);
+            if !shares_end(&r, &s) && segments_intersect(&p, &q, &r, &s) {
+                violations
+                    .push(TriangulationViolation::DiagonalCrossesBoundary { diagonal: i, edge });
+            }
+        }
+        let shared = faces
+            .iter()
+            .filter(|face| {
+                (0..face.len()).any(|k| {
+                    let (u, v) = (face[k], face[(k + 1) % face.len()]);
+                    (u, v) == (a, b) || (u, v) == (b, a)
+                })
+            })
+            .count();
+        if shared != 2 {
+            violations.push(TriangulationViolation::DiagonalNotShared {
+                diagonal: i,
+                faces: shared,
+            });
+        }
+    }
+    report
+}
+
+/// Shrink a valid polygon on which `fails` holds to a smaller one on which
+/// it still holds, by greedily removing vertices and rounding coordinates
+/// as long as the polygon stays valid.\
+/// Used to turn a failing random or fuzzed input into a minimal test case.
+pub fn shrink_polygon(vertices: &[Pos2], fails: impl Fn(&[Pos2]) -> bool) -> Vec<Pos2> {
+    let mut current = vertices.to_vec();
+    let keeps_failing =
+        |candidate: &[Pos2]| validate_polygon(candidate).is_ok() && fails(candidate);
+    let mut shrunk = true;
+    while shrunk {
+        shrunk = false;
+        for idx in (0..current.len()).rev() {
+            if current.len() <= 3 {
+                break;
+            }
+            let mut candidate = current.clone();
+            candidate.remove(idx);
+            if keeps_failing(&candidate) {
+                current = candidate;
+                shrunk = true;
+            }
+        }
+        for idx in 0..current.len() {
+            let rounded = current[idx].round();
+            if rounded == current[idx] {
+                continue;
+            }
+            let mut candidate = current.clone();
+            candidate[idx] = rounded;
+            if keeps_failing(&candidate) {
+                current = candidate;
+                shrunk = true;
+            }
+        }
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{shrink_polygon, verify_triangulation, TriangulationViolation};
+    use crate::monotone_triangulation::polygon_triangulation;
+    use crate::monotone_y_partition::monotone_partition_polygon;
+    use crate::polygon_validation::validate_polygon;
+    use crate::presets::find_preset;
+    use crate::random_polygon::{random_polygon, RandomMethod};
+    use crate::transform_pos::{transform_polygon, TransformPos};
+    use egui::{vec2, Pos2};
+    use proptest::prelude::*;
+    use std::f32::consts::PI;
+
+    /// Whether triangulation of a valid polygon panics or breaks an invariant.
+    fn fails(pts: &[Pos2]) -> bool {
+        let pts = pts.to_vec();
+        std::panic::catch_unwind(move || {
+            !verify_triangulation(&pts, &polygon_triangulation(&pts, None)).is_valid()
+        })
+        .unwrap_or(true)
+    }
+
+    /// Coordinates without the rounding of Pos2's Debug, to reproduce a failure.
+    fn exact(pts: &[Pos2]) -> Vec<(f32, f32)> {
+        pts.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    /// Property check of one valid polygon, a failure reports the
+    /// shrunk polygon besides the one proptest shrank to.
+    fn check(pts: &[Pos2]) -> Result<(), TestCaseError> {
+        if fails(pts) {
+            let shrunk = shrink_polygon(pts, fails);
+            return Err(TestCaseError::fail(format!(
+                "triangulation fails on {:?}, shrunk to {:?}",
+                exact(pts),
+                exact(&shrunk)
+            )));
+        }
+        Ok(())
+    }
+
+    fn method() -> impl Strategy<Value = RandomMethod> {
+        prop::sample::select(RandomMethod::ALL.to_vec())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn prop_random_polygon(method in method(), n in 3usize..60, seed in any::<u64>()) {
+            let pts = random_polygon(method, n, seed);
+            prop_assert_eq!(validate_polygon(&pts), Ok(()));
+            check(&pts)?;
+        }
+
+        #[test]
+        fn prop_affine_copy(
+            method in method(),
+            n in 3usize..40,
+            seed in any::<u64>(),
+            angle in 0f32..2. * PI,
+            scale in (-10f32..10., 0.1f32..10.),
+            shear in -2f32..2.,
+            offset in (-1e4f32..1e4, -1e4f32..1e4),
+        ) {
+            prop_assume!(scale.0.abs() > 0.1);
+            let transform = TransformPos::from_translation(vec2(offset.0, offset.1))
+                * TransformPos::from_rotation(angle)
+                * TransformPos::from_shear(vec2(shear, 0.))
+                * TransformPos::from_scaling(vec2(scale.0, scale.1));
+            let pts = transform_polygon(&random_polygon(method, n, seed), &transform);
+            // A negative scale reverses the ring, rounding may make edges touch.
+            prop_assume!(validate_polygon(&pts).is_ok());
+            check(&pts)?;
+        }
+
+        #[test]
+        fn prop_perturbed(
+            method in method(),
+            n in 3usize..40,
+            seed in any::<u64>(),
+            epsilon in prop::sample::select(vec![1e-3f32, 1e-2, 0.5]),
+            offsets in prop::collection::vec((-1f32..1., -1f32..1.), 40),
+        ) {
+            let pts: Vec<Pos2> = random_polygon(method, n, seed)
+                .iter()
+                .zip(offsets.iter())
+                .map(|(p, d)| *p + vec2(d.0, d.1) * epsilon)
+                .collect();
+            prop_assume!(validate_polygon(&pts).is_ok());
+            check(&pts)?;
+        }
+
+        /// Vertices close to an edge, and vertices snapped to a coarse grid,
+        /// give nearly collinear vertices and events at the same height.
+        #[test]
+        fn prop_near_degenerate(
+            method in method(),
+            n in 3usize..30,
+            seed in any::<u64>(),
+            t in 0.05f32..0.95,
+            distance in -1e-2f32..1e-2,
+            grid in prop::sample::select(vec![1f32, 5., 20.]),
+        ) {
+            let pts = random_polygon(method, n, seed);
+            let (a, b) = (pts[0], pts[1]);
+            let normal = (b - a).normalized().rot90();
+            let mut split = pts.clone();
+            split.insert(1, a + (b - a) * t + normal * distance);
+            if validate_polygon(&split).is_ok() {
+                check(&split)?;
+            }
+            let snapped: Vec<Pos2> = pts.iter().map(|p| (*p / grid).round() * grid).collect();
+            if validate_polygon(&snapped).is_ok() {
+                check(&snapped)?;
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_triangulation() {
+        let pts = find_preset("Two split vertices").unwrap().polygon.exterior;
+        let poly = polygon_triangulation(&pts, None);
+        let report = verify_triangulation(&pts, &poly);
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.triangles, pts.len() - 2);
+        assert!((report.triangle_area - report.polygon_area).abs() < 1e-6);
+
+        // Monotone pieces aren't triangles and there are fewer diagonals.
+        let partition = monotone_partition_polygon(&pts);
+        let report = verify_triangulation(&pts, &partition);
+        assert!(!report.is_valid());
+        assert!(report
+            .violations
+            .contains(&TriangulationViolation::TriangleCount {
+                expected: pts.len() - 2,
+                found: partition.faces.len(),
+            }));
+        assert!(report
+            .violations
+            .iter()
+            .any(|x| matches!(x, TriangulationViolation::NotTriangle { .. })));
+        assert!(report.to_string().contains("violations:"));
+
+        // A diagonal between the wrong vertices crosses the boundary
+        // and isn't shared by any triangle.
+        let mut broken = polygon_triangulation(&pts, None);
+        broken.diagonals[0] = (0, 4);
+        let report = verify_triangulation(&pts, &broken);
+        assert!(report
+            .violations
+            .contains(&TriangulationViolation::DiagonalNotShared {
+                diagonal: 0,
+                faces: 0
+            }));
+        assert!(report
+            .violations
+            .iter()
+            .any(|x| matches!(x, TriangulationViolation::DiagonalCrossesBoundary { .. })));
+
+        let shrunk = shrink_polygon(&pts, |x| x.len() >= 5);
+        assert_eq!(shrunk.len(), 5);
+        assert_eq!(validate_polygon(&shrunk), Ok(()));
+    }
+}
@@ -1,81 +1,268 @@
-use crate::monotone_y_partition::Face;
-use egui::Color32;
+use crate::monotone_y_partition::{Face, PartitionPolygon};
 use log::debug;
+use std::collections::HashSet;
+use std::fmt::Display;
 use std::{cell::RefCell, rc::Rc};
 
-fn coloring_triangle(face: Rc<RefCell<Face>>, colors: &mut [Color32]) {
-    let mut red_avaiable = true;
-    let mut green_avaiable = true;
-    let mut blue_avaiable = true;
-    let mut process_stack: Vec<usize> = Vec::new();
-    for i in 0..3 {
-        let idx = face.as_ref().borrow().vertices[i];
-        let vertex_color = colors[idx];
-        if let Color32::BLACK = vertex_color {
-            process_stack.push(idx);
-        } else if let Color32::RED = vertex_color {
-            red_avaiable = false;
-        } else if let Color32::GREEN = vertex_color {
-            green_avaiable = false;
-        } else if let Color32::BLUE = vertex_color {
-            blue_avaiable = false;
+/// Number of color classes used by the 3-coloring.
+pub const COLOR_CLASSES: u8 = 3;
+
+/// Color class of a vertex which hasn't been colored yet.
+pub const UNCOLORED: u8 = u8::MAX;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColoringError {
+    /// The polygon has no triangulated faces to start from.
+    NoFaces,
+    /// The start face index is out of range.
+    FaceOutOfRange(usize),
+    /// A pre-assigned vertex index is out of range.
+    VertexOutOfRange(usize),
+    /// A pre-assigned color class is not in 0..COLOR_CLASSES.
+    InvalidColorClass { vertex: usize, class: u8 },
+    /// Pre-assigned colors can't be satisfied by any 3-coloring
+    /// of the triangulation.
+    ConflictingPreset { vertex: usize, class: u8 },
+    /// A face isn't a triangle with three distinctly colored vertices.
+    InvalidTriangle { vertices: Vec<usize> },
+    /// Number of color classes doesn't match the number of vertices.
+    ClassCountMismatch { classes: usize, vertices: usize },
+}
+
+impl Display for ColoringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColoringError::NoFaces => write!(f, "polygon is not triangulated"),
+            ColoringError::FaceOutOfRange(idx) => write!(f, "face{} doesn't exist", idx),
+            ColoringError::VertexOutOfRange(idx) => write!(f, "vertex{} doesn't exist", idx),
+            ColoringError::InvalidColorClass { vertex, class } => {
+                write!(f, "vertex{} has invalid color class {}", vertex, class)
+            }
+            ColoringError::ConflictingPreset { vertex, class } => {
+                write!(f, "vertex{} can't have color class {}", vertex, class)
+            }
+            ColoringError::InvalidTriangle { vertices } => {
+                write!(f, "face{:?} is not a properly colored triangle", vertices)
+            }
+            ColoringError::ClassCountMismatch { classes, vertices } => {
+                write!(f, "{} color classes for {} vertices", classes, vertices)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ColoringError {}
+
+/// Assign the color classes that are still available in a triangle
+/// to its uncolored vertices.
+fn coloring_triangle(face: &Face, classes: &mut [u8]) {
+    let mut available = [true; COLOR_CLASSES as usize];
+    for idx in face.vertices.iter() {
+        if classes[*idx] != UNCOLORED {
+            available[classes[*idx] as usize] = false;
+        }
+    }
+
+    for idx in face.vertices.iter() {
+        if classes[*idx] != UNCOLORED {
+            continue;
+        }
+        if let Some(class) = available.iter().position(|x| *x) {
+            classes[*idx] = class as u8;
+            available[class] = false;
+        }
+    }
+}
+
+/// Traverse triangle faces starting from `start_face` in a DFS manner,
+/// faces are linked by the twins of their bounding diagonals.\
+/// An explicit stack is used, so large polygons won't overflow the call stack.
+pub fn traverse_faces(start_face: &Rc<RefCell<Face>>, classes: &mut [u8]) {
+    let mut visited: HashSet<*const RefCell<Face>> = HashSet::new();
+    let mut face_stack: Vec<Rc<RefCell<Face>>> = vec![start_face.clone()];
+    visited.insert(Rc::as_ptr(start_face));
+
+    while let Some(face) = face_stack.pop() {
+        let face = face.as_ref().borrow();
+        debug!("on parition{:?}", face.vertices);
+        coloring_triangle(&face, classes);
+
+        for half_diag in face.bounding_diags.iter() {
+            let twin = half_diag.as_ref().borrow().twin.clone().unwrap();
+            let neighbor = twin.as_ref().borrow().bounding_face.clone();
+            if let Some(neighbor) = neighbor {
+                if visited.insert(Rc::as_ptr(&neighbor)) {
+                    face_stack.push(neighbor);
+                }
+            }
+        }
+    }
+}
+
+/// Permute color classes so that pre-assigned vertices get their colors.\
+/// A triangulated polygon's 3-coloring is unique up to a permutation of
+/// color classes, so the presets either fit a permutation or conflict.
+fn apply_presets(classes: &mut [u8], presets: &[(usize, u8)]) -> Result<(), ColoringError> {
+    let mut permutation = [UNCOLORED; COLOR_CLASSES as usize];
+    for &(vertex, class) in presets.iter() {
+        if vertex >= classes.len() {
+            return Err(ColoringError::VertexOutOfRange(vertex));
+        }
+        if class >= COLOR_CLASSES {
+            return Err(ColoringError::InvalidColorClass { vertex, class });
+        }
+        let from = classes[vertex] as usize;
+        let taken_by_other = permutation
+            .iter()
+            .enumerate()
+            .any(|(i, x)| i != from && *x == class);
+        if (permutation[from] != UNCOLORED && permutation[from] != class) || taken_by_other {
+            return Err(ColoringError::ConflictingPreset { vertex, class });
         }
+        permutation[from] = class;
     }
 
-    for idx in process_stack.iter() {
-        if red_avaiable {
-            colors[*idx] = Color32::RED;
-            red_avaiable = false;
-        } else if green_avaiable {
-            colors[*idx] = Color32::GREEN;
-            green_avaiable = false;
-        } else if blue_avaiable {
-            colors[*idx] = Color32::BLUE;
-            blue_avaiable = false;
+    // Hand out the classes that no preset asked for.
+    let mut unused = (0..COLOR_CLASSES)
+        .filter(|x| !permutation.contains(x))
+        .collect::<Vec<u8>>()
+        .into_iter();
+    for class in permutation.iter_mut() {
+        if *class == UNCOLORED {
+            *class = unused.next().unwrap();
         }
     }
+
+    for class in classes.iter_mut() {
+        *class = permutation[*class as usize];
+    }
+    Ok(())
 }
 
-pub fn dfs(
-    start_face: Rc<RefCell<Face>>,
-    half_diag_check_table: &mut Vec<(usize, usize)>,
-    colors: &mut Vec<Color32>,
-) {
-    let face_clone1 = start_face.clone();
-    let mut unchecked_diags = start_face.as_ref().borrow().bounding_diags.len();
-
-    let face_clone2 = start_face.clone();
-    debug!("on parition{:?}", face_clone2.as_ref().borrow().vertices);
-    coloring_triangle(start_face.clone(), colors);
-
-    // Traverse triangulation partition recursively
-    loop {
-        if unchecked_diags == 0 {
-            break;
+/// Check that every face is a triangle with three distinct color classes.
+pub fn verify_coloring(poly: &PartitionPolygon, classes: &[u8]) -> Result<(), ColoringError> {
+    if classes.len() != poly.vertices.len() {
+        return Err(ColoringError::ClassCountMismatch {
+            classes: classes.len(),
+            vertices: poly.vertices.len(),
+        });
+    }
+    for face in poly.faces.iter() {
+        let face = face.as_ref().borrow();
+        let colors = face
+            .vertices
+            .iter()
+            .map(|idx| classes[*idx])
+            .collect::<Vec<u8>>();
+        let valid = colors.len() == 3
+            && colors.iter().all(|x| *x < COLOR_CLASSES)
+            && colors[0] != colors[1]
+            && colors[1] != colors[2]
+            && colors[0] != colors[2];
+        if !valid {
+            return Err(ColoringError::InvalidTriangle {
+                vertices: face.vertices.clone(),
+            });
         }
+    }
+    Ok(())
+}
+
+/// 3-color the vertices of a triangulated polygon.
+///
+/// Returns the color class (0, 1 or 2) of every vertex. The coloring starts
+/// from the triangle `start_face` in `poly.faces`, vertices in `presets`
+/// are guaranteed to get the given color class.\
+/// Runs in linear time w.r.t. the number of vertices.
+pub fn three_coloring(
+    poly: &PartitionPolygon,
+    start_face: usize,
+    presets: &[(usize, u8)],
+) -> Result<Vec<u8>, ColoringError> {
+    if poly.faces.is_empty() {
+        return Err(ColoringError::NoFaces);
+    }
+    if start_face >= poly.faces.len() {
+        return Err(ColoringError::FaceOutOfRange(start_face));
+    }
 
-        let half_diag =
-            face_clone1.as_ref().borrow_mut().bounding_diags[unchecked_diags - 1].clone();
-        let twin = half_diag.as_ref().borrow().twin.clone().unwrap().clone();
-        half_diag_check_table.push((
-            half_diag.as_ref().borrow().origin,
-            half_diag.as_ref().borrow().end,
-        ));
-
-        if !half_diag_check_table
-            .contains(&(twin.as_ref().borrow().origin, twin.as_ref().borrow().end))
-        {
-            dfs(
-                twin.as_ref()
-                    .borrow()
-                    .bounding_face
-                    .clone()
-                    .unwrap()
-                    .clone(),
-                half_diag_check_table,
-                colors,
+    let mut classes = vec![UNCOLORED; poly.vertices.len()];
+    traverse_faces(&poly.faces[start_face], &mut classes);
+    verify_coloring(poly, &classes)?;
+    apply_presets(&mut classes, presets)?;
+    Ok(classes)
+}
+
+/// Guard positions of the art gallery problem, i.e. vertices of the smallest
+/// color class of a 3-coloring.\
+/// Every triangle has a vertex of each class, so these floor(n/3) vertices
+/// see the whole polygon.
+pub fn art_gallery_guards(classes: &[u8]) -> Vec<usize> {
+    let mut counts = [0usize; COLOR_CLASSES as usize];
+    for class in classes.iter().filter(|x| **x < COLOR_CLASSES) {
+        counts[*class as usize] += 1;
+    }
+    let Some(smallest) = (0..COLOR_CLASSES).min_by_key(|x| counts[*x as usize]) else {
+        return Vec::new();
+    };
+    (0..classes.len())
+        .filter(|idx| classes[*idx] == smallest)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{art_gallery_guards, three_coloring, verify_coloring, ColoringError};
+    use crate::monotone_triangulation::polygon_triangulation;
+    use crate::monotone_y_partition::PartitionPolygon;
+    use egui::Pos2;
+
+    fn triangulated_poly() -> PartitionPolygon {
+        let pts = vec![
+            Pos2::new(157., 29.),
+            Pos2::new(308., 173.),
+            Pos2::new(481., 49.),
+            Pos2::new(624., 180.),
+            Pos2::new(500., 349.),
+            Pos2::new(378., 286.),
+            Pos2::new(185., 333.),
+        ];
+        polygon_triangulation(&pts, None)
+    }
+
+    #[test]
+    fn test_three_coloring() {
+        let poly = triangulated_poly();
+        for start_face in 0..poly.faces.len() {
+            let classes = three_coloring(&poly, start_face, &[]).unwrap();
+            assert!(verify_coloring(&poly, &classes).is_ok());
+            assert_eq!(
+                verify_coloring(&poly, &classes[1..]),
+                Err(ColoringError::ClassCountMismatch {
+                    classes: classes.len() - 1,
+                    vertices: classes.len()
+                })
             );
+            let guards = art_gallery_guards(&classes);
+            assert!(!guards.is_empty() && guards.len() <= classes.len() / 3);
         }
-        unchecked_diags -= 1;
+    }
+
+    #[test]
+    fn test_three_coloring_presets() {
+        let poly = triangulated_poly();
+        let classes = three_coloring(&poly, 0, &[(0, 2), (1, 0)]).unwrap();
+        assert_eq!((classes[0], classes[1]), (2, 0));
+        assert!(verify_coloring(&poly, &classes).is_ok());
+
+        // Vertex 0 and 1 share an edge, they can't have the same color.
+        let conflict = three_coloring(&poly, 0, &[(0, 1), (1, 1)]);
+        assert_eq!(
+            conflict,
+            Err(ColoringError::ConflictingPreset {
+                vertex: 1,
+                class: 1
+            })
+        );
     }
 }
@@ -0,0 +1,91 @@
+//! Earcut fixture suite.
+//!
+//! Runs fixtures in the layout of earcut's `test/` directory: every entry of
+//! `expected.json` names a fixture in `fixtures/<name>.json`, with its
+//! expected number of triangles and the maximal deviation of their area
+//! (0 if missing). Like earcut, a count of 0 only checks the deviation, so
+//! degenerate polygons may be rejected with an error.
+//!
+//! `tests/earcut/` is used by default, run another suite (e.g. earcut's own)
+//! with
+//!
+//! ```shell
+//! EARCUT_TEST_DIR=../earcut/test cargo test --test earcut
+//! ```
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use triangulate_rs::parse_earcut_fixture;
+
+#[derive(Deserialize)]
+struct Expected {
+    triangles: BTreeMap<String, usize>,
+    #[serde(default)]
+    errors: BTreeMap<String, f64>,
+}
+
+fn test_dir() -> PathBuf {
+    std::env::var_os("EARCUT_TEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/earcut"))
+}
+
+#[test]
+fn earcut_fixtures() {
+    let dir = test_dir();
+    let text = fs::read_to_string(dir.join("expected.json")).unwrap();
+    let expected: Expected = serde_json::from_str(&text).unwrap();
+    assert!(!expected.triangles.is_empty(), "no fixtures in {:?}", dir);
+
+    let mut failures = Vec::new();
+    for (name, triangle_count) in expected.triangles.iter() {
+        let path = dir.join("fixtures").join(format!("{}.json", name));
+        let data = match parse_earcut_fixture(&fs::read_to_string(&path).unwrap()) {
+            Ok(data) => data,
+            Err(err) => {
+                failures.push(format!("{}: parse error: {}", name, err));
+                continue;
+            }
+        };
+        let triangles = match data.triangulate() {
+            Ok(triangles) => triangles,
+            Err(err) if *triangle_count > 0 => {
+                failures.push(format!("{}: {}", name, err));
+                continue;
+            }
+            Err(_) => Vec::new(),
+        };
+
+        if *triangle_count > 0 && triangles.len() / 3 != *triangle_count {
+            failures.push(format!(
+                "{}: {} triangles, expected {}",
+                name,
+                triangles.len() / 3,
+                triangle_count
+            ));
+        }
+        let max_deviation = expected.errors.get(name).copied().unwrap_or(0.);
+        let deviation = match data.deviation(&triangles) {
+            Ok(deviation) => deviation,
+            Err(err) => {
+                failures.push(format!("{}: {}", name, err));
+                continue;
+            }
+        };
+        if deviation > max_deviation {
+            failures.push(format!(
+                "{}: deviation {}, expected at most {}",
+                name, deviation, max_deviation
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} earcut fixtures failed\n\n{}",
+        failures.len(),
+        expected.triangles.len(),
+        failures.join("\n")
+    );
+}
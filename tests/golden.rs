@@ -0,0 +1,188 @@
+//! Golden regression tests.
+//!
+//! Every polygon file in `tests/golden/` is triangulated and 3-colored, and
+//! the result is compared with the snapshot next to it (same name, `.out`
+//! extension). Changes of the monotone partition show up as a diff, so they
+//! must be deliberate. After checking a diff, bless the new outputs with
+//!
+//! ```shell
+//! BLESS=1 cargo test --test golden
+//! ```
+//!
+//! A new input file needs a blessed snapshot before the test passes.
+
+use std::fmt::Write;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use triangulate_rs::{
+    bridge_holes, monotone_partition_polygon, parse_polygons, polygon_triangulation,
+    three_coloring, validate_polygon_with_holes, verify_triangulation, vertex_types, InputFormat,
+    PolygonWithHoles, VertexType,
+};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn type_label(vertex_type: &VertexType) -> &'static str {
+    match vertex_type {
+        VertexType::StartVertex => "start",
+        VertexType::EndVertex => "end",
+        VertexType::RegularVetex => "regular",
+        VertexType::SplitVertex => "split",
+        VertexType::MergeVertex => "merge",
+    }
+}
+
+fn join<T: ToString>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Snapshot of one polygon, holes are bridged to the exterior and the
+/// bridged ring is snapshotted, indices are those of its vertices.
+fn snapshot_polygon(polygon: &PolygonWithHoles, out: &mut String) {
+    writeln!(out, "vertices: {}", polygon.exterior.len()).unwrap();
+    if let Err(err) = validate_polygon_with_holes(&polygon.exterior, &polygon.holes) {
+        writeln!(out, "error: {}", err).unwrap();
+        return;
+    }
+    let bridged = bridge_holes(&polygon.exterior, &polygon.holes);
+    if !polygon.holes.is_empty() {
+        writeln!(out, "holes: {}", polygon.holes.len()).unwrap();
+        writeln!(out, "bridged: {}", join(bridged.indices.iter())).unwrap();
+    }
+    let vertices = bridged.vertices.as_slice();
+    writeln!(
+        out,
+        "types: {}",
+        join(vertex_types(vertices).iter().map(type_label))
+    )
+    .unwrap();
+
+    // A panic is part of the snapshot, keep it off stderr.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let monotone = monotone_partition_polygon(vertices);
+        let poly = polygon_triangulation(vertices, None);
+        (monotone.diagonals, poly)
+    }));
+    panic::set_hook(hook);
+    let (monotone_diagonals, poly) = match result {
+        Ok(res) => res,
+        Err(err) => {
+            let message = err
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| err.downcast_ref::<&str>().map(|x| x.to_string()))
+                .unwrap_or_default();
+            writeln!(out, "panic: {}", message.lines().next().unwrap_or("")).unwrap();
+            return;
+        }
+    };
+    let report = verify_triangulation(vertices, &poly);
+    assert!(report.is_valid(), "invalid triangulation: {}", report);
+    let diagonals =
+        |diagonals: &[(usize, usize)]| join(diagonals.iter().map(|(a, b)| format!("{}-{}", a, b)));
+    writeln!(
+        out,
+        "monotone diagonals: {}",
+        diagonals(&monotone_diagonals)
+    )
+    .unwrap();
+    writeln!(out, "diagonals: {}", diagonals(&poly.diagonals)).unwrap();
+    writeln!(out, "triangles: {}", poly.faces.len()).unwrap();
+    for face in poly.faces.iter() {
+        writeln!(out, "  {}", join(face.borrow().vertices.iter())).unwrap();
+    }
+    match three_coloring(&poly, 0, &[]) {
+        Ok(classes) => writeln!(out, "coloring: {}", join(classes)).unwrap(),
+        Err(err) => writeln!(out, "coloring error: {}", err).unwrap(),
+    }
+}
+
+fn snapshot(path: &Path, format: InputFormat) -> String {
+    let text = fs::read_to_string(path).unwrap();
+    let mut out = String::new();
+    match parse_polygons(&text, format) {
+        Ok(polygons) => {
+            for (idx, polygon) in polygons.iter().enumerate() {
+                writeln!(out, "# polygon {}", idx).unwrap();
+                snapshot_polygon(polygon, &mut out);
+            }
+        }
+        Err(err) => writeln!(out, "parse error: {}", err).unwrap(),
+    }
+    out
+}
+
+/// Line diff of expected and actual output, based on their longest common
+/// subsequence, removed lines start with `-` and added ones with `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let (a, b): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j, mut ret) = (0, 0, String::new());
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            writeln!(ret, "  {}", a[i]).unwrap();
+            (i, j) = (i + 1, j + 1);
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            writeln!(ret, "- {}", a[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(ret, "+ {}", b[j]).unwrap();
+            j += 1;
+        }
+    }
+    ret
+}
+
+#[test]
+fn golden() {
+    let bless = std::env::var_os("BLESS").is_some();
+    let mut inputs: Vec<(PathBuf, InputFormat)> = fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter_map(|path| InputFormat::from_path(&path).map(|format| (path, format)))
+        .collect();
+    inputs.sort_by(|a, b| a.0.cmp(&b.0));
+    assert!(!inputs.is_empty(), "no inputs in {:?}", corpus_dir());
+
+    let mut failures = Vec::new();
+    for (path, format) in inputs.iter() {
+        let actual = snapshot(path, *format);
+        let expected_path = path.with_extension("out");
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy();
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => (),
+            Ok(expected) => failures.push(format!("{}:\n{}", name, diff(&expected, &actual))),
+            Err(_) => failures.push(format!("{}: no snapshot, new output:\n{}", name, actual)),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} golden outputs differ, run `BLESS=1 cargo test --test golden` \
+         if the changes are intended\n\n{}",
+        failures.len(),
+        inputs.len(),
+        failures.join("\n")
+    );
+}